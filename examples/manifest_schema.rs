@@ -0,0 +1,6 @@
+//! Prints the manifest JSON Schema: `cargo run --example manifest_schema --features schema > setup.schema.json`
+
+fn main() {
+    let schema = linux_setup_ur::manifest::manifest_schema();
+    println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+}
@@ -0,0 +1,81 @@
+use std::path::Path;
+use std::{fs, io};
+
+use super::Artifact;
+use crate::utils::Status;
+use crate::CommandStruct;
+
+/// Index written next to the bundled artifacts, listing what the bundle contains.
+pub const BUNDLE_INDEX: &str = "bundle.json";
+
+/// Directory of a bundle that holds the downloaded packages.
+pub const BUNDLE_PACKAGES: &str = "packages";
+
+/// Downloads every artifact into `dir` so a later run can provision an air-gapped machine.
+/// Entries may share an artifact, but two different sources under one name would
+/// overwrite each other's file, so that is an error.
+pub fn build_bundle<'a>(
+    artifacts: impl IntoIterator<Item = &'a Artifact>,
+    dir: &Path,
+) -> io::Result<Vec<Artifact>> {
+    fs::create_dir_all(dir)?;
+    let mut bundled: Vec<Artifact> = Vec::new();
+
+    for artifact in artifacts {
+        if let Some(existing) = bundled.iter().find(|a| a.name() == artifact.name()) {
+            if existing.lock_key() != artifact.lock_key() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "artifact `{}` is declared with two different sources",
+                        artifact.name()
+                    ),
+                ));
+            }
+            continue;
+        }
+
+        Status::Running.print_message(&format!("Bundling {}", artifact.name()));
+        artifact.fetch(dir)?;
+        bundled.push(artifact.clone());
+    }
+
+    fs::write(
+        dir.join(BUNDLE_INDEX),
+        serde_json::to_string_pretty(&bundled).map_err(io::Error::other)?,
+    )?;
+    Ok(bundled)
+}
+
+/// Downloads the packages of every `use_package_manager` command that runs on this
+/// machine into the bundle's `packages` directory. Package managers that cannot install
+/// from there are reported and left to a mirror on the target machine.
+pub fn bundle_packages<'a>(
+    commands: impl IntoIterator<Item = &'a CommandStruct>,
+    dir: &Path,
+) -> io::Result<()> {
+    let packages = dir.join(BUNDLE_PACKAGES);
+    // apt refuses an archive directory without `partial`.
+    fs::create_dir_all(packages.join("partial"))?;
+
+    let wanted = commands
+        .into_iter()
+        .filter(|command| command.uses_package_manager() && !command.should_skip());
+    for command in wanted {
+        let Some(mut download) = command.download_packages(&packages) else {
+            Status::Warning.print_message(&format!(
+                "Not bundling `{}`: its package manager cannot install offline",
+                command.script()
+            ));
+            continue;
+        };
+        Status::Running.print_message(&format!("Bundling packages {}", command.script()));
+        if !download.status()?.success() {
+            return Err(io::Error::other(format!(
+                "downloading `{}` failed",
+                command.script()
+            )));
+        }
+    }
+    Ok(())
+}
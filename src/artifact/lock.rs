@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+
+/// The exact source an artifact resolved to on the first run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockedArtifact {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+}
+
+/// Resolved artifact sources by `Artifact::lock_key`, e.g. `setup.lock` next to the manifest.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub artifacts: BTreeMap<String, LockedArtifact>,
+}
+
+impl Lockfile {
+    /// A missing lockfile is an empty one.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(io::Error::other),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Lockfile::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, content + "\n")
+    }
+}
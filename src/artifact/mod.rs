@@ -0,0 +1,361 @@
+mod bundle;
+mod lock;
+
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::{env, fs, io, process};
+
+use serde::{Deserialize, Serialize};
+
+pub use bundle::{build_bundle, bundle_packages, BUNDLE_INDEX, BUNDLE_PACKAGES};
+pub use lock::{LockedArtifact, Lockfile};
+
+use crate::utils::{cache_home, sha256_hex};
+
+/// Something an entry downloads: a file behind a URL or a git repository.
+/// Commands read its location from `$LSU_ARTIFACT_<NAME>`, which points at the local file
+/// (or git bundle) when running from an offline bundle.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Artifact {
+    name: String,
+    url: Option<String>,
+    git: Option<String>,
+    /// Expected SHA-256 of a `url` download, usually filled in from the lockfile.
+    sha256: Option<String>,
+    /// Commit a `git` artifact is pinned to, exported as `$LSU_ARTIFACT_<NAME>_REV`.
+    rev: Option<String>,
+}
+
+fn capture(command: &mut process::Command) -> io::Result<String> {
+    let output = command.output()?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+fn run(command: &mut process::Command) -> io::Result<()> {
+    capture(command).map(|_| ())
+}
+
+/// Artifact names become file names inside the bundle and download cache, so they must
+/// not be able to point anywhere else or collide with the hidden `.part` and `.git` files.
+pub fn is_valid_artifact_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains('/') && !name.contains("..")
+}
+
+/// The per-user download cache, `$XDG_CACHE_HOME/linux_setup_ur`. Only its owner may
+/// enter it, so no one else can plant a file that commands will later run.
+pub fn cache_dir() -> io::Result<PathBuf> {
+    let dir = cache_home()
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "no cache directory: HOME is not set",
+            )
+        })?
+        .join("linux_setup_ur");
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)?;
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+    Ok(dir)
+}
+
+fn sha256_file(path: &Path) -> io::Result<String> {
+    Ok(sha256_hex(&fs::read(path)?))
+}
+
+impl Artifact {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn env_var(&self) -> String {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("LSU_ARTIFACT_{}", name)
+    }
+
+    /// Identifies the artifact in the lockfile: its name and floating source, so pointing
+    /// the artifact somewhere else resolves it again instead of keeping the old pin.
+    pub fn lock_key(&self) -> String {
+        let source = self
+            .url
+            .as_deref()
+            .or(self.git.as_deref())
+            .unwrap_or_default();
+        format!("{} {}", self.name, source)
+    }
+
+    /// File name used for this artifact inside a bundle or download cache.
+    pub fn file_name(&self) -> String {
+        if self.git.is_some() {
+            format!("{}.bundle", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// Downloads the artifact into `dir`, turning git repositories into a single `git bundle`.
+    pub fn fetch(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let target = dir.join(self.file_name());
+
+        match (&self.url, &self.git) {
+            (Some(url), _) => {
+                // Downloads land under a temporary name, so an interrupted one is never
+                // mistaken for a cached copy.
+                let partial = dir.join(format!(".{}.part", self.file_name()));
+                run(process::Command::new("curl")
+                    .args(["-fsSL", "-o"])
+                    .arg(&partial)
+                    .arg(url))?;
+                if let Some(expected) = &self.sha256 {
+                    let actual = sha256_file(&partial)?;
+                    if !actual.eq_ignore_ascii_case(expected) {
+                        fs::remove_file(&partial)?;
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!(
+                                "artifact `{}` checksum mismatch: expected {}, got {}",
+                                self.name, expected, actual
+                            ),
+                        ));
+                    }
+                }
+                fs::rename(&partial, &target)?;
+            }
+            (None, Some(repository)) => {
+                let mirror = dir.join(format!(".{}.git", self.name));
+                if mirror.exists() {
+                    fs::remove_dir_all(&mirror)?;
+                }
+                run(process::Command::new("git")
+                    .args(["clone", "--mirror", "--quiet", repository])
+                    .arg(&mirror))?;
+                let bundled = run(process::Command::new("git")
+                    .arg("-C")
+                    .arg(&mirror)
+                    .args(["bundle", "create"])
+                    .arg(&target)
+                    .arg("--all"));
+                fs::remove_dir_all(&mirror)?;
+                bundled?;
+            }
+            (None, None) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("artifact `{}` needs a `url` or `git` source", self.name),
+                ))
+            }
+        }
+
+        Ok(target)
+    }
+
+    /// Downloads a `url` artifact into its own directory below `cache_dir` unless an
+    /// earlier run already did. A cached copy is only reused while it matches `sha256`;
+    /// without a checksum there is nothing to trust it by, so it is downloaded again.
+    fn fetch_cached(&self, cache_dir: &Path) -> io::Result<PathBuf> {
+        let dir = cache_dir.join(&sha256_hex(self.lock_key().as_bytes())[..16]);
+        let path = dir.join(self.file_name());
+        let cached = self.sha256.as_ref().is_some_and(|expected| {
+            sha256_file(&path).is_ok_and(|actual| actual.eq_ignore_ascii_case(expected))
+        });
+        if cached {
+            return Ok(path);
+        }
+        self.fetch(&dir)
+    }
+
+    /// Resolves where commands should read the artifact from and exports it as `$LSU_ARTIFACT_<NAME>`.
+    /// From a bundle this is the bundled file; online, URLs are downloaded into `cache_dir`
+    /// once and git repositories are used directly.
+    pub fn prepare(&self, bundle_dir: Option<&Path>, cache_dir: &Path) -> io::Result<String> {
+        let location = match (bundle_dir, &self.git) {
+            (Some(bundle), _) => {
+                let path = bundle.join(self.file_name());
+                if !path.exists() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("artifact `{}` is missing from the bundle", self.name),
+                    ));
+                }
+                path.display().to_string()
+            }
+            (None, Some(repository)) => repository.clone(),
+            (None, None) => self.fetch_cached(cache_dir)?.display().to_string(),
+        };
+
+        env::set_var(self.env_var(), &location);
+        if let Some(rev) = &self.rev {
+            env::set_var(format!("{}_REV", self.env_var()), rev);
+        }
+        Ok(location)
+    }
+
+    /// Resolves floating sources to exact ones: the final URL after redirects
+    /// (e.g. `releases/latest/download/...`) with its checksum, or the commit at `HEAD`.
+    pub fn resolve(&self, cache_dir: &Path) -> io::Result<LockedArtifact> {
+        match (&self.url, &self.git) {
+            (Some(url), _) => {
+                let resolved = capture(
+                    process::Command::new("curl")
+                        .args(["-fsSLI", "-o", "/dev/null", "-w", "%{url_effective}"])
+                        .arg(url),
+                )?;
+                let pinned = Artifact {
+                    url: Some(resolved.clone()),
+                    sha256: None,
+                    ..self.clone()
+                };
+                let sha256 = sha256_file(&pinned.fetch(cache_dir)?)?;
+                Ok(LockedArtifact {
+                    url: Some(resolved),
+                    sha256: Some(sha256),
+                    rev: None,
+                })
+            }
+            (None, Some(repository)) => {
+                let head =
+                    capture(process::Command::new("git").args(["ls-remote", repository, "HEAD"]))?;
+                let rev = head
+                    .split_whitespace()
+                    .next()
+                    .ok_or_else(|| io::Error::other(format!("{} has no HEAD", repository)))?;
+                Ok(LockedArtifact {
+                    url: None,
+                    sha256: None,
+                    rev: Some(rev.to_string()),
+                })
+            }
+            (None, None) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("artifact `{}` needs a `url` or `git` source", self.name),
+            )),
+        }
+    }
+
+    /// Replaces the floating source with what the lockfile recorded.
+    pub fn pin(&mut self, locked: &LockedArtifact) {
+        if self.url.is_some() {
+            if let Some(url) = &locked.url {
+                self.url = Some(url.clone());
+            }
+            self.sha256 = locked.sha256.clone();
+        }
+        if self.git.is_some() {
+            self.rev = locked.rev.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_var_name() {
+        let artifact = Artifact {
+            name: "neovim-nightly.tar.gz".to_string(),
+            url: Some("https://example.com/nvim.tar.gz".to_string()),
+            git: None,
+            sha256: None,
+            rev: None,
+        };
+        assert_eq!(artifact.env_var(), "LSU_ARTIFACT_NEOVIM_NIGHTLY_TAR_GZ");
+        assert_eq!(artifact.file_name(), "neovim-nightly.tar.gz");
+        assert_eq!(
+            artifact.lock_key(),
+            "neovim-nightly.tar.gz https://example.com/nvim.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        let path = std::env::temp_dir().join(format!("lsu-sha256-{}", std::process::id()));
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_cached_download_is_reused_while_it_matches() {
+        let cache = std::env::temp_dir().join(format!("lsu-artifacts-{}", std::process::id()));
+        let artifact = Artifact {
+            name: "tool".to_string(),
+            url: Some("https://lsu.invalid/tool".to_string()),
+            git: None,
+            sha256: Some(
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string(),
+            ),
+            rev: None,
+        };
+        let dir = cache.join(&sha256_hex(artifact.lock_key().as_bytes())[..16]);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("tool"), "abc").unwrap();
+        assert_eq!(artifact.fetch_cached(&cache).unwrap(), dir.join("tool"));
+
+        fs::write(dir.join("tool"), "tampered").unwrap();
+        assert!(artifact.fetch_cached(&cache).is_err());
+
+        let unchecked = Artifact {
+            sha256: None,
+            ..artifact
+        };
+        fs::write(dir.join("tool"), "abc").unwrap();
+        assert!(unchecked.fetch_cached(&cache).is_err());
+        fs::remove_dir_all(cache).unwrap();
+    }
+
+    #[test]
+    fn test_pin_from_lockfile() {
+        let mut release: Artifact = serde_json::from_value(serde_json::json!({
+            "name": "nvim.tar.gz",
+            "url": "https://github.com/neovim/neovim/releases/latest/download/nvim.tar.gz"
+        }))
+        .unwrap();
+        release.pin(&LockedArtifact {
+            url: Some(
+                "https://github.com/neovim/neovim/releases/download/v0.10.0/nvim.tar.gz"
+                    .to_string(),
+            ),
+            sha256: Some("abc123".to_string()),
+            rev: Some("ignored".to_string()),
+        });
+        assert!(release.url.as_deref().unwrap().contains("v0.10.0"));
+        assert_eq!(release.sha256.as_deref(), Some("abc123"));
+        assert_eq!(release.rev, None);
+
+        let mut dotfiles: Artifact = serde_json::from_value(serde_json::json!({
+            "name": "dotfiles",
+            "git": "https://github.com/example/dotfiles"
+        }))
+        .unwrap();
+        dotfiles.pin(&LockedArtifact {
+            url: None,
+            sha256: None,
+            rev: Some("0123abcd".to_string()),
+        });
+        assert_eq!(dotfiles.rev.as_deref(), Some("0123abcd"));
+        assert_eq!(dotfiles.url, None);
+    }
+}
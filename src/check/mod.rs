@@ -0,0 +1,90 @@
+mod provider;
+mod version;
+
+use std::process;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub use provider::{check_provider, register_check_provider, string_param};
+pub use version::{installed_version, parse_version, Version};
+
+/// Decides whether a command is already satisfied and can be skipped.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum Check {
+    /// A shell script; satisfied when it succeeds and prints something.
+    Shell(String),
+    /// Handled by the `CheckProvider` registered for `type`, with the other keys as parameters.
+    Provider {
+        #[serde(rename = "type")]
+        kind: String,
+        #[serde(flatten)]
+        params: Map<String, Value>,
+    },
+    /// Satisfied when `<binary> --version` reports at least `min_version`.
+    MinVersion { binary: String, min_version: String },
+}
+
+impl Check {
+    pub fn script(&self) -> Option<&str> {
+        match self {
+            Check::Shell(script) => Some(script),
+            _ => None,
+        }
+    }
+
+    /// The provider name and parameters; `MinVersion` is the `min_version` provider.
+    fn provider_call(&self) -> Option<(String, Map<String, Value>)> {
+        match self {
+            Check::Shell(_) => None,
+            Check::Provider { kind, params } => Some((kind.clone(), params.clone())),
+            Check::MinVersion {
+                binary,
+                min_version,
+            } => {
+                let params = [("binary", binary), ("min_version", min_version)]
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), Value::from(value.as_str())))
+                    .collect();
+                Some(("min_version".to_string(), params))
+            }
+        }
+    }
+
+    /// Whether the check holds. Shell checks run with plain `sh`; commands run their own
+    /// checks with their shell and user instead.
+    pub fn is_satisfied(&self) -> bool {
+        if let Check::Shell(script) = self {
+            return process::Command::new("sh")
+                .arg("-c")
+                .arg(script)
+                .output()
+                .is_ok_and(|output| output.status.success() && !output.stdout.is_empty());
+        }
+        match self.provider_call() {
+            Some((kind, params)) => {
+                check_provider(&kind).is_some_and(|provider| provider.is_satisfied(&params))
+            }
+            None => false,
+        }
+    }
+
+    /// Rejects unknown check types and parameters their provider cannot use.
+    pub fn validate(&self) -> Result<(), String> {
+        let Some((kind, params)) = self.provider_call() else {
+            return Ok(());
+        };
+        match check_provider(&kind) {
+            Some(provider) => provider.validate(&params),
+            None => Err(format!("unknown check type `{}`", kind)),
+        }
+    }
+}
+
+impl From<&str> for Check {
+    fn from(script: &str) -> Self {
+        Check::Shell(script.to_string())
+    }
+}
@@ -0,0 +1,270 @@
+use std::process;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use serde_json::{Map, Value};
+
+use super::{installed_version, parse_version, Check};
+use crate::condition::Facts;
+use crate::distribution::identify_linux_distribution;
+use crate::traits::CheckProvider;
+
+type Providers = RwLock<Vec<Arc<dyn CheckProvider>>>;
+
+fn providers() -> &'static Providers {
+    static PROVIDERS: OnceLock<Providers> = OnceLock::new();
+    PROVIDERS.get_or_init(|| {
+        RwLock::new(vec![
+            Arc::new(BinaryExists),
+            Arc::new(PackageInstalled),
+            Arc::new(DconfValue),
+            Arc::new(ServiceEnabled),
+            Arc::new(MinVersion),
+            Arc::new(Combined::All),
+            Arc::new(Combined::Any),
+        ])
+    })
+}
+
+/// Makes `provider` available to manifests as a check `type`, replacing any provider
+/// of the same name. Register before loading the manifest so it validates.
+pub fn register_check_provider(provider: impl CheckProvider + 'static) {
+    let mut providers = providers().write().unwrap_or_else(|e| e.into_inner());
+    providers.retain(|existing| existing.name() != provider.name());
+    providers.push(Arc::new(provider));
+}
+
+/// The provider for the check `type` called `name`.
+pub fn check_provider(name: &str) -> Option<Arc<dyn CheckProvider>> {
+    providers()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|provider| provider.name() == name)
+        .cloned()
+}
+
+/// A required string parameter.
+pub fn string_param<'a>(params: &'a Map<String, Value>, name: &str) -> Result<&'a str, String> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("missing string parameter `{}`", name))
+}
+
+fn succeeds(mut command: process::Command) -> bool {
+    command
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// `binary`: satisfied when the program is on `PATH` (or the path exists).
+struct BinaryExists;
+
+impl CheckProvider for BinaryExists {
+    fn name(&self) -> &str {
+        "binary_exists"
+    }
+
+    fn validate(&self, params: &Map<String, Value>) -> Result<(), String> {
+        string_param(params, "binary").map(drop)
+    }
+
+    fn is_satisfied(&self, params: &Map<String, Value>) -> bool {
+        string_param(params, "binary").is_ok_and(|binary| Facts::gather().command_exists(binary))
+    }
+}
+
+/// `package`: satisfied when the distribution's package manager has it installed.
+struct PackageInstalled;
+
+impl CheckProvider for PackageInstalled {
+    fn name(&self) -> &str {
+        "package_installed"
+    }
+
+    fn validate(&self, params: &Map<String, Value>) -> Result<(), String> {
+        string_param(params, "package").map(drop)
+    }
+
+    fn is_satisfied(&self, params: &Map<String, Value>) -> bool {
+        let Ok(package) = string_param(params, "package") else {
+            return false;
+        };
+        identify_linux_distribution()
+            .package_installer()
+            .is_some_and(|installer| succeeds(installer.query_package(package)))
+    }
+}
+
+/// `key`, `value`: satisfied when `dconf read <key>` prints `value` (a GVariant, so
+/// strings keep their quotes).
+struct DconfValue;
+
+impl CheckProvider for DconfValue {
+    fn name(&self) -> &str {
+        "dconf"
+    }
+
+    fn validate(&self, params: &Map<String, Value>) -> Result<(), String> {
+        string_param(params, "key")?;
+        string_param(params, "value").map(drop)
+    }
+
+    fn is_satisfied(&self, params: &Map<String, Value>) -> bool {
+        let (Ok(key), Ok(value)) = (string_param(params, "key"), string_param(params, "value"))
+        else {
+            return false;
+        };
+        process::Command::new("dconf")
+            .args(["read", key])
+            .output()
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == value.trim())
+    }
+}
+
+/// `service`, optional `user`: satisfied when the systemd unit is enabled.
+struct ServiceEnabled;
+
+impl CheckProvider for ServiceEnabled {
+    fn name(&self) -> &str {
+        "service_enabled"
+    }
+
+    fn validate(&self, params: &Map<String, Value>) -> Result<(), String> {
+        string_param(params, "service").map(drop)
+    }
+
+    fn is_satisfied(&self, params: &Map<String, Value>) -> bool {
+        let Ok(service) = string_param(params, "service") else {
+            return false;
+        };
+        let mut command = process::Command::new("systemctl");
+        if params.get("user").and_then(Value::as_bool).unwrap_or(false) {
+            command.arg("--user");
+        }
+        command.args(["is-enabled", "--quiet", service]);
+        succeeds(command)
+    }
+}
+
+/// `binary`, `min_version`: satisfied when `<binary> --version` reports at least `min_version`.
+struct MinVersion;
+
+impl CheckProvider for MinVersion {
+    fn name(&self) -> &str {
+        "min_version"
+    }
+
+    fn validate(&self, params: &Map<String, Value>) -> Result<(), String> {
+        string_param(params, "binary")?;
+        let min_version = string_param(params, "min_version")?;
+        match parse_version(min_version) {
+            Some(_) => Ok(()),
+            None => Err(format!("invalid `min_version`: {}", min_version)),
+        }
+    }
+
+    fn is_satisfied(&self, params: &Map<String, Value>) -> bool {
+        let (Ok(binary), Ok(min_version)) = (
+            string_param(params, "binary"),
+            string_param(params, "min_version"),
+        ) else {
+            return false;
+        };
+        match (installed_version(binary), parse_version(min_version)) {
+            (Some(installed), Some(required)) => installed >= required,
+            _ => false,
+        }
+    }
+}
+
+/// `checks`: satisfied when all (or any) of the nested checks are.
+enum Combined {
+    All,
+    Any,
+}
+
+impl Combined {
+    fn checks(params: &Map<String, Value>) -> Result<Vec<Check>, String> {
+        let checks = params
+            .get("checks")
+            .ok_or("missing list parameter `checks`")?;
+        serde_json::from_value(checks.clone()).map_err(|e| format!("invalid `checks`: {}", e))
+    }
+}
+
+impl CheckProvider for Combined {
+    fn name(&self) -> &str {
+        match self {
+            Combined::All => "all",
+            Combined::Any => "any",
+        }
+    }
+
+    fn validate(&self, params: &Map<String, Value>) -> Result<(), String> {
+        Self::checks(params)?.iter().try_for_each(Check::validate)
+    }
+
+    fn is_satisfied(&self, params: &Map<String, Value>) -> bool {
+        let Ok(checks) = Self::checks(params) else {
+            return false;
+        };
+        match self {
+            Combined::All => checks.iter().all(Check::is_satisfied),
+            Combined::Any => checks.iter().any(Check::is_satisfied),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct VpnConnected;
+
+    impl CheckProvider for VpnConnected {
+        fn name(&self) -> &str {
+            "vpn_connected"
+        }
+
+        fn is_satisfied(&self, params: &Map<String, Value>) -> bool {
+            params.get("interface") == Some(&json!("wg0"))
+        }
+    }
+
+    fn check(value: Value) -> Check {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_custom_providers_compose_with_builtins() {
+        let vpn = check(json!({ "type": "vpn_connected", "interface": "wg0" }));
+        assert_eq!(
+            vpn.validate(),
+            Err("unknown check type `vpn_connected`".to_string())
+        );
+
+        register_check_provider(VpnConnected);
+        assert_eq!(vpn.validate(), Ok(()));
+        assert!(vpn.is_satisfied());
+
+        let all = check(json!({ "type": "all", "checks": [
+            { "type": "vpn_connected", "interface": "wg0" },
+            { "type": "binary_exists", "binary": "sh" },
+            "echo shell checks too"
+        ] }));
+        assert!(all.is_satisfied());
+
+        let any = check(json!({ "type": "any", "checks": [
+            { "type": "binary_exists", "binary": "lsu-not-installed" },
+            { "type": "vpn_connected", "interface": "wg1" }
+        ] }));
+        assert!(!any.is_satisfied());
+        assert!(check(json!({ "type": "binary_exists" }))
+            .validate()
+            .is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use std::process;
+
+/// A dotted numeric version; missing components compare as zero, so `20` == `20.0.0`.
+#[derive(Debug, Clone)]
+pub struct Version(Vec<u64>);
+
+impl Version {
+    fn component(&self, index: usize) -> u64 {
+        self.0.get(index).copied().unwrap_or(0)
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (0..self.0.len().max(other.0.len()))
+            .map(|i| self.component(i).cmp(&other.component(i)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    }
+}
+
+/// Finds the first version-looking token in `text`, so output such as
+/// `v20.11.1`, `git version 2.43.0` or `rustc 1.75.0 (82e1608df 2023-12-21)` all parse.
+pub fn parse_version(text: &str) -> Option<Version> {
+    text.split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .map(|token| token.trim_matches('.'))
+        .find(|token| !token.is_empty())
+        .and_then(|token| {
+            token
+                .split('.')
+                .map(|part| part.parse().ok())
+                .collect::<Option<Vec<u64>>>()
+        })
+        .map(Version)
+}
+
+/// Runs `<binary> --version`; some tools (e.g. java) print it on stderr.
+pub fn installed_version(binary: &str) -> Option<Version> {
+    let output = process::Command::new(binary)
+        .arg("--version")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_version(&String::from_utf8_lossy(&output.stdout))
+        .or_else(|| parse_version(&String::from_utf8_lossy(&output.stderr)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v20.11.1"), parse_version("20.11.1"));
+        assert_eq!(parse_version("20"), parse_version("20.0.0"));
+        assert!(parse_version("git version 2.43.0").unwrap() > parse_version("2.9").unwrap());
+        assert!(
+            parse_version("rustc 1.75.0 (82e1608df 2023-12-21)").unwrap()
+                >= parse_version("1.75").unwrap()
+        );
+        assert!(parse_version("no digits here").is_none());
+    }
+}
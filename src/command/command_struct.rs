@@ -1,41 +1,453 @@
-use std::{cell::RefCell, error, io, process};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use std::{cell::RefCell, env, error, io, process};
 
 use serde::{Deserialize, Serialize};
 
+use super::requires_previous::RequiresPrevious;
+use super::retry::{Retry, RetryPolicy};
 use super::shell::Shell;
-use crate::distribution::{ArchLinux, PackageInstaller, Ubuntu};
+use crate::check::Check;
+use crate::condition::condition_met;
+use crate::distribution::{LogCursor, PackageOptions, PackageResult};
+use crate::utils::{
+    is_root, output_timeout, parse_duration, reporter, Color, OnError, SkipReason,
+    TMPDIR_PLACEHOLDER,
+};
 use crate::{
-    distribution::identify_linux_distribution, traits::ProcessRunner, utils::Status, CommandRunner,
-    DistributionType, ErrorHandler,
+    distribution::{arch_skip, identify_linux_distribution, release_skip},
+    traits::ProcessRunner,
+    utils::Status,
+    CommandRunner, DistributionType, ErrorHandler,
 };
 
 const COMMAND_NOT_FOUND: &str = "Command not found";
 const COMMAND_EXECUTION_FAILED: &str = "Command execution failed";
+/// Programs that change every path they are given.
+const PATH_WRITERS: [&str; 6] = ["tee", "rm", "chmod", "chown", "touch", "mkdir"];
+/// Programs that only change their last argument: the destination, or the file `sed -i` edits.
+const DESTINATION_WRITERS: [&str; 5] = ["cp", "mv", "ln", "install", "sed"];
+/// How much of a failed command's stderr is shown inline.
+const STDERR_EXCERPT_LINES: usize = 10;
+
+/// Whether `script` changes something below `/etc`: a redirection into it, or an `/etc`
+/// path that a writing program is given as its target.
+fn writes_below_etc(script: &str) -> bool {
+    let is_etc = |path: &str| {
+        let path = path.trim_matches(['\'', '"']);
+        path == "/etc" || path.starts_with("/etc/")
+    };
+    script.split(['\n', ';', '|', '&']).any(|command| {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let redirected = words.iter().enumerate().any(|(index, word)| {
+            let Some((_, target)) = word.rsplit_once('>') else {
+                return false;
+            };
+            match target {
+                "" => words.get(index + 1).is_some_and(|next| is_etc(next)),
+                target => is_etc(target),
+            }
+        });
+
+        let mut words = words
+            .into_iter()
+            .skip_while(|word| matches!(*word, "sudo" | "env") || word.contains('='));
+        let Some(program) = words.next() else {
+            return redirected;
+        };
+        let (options, paths): (Vec<&str>, Vec<&str>) = words
+            .filter(|word| !word.contains('>'))
+            .partition(|word| word.starts_with('-'));
+        let written = match program {
+            "sed" if !options.iter().any(|option| option.starts_with("-i")) => None,
+            program if DESTINATION_WRITERS.contains(&program) => paths.last().copied(),
+            program if PATH_WRITERS.contains(&program) => {
+                paths.into_iter().find(|path| is_etc(path))
+            }
+            _ => None,
+        };
+        redirected || written.is_some_and(is_etc)
+    })
+}
+
+fn is_any(requires: &RequiresPrevious) -> bool {
+    *requires == RequiresPrevious::Any
+}
+
+/// Quotes `arg` for display when the shell would otherwise split or expand it.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@+%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CommandStruct {
     command: String,
     shell: Option<Shell>,
     distribution: Option<DistributionType>,
+    /// Further distributions the command runs on, e.g. `["Ubuntu", "Debian"]` for a
+    /// shared apt command; combined with `distribution`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    distributions: Vec<DistributionType>,
     #[serde(skip)]
     status: RefCell<Status>,
-    check: Option<String>,
+    #[serde(skip)]
+    skip_reason: RefCell<Option<SkipReason>>,
+    /// Directory substituted for `{{tmpdir}}` and exported as `$LSU_TMPDIR`.
+    #[serde(skip)]
+    tmpdir: RefCell<Option<PathBuf>>,
+    /// Transaction log position while a `use_package_manager` install runs.
+    #[serde(skip)]
+    package_log: RefCell<Option<LogCursor>>,
+    #[serde(skip)]
+    packages: RefCell<Vec<PackageResult>>,
+    /// User the command is run as via `sudo -u`, when de-escalating from root.
+    #[serde(skip)]
+    run_as: RefCell<Option<String>>,
+    /// Replacement typed in step-through mode; runs instead of `command` from then on.
+    #[serde(skip)]
+    edited: RefCell<Option<String>>,
+    /// stdout and stderr of the last run.
+    #[serde(skip)]
+    last_output: RefCell<Option<process::Output>>,
+    /// Name of the entry the command belongs to, for prefixing streamed output.
+    #[serde(skip)]
+    label: RefCell<Option<String>>,
+    /// The entry's `working_dir`, used when `cwd` is not set.
+    #[serde(skip)]
+    default_cwd: RefCell<Option<PathBuf>>,
+    /// When the current run began, retries included.
+    #[serde(skip)]
+    started: RefCell<Option<Instant>>,
+    /// Wall-clock time of the last run, retries and their pauses included.
+    #[serde(skip)]
+    duration: RefCell<Option<Duration>>,
+    /// The result of `decide_skip`, used once by the next run so `when` and the other
+    /// conditions are not evaluated a second time.
+    #[serde(skip)]
+    decided_skip: RefCell<Option<Option<SkipReason>>>,
+    /// Packages of an offline bundle, installed from instead of the network.
+    #[serde(skip)]
+    package_cache: RefCell<Option<PathBuf>>,
+    check: Option<Check>,
+    /// Keeps stdin on the terminal so the command can prompt; its output is collected
+    /// and printed as one block when it finishes.
     run_spawn: Option<bool>,
+    /// Prints output line by line while the command runs, prefixed with the entry name.
+    stream: Option<bool>,
+    /// Runs the command as root through `sudo`, or with `use_package_manager` runs
+    /// the package manager through it.
     sudo: Option<bool>,
+    /// Runs the whole command as root through the run's shared sudo session.
+    needs_root: Option<bool>,
     use_package_manager: Option<bool>,
+    /// Package manager options for `use_package_manager`, such as dnf repositories to enable.
+    package_options: Option<PackageOptions>,
+    evaluate: Option<String>,
+    when: Option<String>,
+    /// Oldest distribution release the command runs on, e.g. `22.04`.
+    min_version: Option<String>,
+    /// Newest distribution release the command runs on.
+    max_version: Option<String>,
+    /// CPU architectures the command runs on, e.g. `["aarch64"]` for a Raspberry Pi
+    /// binary; empty when it runs on any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    arch: Vec<String>,
+    /// Directory the command runs in; defaults to the entry's `working_dir`.
+    #[serde(
+        default,
+        deserialize_with = "crate::utils::path::deserialize_expanded_path"
+    )]
+    cwd: Option<PathBuf>,
+    /// Variables set for this command only, e.g. `DEBIAN_FRONTEND: noninteractive`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+    /// Exit codes that count as success, e.g. `[0, 1]` for tools that exit 1 when
+    /// there is nothing to do. Defaults to just 0.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    success_exit_codes: Vec<i32>,
+    /// Extra attempts after a failure, for flaky network commands.
+    retries: Option<u32>,
+    /// Retries with a growing pause, e.g. `{ max: 5, backoff: exponential, base: 2s }`;
+    /// takes precedence over `retries` and `retry_delay`.
+    retry: Option<Retry>,
+    /// Pause between attempts, e.g. `5s`.
+    retry_delay: Option<String>,
+    /// Kills the command after this long, e.g. `10m`, so a hung installer cannot stall the run.
+    timeout: Option<String>,
+    /// Overrides the entry's `on_error` for this command.
+    on_error: Option<OnError>,
+    /// Runs only if the preceding command in the entry succeeded, or only if it failed.
+    #[serde(default, skip_serializing_if = "is_any")]
+    requires_previous: RequiresPrevious,
+    /// Command that takes this one back when its entry is rolled back.
+    undo: Option<String>,
 }
 impl CommandStruct {
     pub fn command(&self) -> &str {
         &self.command
     }
 
+    /// The command line that actually runs, including a step-through edit.
+    pub fn script(&self) -> String {
+        self.edited
+            .borrow()
+            .clone()
+            .unwrap_or_else(|| self.command.clone())
+    }
+
+    pub fn edit(&self, script: &str) {
+        self.edited.replace(Some(script.to_string()));
+    }
+
+    pub fn last_output(&self) -> Option<process::Output> {
+        self.last_output.borrow().clone()
+    }
+
+    pub fn status(&self) -> Status {
+        self.status.borrow().clone()
+    }
+
+    /// How long the last run took; `None` when it was skipped or has not run.
+    pub fn duration(&self) -> Option<Duration> {
+        *self.duration.borrow()
+    }
+
+    /// What to show when the command failed: the exact command line, the working
+    /// directory and, when the output was captured, the last lines of stderr.
+    pub fn failure_context(&self) -> Vec<String> {
+        let command = self.setup_command();
+        let line: Vec<String> = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| shell_quote(&arg.to_string_lossy()))
+            .collect();
+        let cwd = command
+            .get_current_dir()
+            .map(Path::to_path_buf)
+            .or_else(|| env::current_dir().ok())
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+
+        let mut lines = vec![
+            format!("Command: {}", line.join(" ")),
+            format!("Working directory: {}", cwd),
+        ];
+        if let Some(output) = self.last_output.borrow().as_ref() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr: Vec<&str> = stderr.lines().collect();
+            if !stderr.is_empty() {
+                let skipped = stderr.len().saturating_sub(STDERR_EXCERPT_LINES);
+                lines.push(match skipped {
+                    0 => "stderr:".to_string(),
+                    n => format!(
+                        "stderr (last {} of {} lines):",
+                        stderr.len() - n,
+                        stderr.len()
+                    ),
+                });
+                lines.extend(stderr[skipped..].iter().map(|line| format!("  {}", line)));
+            }
+        }
+        lines
+    }
+
+    /// The last `count` lines of stderr from the last run, if it was captured.
+    pub fn stderr_tail(&self, count: usize) -> Vec<String> {
+        let Some(output) = self.last_output.borrow().clone() else {
+            return Vec::new();
+        };
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let lines: Vec<&str> = stderr.lines().collect();
+        lines[lines.len().saturating_sub(count)..]
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn report_failure(&self) {
+        for line in self.failure_context() {
+            reporter().error(&format!("{}    {}{}", Color::Red, line, Color::None));
+        }
+    }
+
+    /// Whether the script looks like it changes files below `/etc`, which NixOS
+    /// generates from its configuration and overwrites on the next rebuild.
+    pub fn writes_etc(&self) -> bool {
+        writes_below_etc(&self.script())
+    }
+
     pub fn should_skip(&self) -> bool {
-        if let Some(distribution) = &self.distribution {
-            if *distribution != identify_linux_distribution() {
-                return true;
+        self.evaluate_skip().is_some()
+    }
+
+    pub fn shell(&self) -> Option<&Shell> {
+        self.shell.as_ref()
+    }
+
+    pub fn check(&self) -> Option<&Check> {
+        self.check.as_ref()
+    }
+
+    pub fn on_error(&self) -> Option<OnError> {
+        self.on_error
+    }
+
+    pub fn uses_sudo(&self) -> bool {
+        self.sudo.unwrap_or(false)
+    }
+
+    pub fn needs_root(&self) -> bool {
+        self.needs_root.unwrap_or(false)
+    }
+
+    pub fn uses_package_manager(&self) -> bool {
+        self.use_package_manager.unwrap_or(false)
+    }
+
+    pub fn retry_delay(&self) -> Option<&str> {
+        self.retry_delay.as_deref()
+    }
+
+    pub fn retry(&self) -> Option<&Retry> {
+        self.retry.as_ref()
+    }
+
+    pub(crate) fn raw_timeout(&self) -> Option<&str> {
+        self.timeout.as_deref()
+    }
+
+    pub fn when(&self) -> Option<&str> {
+        self.when.as_deref()
+    }
+
+    pub fn min_version(&self) -> Option<&str> {
+        self.min_version.as_deref()
+    }
+
+    pub fn max_version(&self) -> Option<&str> {
+        self.max_version.as_deref()
+    }
+
+    pub fn arch(&self) -> &[String] {
+        &self.arch
+    }
+
+    /// Returns the reason this command cannot run on the current system, if any.
+    pub fn evaluate_skip(&self) -> Option<SkipReason> {
+        let targets = self.distributions();
+        if !targets.is_empty() {
+            let detected = identify_linux_distribution();
+            if !targets.iter().any(|target| target.matches(&detected)) {
+                return Some(SkipReason::DistributionMismatch {
+                    required: targets.into_iter().cloned().collect(),
+                    detected,
+                });
             }
         }
-        false
+
+        if let Some(reason) = release_skip(self.min_version(), self.max_version()) {
+            return Some(reason);
+        }
+
+        if let Some(reason) = arch_skip(&self.arch) {
+            return Some(reason);
+        }
+
+        if let Some(when) = &self.when {
+            if !condition_met(when) {
+                return Some(SkipReason::Condition(when.clone()));
+            }
+        }
+        None
+    }
+
+    /// `evaluate_skip`, kept for the next run of this command.
+    pub(crate) fn decide_skip(&self) -> Option<SkipReason> {
+        let reason = self.evaluate_skip();
+        self.decided_skip.replace(Some(reason.clone()));
+        reason
+    }
+
+    /// The reason recorded the last time this command was skipped or passed its check.
+    pub fn skip_reason(&self) -> Option<SkipReason> {
+        self.skip_reason.borrow().clone()
+    }
+
+    /// What the package manager reported for each package of the last install.
+    pub fn package_results(&self) -> Vec<PackageResult> {
+        self.packages.borrow().clone()
+    }
+
+    /// Runs the command as `user` instead of the current (root) user.
+    pub fn set_run_as(&self, user: &str) {
+        self.run_as.replace(Some(user.to_string()));
+    }
+
+    pub(crate) fn tmpdir(&self) -> Option<PathBuf> {
+        self.tmpdir.borrow().clone()
+    }
+
+    pub fn set_tmpdir(&self, dir: &Path) {
+        self.tmpdir.replace(Some(dir.to_path_buf()));
+    }
+
+    /// Installs `use_package_manager` packages from `dir`, where the package manager can.
+    pub fn set_package_cache(&self, dir: &Path) {
+        self.package_cache.replace(Some(dir.to_path_buf()));
+    }
+
+    /// The command that downloads this command's packages into `dir` for an offline
+    /// bundle; `None` unless it uses a package manager that can.
+    pub fn download_packages(&self, dir: &Path) -> Option<process::Command> {
+        if !self.uses_package_manager() {
+            return None;
+        }
+        let mut installer = self.package_distribution()?.package_installer()?;
+        if let Some(options) = &self.package_options {
+            installer.configure(options);
+        }
+        installer.download_package(&self.script(), dir, self.sudo.unwrap_or(false))
+    }
+
+    pub fn set_label(&self, label: &str) {
+        self.label.replace(Some(label.to_string()));
+    }
+
+    /// Runs the command in `dir` unless it sets its own `cwd`.
+    pub fn set_default_cwd(&self, dir: &Path) {
+        self.default_cwd.replace(Some(dir.to_path_buf()));
+    }
+
+    pub fn cwd(&self) -> Option<PathBuf> {
+        self.cwd
+            .clone()
+            .or_else(|| self.default_cwd.borrow().clone())
+    }
+
+    pub fn requires_previous(&self) -> RequiresPrevious {
+        self.requires_previous
+    }
+
+    /// Downgrades a failure that a `requires_previous: failure` command recovered from.
+    pub fn mark_recovered(&self) {
+        self.set_status(
+            Status::Warning,
+            &format!("{} (recovered by fallback)", self.command),
+        );
+    }
+
+    pub fn record_skip(&self, status: Status, reason: SkipReason) {
+        self.set_status(status, &format!("{} ({})", self.command, reason));
+        self.skip_reason.replace(Some(reason));
     }
 
     fn set_status(&self, status: Status, message: &str) {
@@ -47,75 +459,296 @@ impl CommandStruct {
         &self,
         check: impl Fn(process::Output) -> bool,
     ) -> Result<bool, Box<dyn error::Error>> {
-        let output = process::Command::new("sh")
-            .arg("-c")
-            .arg(&self.check.as_ref().unwrap())
-            .output()?;
+        let Some(script) = self.check.as_ref().and_then(Check::script) else {
+            return Ok(false);
+        };
+        let mut command = process::Command::new("sh");
+        command.arg("-c");
+        self.script_arg(&mut command, script);
+        let output = command.output()?;
 
         Ok(output.status.success() && check(output))
     }
 
-    pub fn distribution(&self) -> Option<&DistributionType> {
-        self.distribution.as_ref()
+    /// Whether the `check` says the command is already satisfied; `false` without a check.
+    pub fn check_passes(&self) -> bool {
+        match &self.check {
+            Some(Check::Shell(_)) => self
+                .validate_command(|output| !String::from_utf8_lossy(&output.stdout).is_empty())
+                .unwrap_or(false),
+            Some(check) => check.is_satisfied(),
+            None => false,
+        }
+    }
+
+    /// Runs the `evaluate` script with the captured result in `LSU_STDOUT`, `LSU_STDERR`
+    /// and `LSU_EXIT_CODE`. A status name printed on stdout wins; otherwise its exit code decides.
+    fn run_evaluator(script: &str, output: &process::Output) -> Status {
+        let result = process::Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .env(
+                "LSU_STDOUT",
+                String::from_utf8_lossy(&output.stdout).as_ref(),
+            )
+            .env(
+                "LSU_STDERR",
+                String::from_utf8_lossy(&output.stderr).as_ref(),
+            )
+            .env(
+                "LSU_EXIT_CODE",
+                output.status.code().unwrap_or(-1).to_string(),
+            )
+            .output();
+
+        let Ok(result) = result else {
+            return Status::Failure;
+        };
+
+        match String::from_utf8_lossy(&result.stdout)
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "success" => Status::Success,
+            "passed" => Status::Passed,
+            "skipped" => Status::Skipped,
+            "warning" => Status::Warning,
+            "failure" | "failed" => Status::Failure,
+            _ if result.status.success() => Status::Success,
+            _ => Status::Failure,
+        }
+    }
+
+    /// The distributions the command is limited to, from `distribution` and
+    /// `distributions`; empty when it runs everywhere.
+    pub fn distributions(&self) -> Vec<&DistributionType> {
+        self.distribution
+            .iter()
+            .chain(&self.distributions)
+            .collect()
+    }
+
+    /// Whose package manager `use_package_manager` uses: the running distribution when
+    /// the command targets it, otherwise the first target.
+    fn package_distribution(&self) -> Option<DistributionType> {
+        let targets = self.distributions();
+        let detected = identify_linux_distribution();
+        if targets.iter().any(|target| target.matches(&detected)) {
+            return Some(detected);
+        }
+        targets.first().map(|target| (*target).clone())
+    }
+
+    pub fn undo_command(&self) -> Option<&str> {
+        self.undo.as_deref()
+    }
+
+    /// Runs `script` the way the command itself runs: same shell, `sudo`, user, tmpdir
+    /// and `env`. `needs_root` and `sudo` commands run as root and are never de-escalated.
+    fn shell_command(&self, script: &str) -> process::Command {
+        let shell = self.shell.as_ref().unwrap_or(&Shell::Sh).to_string();
+        let preserved: Vec<&str> = std::iter::once("LSU_TMPDIR")
+            .chain(self.env.keys().map(String::as_str))
+            .collect();
+        let preserve_env = format!("--preserve-env={}", preserved.join(","));
+        let run_as = self.run_as.borrow().clone();
+        let as_root = self.needs_root() || self.uses_sudo();
+        let mut command = match (as_root, run_as) {
+            (true, _) if is_root() => process::Command::new(shell),
+            (true, _) => {
+                // Non-interactive: the run's `SudoSession` already holds the credentials.
+                let mut command = process::Command::new("sudo");
+                command.args(["-n", &preserve_env, "--", &shell]);
+                command
+            }
+            (false, Some(user)) => {
+                let mut command = process::Command::new("sudo");
+                command.args(["-u", &user, "-H", &preserve_env, "--", &shell]);
+                command
+            }
+            (false, None) => process::Command::new(shell),
+        };
+        command.envs(&self.env);
+        if let Some(dir) = self.cwd() {
+            command.current_dir(dir);
+        }
+        command.arg("-c");
+        self.script_arg(&mut command, script);
+        command
+    }
+
+    /// Passes `script` to `command` with `{{tmpdir}}` filled in and `$LSU_TMPDIR` set.
+    fn script_arg(&self, command: &mut process::Command, script: &str) {
+        match self.tmpdir.borrow().as_deref() {
+            Some(dir) => {
+                command.arg(script.replace(TMPDIR_PLACEHOLDER, &dir.display().to_string()));
+                command.env("LSU_TMPDIR", dir);
+            }
+            None => {
+                command.arg(script);
+            }
+        }
+    }
+
+    /// Sets `env` on a package manager command. sudo resets the environment, so a
+    /// `sudo …` command is rebuilt to keep those variables with `--preserve-env`.
+    fn with_env(&self, mut command: process::Command) -> process::Command {
+        if command.get_program() != "sudo" || self.env.is_empty() {
+            command.envs(&self.env);
+            return command;
+        }
+
+        let keys: Vec<&str> = self.env.keys().map(String::as_str).collect();
+        let mut sudo = process::Command::new("sudo");
+        sudo.arg(format!("--preserve-env={}", keys.join(",")))
+            .args(command.get_args())
+            .envs(&self.env);
+        if let Some(dir) = command.get_current_dir() {
+            sudo.current_dir(dir);
+        }
+        sudo
+    }
+
+    /// Runs the `undo` command to take back a successful run; `Passed` when there is none.
+    pub fn undo(&self) -> Status {
+        let Some(undo) = &self.undo else {
+            return Status::Passed;
+        };
+
+        Status::Running.print_message(&format!("Undo: {}", undo));
+        match output_timeout(&mut self.shell_command(undo), self.timeout()) {
+            Ok(Some(output)) if output.status.success() => Status::Success,
+            Ok(Some(output)) => {
+                Self::handle_command_error(&String::from_utf8_lossy(&output.stderr));
+                Status::Failure
+            }
+            Ok(None) => self.timed_out(),
+            Err(e) => {
+                Self::handle_command_error(&e.to_string());
+                Status::Failure
+            }
+        }
     }
 }
 
 impl CommandRunner for CommandStruct {
     fn setup_command(&self) -> process::Command {
         if self.use_package_manager.unwrap_or(false) {
-            match self.distribution.as_ref().unwrap() {
-                DistributionType::ArchLinux => {
-                    return ArchLinux::install_package(&self.command, self.sudo.unwrap_or(false))
-                }
-                DistributionType::Ubuntu => {
-                    return Ubuntu::install_package(&self.command, self.sudo.unwrap_or(false))
-                }
-                DistributionType::Unknown => (),
-            };
+            let sudo = self.sudo.unwrap_or(false);
+            let install = self
+                .package_distribution()
+                .and_then(|distribution| distribution.package_installer())
+                .map(|mut installer| {
+                    if let Some(options) = &self.package_options {
+                        installer.configure(options);
+                    }
+                    let cache = self.package_cache.borrow();
+                    cache
+                        .as_deref()
+                        .and_then(|dir| installer.install_downloaded(&self.script(), dir, sudo))
+                        .unwrap_or_else(|| installer.install_package(&self.script(), sudo))
+                });
+            if let Some(command) = install {
+                return self.with_env(command);
+            }
         }
 
-        let mut command =
-            process::Command::new(self.shell.as_ref().unwrap_or(&Shell::Sh).to_string());
-        command.arg("-c");
+        self.shell_command(&self.script())
+    }
+
+    /// An `evaluate` script needs the captured output, so it disables `run_spawn`.
+    fn is_run_spawn(&self) -> bool {
+        self.evaluate.is_none() && self.run_spawn.unwrap_or(false)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout.as_deref().and_then(parse_duration)
+    }
 
-        if self.sudo.unwrap_or(false) {
-            command.arg("sudo");
+    fn is_success(&self, status: &process::ExitStatus) -> bool {
+        match status.code() {
+            Some(code) if !self.success_exit_codes.is_empty() => {
+                self.success_exit_codes.contains(&code)
+            }
+            _ => status.success(),
         }
+    }
 
-        command.arg(&self.command);
-        command
+    fn stream_prefix(&self) -> Option<String> {
+        self.stream.unwrap_or(false).then(|| {
+            let label = self.label.borrow().clone();
+            format!("[{}]", label.unwrap_or_else(|| self.command.clone()))
+        })
     }
 
-    fn is_run_spawn(&self) -> bool {
-        self.run_spawn.unwrap_or(false)
+    fn spawned_output(&self, output: &process::Output) {
+        self.last_output.replace(Some(output.clone()));
+        let (stdout, stderr) = (
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+        if stdout.trim().is_empty() && stderr.trim().is_empty() {
+            return;
+        }
+        // One write, so output from concurrent entries stays grouped.
+        let mut block = format!("Output of `{}`:", self.script());
+        for line in stdout.lines().chain(stderr.lines()) {
+            block.push_str(&format!("\n    {}", line));
+        }
+        reporter().line(&block);
+    }
+
+    fn evaluate(&self, output: &process::Output) -> Option<Status> {
+        self.last_output.replace(Some(output.clone()));
+        self.evaluate
+            .as_ref()
+            .map(|script| Self::run_evaluator(script, output))
     }
 }
 
 impl ProcessRunner for CommandStruct {
     fn before_run(&self) -> Status {
-        if self.should_skip() {
+        self.last_output.replace(None);
+        self.duration.replace(None);
+        let decided = self.decided_skip.take();
+        if let Some(reason) = decided.unwrap_or_else(|| self.evaluate_skip()) {
+            self.record_skip(Status::Skipped, reason);
             return Status::Skipped;
         }
 
-        if self.check.is_some() {
-            if let Ok(result) =
-                self.validate_command(|output| !String::from_utf8_lossy(&output.stdout).is_empty())
-            {
-                if result {
-                    self.set_status(Status::Passed, &format!("{}", self.command));
-                    return Status::Passed;
-                }
-            }
+        if self.check_passes() {
+            self.record_skip(Status::Passed, SkipReason::CheckPassed);
+            return Status::Passed;
         }
 
+        if self.uses_package_manager() {
+            let cursor = self
+                .package_distribution()
+                .and_then(|distribution| LogCursor::open(&distribution));
+            self.package_log.replace(cursor);
+        }
+        self.started.replace(Some(Instant::now()));
         Status::Success
     }
 
     fn after_run(&self, command_status: Status) -> Status {
         self.status.replace(command_status.clone());
+        let started = self.started.take();
+        self.duration
+            .replace(started.map(|started| started.elapsed()));
+        if command_status == Status::Failure {
+            self.report_failure();
+        }
+        if let Some(cursor) = self.package_log.take() {
+            let requested: Vec<&str> = self.command.split_whitespace().collect();
+            let results = cursor.results(&requested, command_status != Status::Failure);
+            self.packages.replace(results);
+        }
 
         match command_status {
             Status::Failure => Status::Failure,
+            Status::Aborted => Status::Aborted,
             Status::Skipped => Status::Skipped,
             Status::Passed => Status::Passed,
             _ => Status::Success,
@@ -123,7 +756,21 @@ impl ProcessRunner for CommandStruct {
     }
 
     fn print_pre_run_info(&self) {
-        Status::Running.print_message(&format!("{}", self.command));
+        Status::Running.print_message(&self.command);
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        if let Some(retry) = &self.retry {
+            return RetryPolicy::from(retry);
+        }
+        RetryPolicy {
+            retries: self.retries.unwrap_or(0),
+            base: self
+                .retry_delay()
+                .and_then(parse_duration)
+                .unwrap_or(Duration::ZERO),
+            ..RetryPolicy::default()
+        }
     }
 }
 
@@ -132,7 +779,7 @@ impl ErrorHandler for CommandStruct {
         if stderr.contains(COMMAND_NOT_FOUND) {
             io::Error::new(io::ErrorKind::NotFound, COMMAND_NOT_FOUND)
         } else {
-            io::Error::new(io::ErrorKind::Other, COMMAND_EXECUTION_FAILED)
+            io::Error::other(COMMAND_EXECUTION_FAILED)
         }
     }
 }
@@ -150,8 +797,9 @@ mod tests {
             shell: Some(Shell::Sh),
             distribution: None,
             status: RefCell::new(Status::Normal),
-            check: Some("echo true".to_string()),
+            check: Some("echo true".into()),
             run_spawn: Some(false),
+            ..Default::default()
         };
 
         let check =
@@ -169,8 +817,9 @@ mod tests {
             shell: Some(Shell::Sh),
             distribution: None,
             status: RefCell::new(Status::Normal),
-            check: Some("echo".to_string()),
+            check: Some("echo".into()),
             run_spawn: Some(false),
+            ..Default::default()
         };
 
         let check =
@@ -190,6 +839,7 @@ mod tests {
             status: RefCell::new(Status::Normal),
             check: None,
             run_spawn: Some(false),
+            ..Default::default()
         };
 
         let status = command_struct.run();
@@ -205,12 +855,42 @@ mod tests {
             status: RefCell::new(Status::Normal),
             check: None,
             run_spawn: Some(false),
+            ..Default::default()
         };
 
         let status = command_struct.run();
         assert_eq!(status, Status::Failure);
     }
 
+    #[test]
+    fn test_run_with_evaluator() {
+        let command_struct = CommandStruct {
+            command: "echo 'ERROR: license expired'".to_string(),
+            shell: Some(Shell::Sh),
+            run_spawn: Some(true),
+            evaluate: Some("! echo \"$LSU_STDOUT\" | grep -q ERROR".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(command_struct.run(), Status::Failure);
+    }
+
+    #[test]
+    fn test_execute_retries_failures() {
+        let marker = std::env::temp_dir().join(format!("lsu-retry-{}", std::process::id()));
+        let command_struct = CommandStruct {
+            command: format!("test -f {0} || {{ touch {0}; exit 1; }}", marker.display()),
+            shell: Some(Shell::Sh),
+            run_spawn: Some(false),
+            retries: Some(2),
+            retry_delay: Some("0s".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(command_struct.execute(), Status::Success);
+        std::fs::remove_file(marker).unwrap();
+    }
+
     #[test]
     fn test_run_use_zsh() {
         use std::fs;
@@ -219,7 +899,7 @@ mod tests {
         use std::path::Path;
 
         let zshrc_path = Path::new(".zshrc");
-        let mut file = File::create(&zshrc_path).expect("Unable to create .zshrc file");
+        let mut file = File::create(zshrc_path).expect("Unable to create .zshrc file");
         writeln!(file, "echo 'Hello from .zshrc'").expect("Unable to write to .zshrc file");
 
         let command_struct = CommandStruct {
@@ -229,6 +909,7 @@ mod tests {
             status: RefCell::new(Status::Normal),
             check: None,
             run_spawn: Some(false),
+            ..Default::default()
         };
 
         let status = command_struct.run();
@@ -236,4 +917,253 @@ mod tests {
 
         fs::remove_file(zshrc_path).expect("Unable to delete .zshrc file");
     }
+
+    #[test]
+    fn test_run_uses_the_decided_skip() {
+        let command: CommandStruct = serde_json::from_value(serde_json::json!({
+            "command": "true",
+            "when": "file_exists('/nonexistent/lsu')"
+        }))
+        .unwrap();
+        assert!(command.decide_skip().is_some());
+        assert_eq!(command.execute(), Status::Skipped);
+
+        command.decided_skip.replace(Some(None));
+        assert_eq!(command.execute(), Status::Success);
+        assert_eq!(command.execute(), Status::Skipped);
+    }
+
+    #[test]
+    fn test_failure_context_shows_stderr_tail() {
+        let command: CommandStruct = serde_json::from_value(serde_json::json!({
+            "command": "seq 1 12 >&2; exit 1",
+            "shell": "Sh"
+        }))
+        .unwrap();
+        assert_eq!(command.execute(), Status::Failure);
+
+        let context = command.failure_context();
+        assert_eq!(context[0], "Command: sh -c 'seq 1 12 >&2; exit 1'");
+        assert!(context[1].starts_with("Working directory: /"));
+        assert_eq!(context[2], "stderr (last 10 of 12 lines):");
+        assert_eq!(context[3], "  3");
+        assert_eq!(context.last().unwrap(), "  12");
+    }
+
+    #[test]
+    fn test_distributions_list() {
+        let detected = identify_linux_distribution();
+        let command = |targets: serde_json::Value| -> CommandStruct {
+            serde_json::from_value(serde_json::json!({
+                "command": "true",
+                "distributions": targets
+            }))
+            .unwrap()
+        };
+        assert!(
+            command(serde_json::json!([DistributionType::Unknown, detected]))
+                .evaluate_skip()
+                .is_none()
+        );
+        let elsewhere = command(serde_json::json!(["Unknown"]));
+        assert_eq!(
+            elsewhere.evaluate_skip(),
+            Some(SkipReason::DistributionMismatch {
+                required: vec![DistributionType::Unknown],
+                detected,
+            })
+        );
+    }
+
+    #[test]
+    fn test_writes_etc() {
+        let command = |script: &str| -> CommandStruct {
+            serde_json::from_value(serde_json::json!({ "command": script })).unwrap()
+        };
+        assert!(
+            command("echo 'Defaults timestamp_timeout=30' | sudo tee /etc/sudoers.d/lsu")
+                .writes_etc()
+        );
+        assert!(command("echo 127.0.0.1 dev >> /etc/hosts").writes_etc());
+        assert!(!command("grep -q nixos /etc/os-release 2>/dev/null").writes_etc());
+        assert!(!command("cp .zshrc ~/").writes_etc());
+        assert!(!command("rm -rf ~/.cache; cat /etc/os-release").writes_etc());
+        assert!(command("sudo cp lsu.conf /etc/lsu.conf").writes_etc());
+        assert!(!command("cp /etc/hosts /tmp/hosts").writes_etc());
+        assert!(command("sed -i 's/a/b/' /etc/fstab").writes_etc());
+        assert!(!command("sed 's/a/b/' /etc/fstab > fstab").writes_etc());
+        assert!(command("true && echo x >/etc/motd").writes_etc());
+        assert!(command("sudo rm -f /etc/apt/sources.list.d/old.list").writes_etc());
+    }
+
+    #[test]
+    fn test_success_exit_codes() {
+        let command = |codes: serde_json::Value| -> CommandStruct {
+            serde_json::from_value(serde_json::json!({
+                "command": "exit 3",
+                "success_exit_codes": codes
+            }))
+            .unwrap()
+        };
+        assert_eq!(
+            command(serde_json::json!([0, 3])).execute(),
+            Status::Success
+        );
+        assert_eq!(
+            command(serde_json::json!([0, 1])).execute(),
+            Status::Failure
+        );
+        assert_eq!(command(serde_json::json!([])).execute(), Status::Failure);
+    }
+
+    #[test]
+    fn test_env_applies_to_command_only() {
+        let command: CommandStruct = serde_json::from_value(serde_json::json!({
+            "command": "test \"$LSU_COMMAND_ENV\" = noninteractive && test \"$LSU_EMPTY\" = ''",
+            "env": { "LSU_COMMAND_ENV": "noninteractive", "LSU_EMPTY": "" }
+        }))
+        .unwrap();
+        assert_eq!(command.execute(), Status::Success);
+        assert!(env::var_os("LSU_COMMAND_ENV").is_none());
+    }
+
+    #[test]
+    fn test_env_is_preserved_through_sudo() {
+        let install: CommandStruct = serde_json::from_value(serde_json::json!({
+            "command": "tzdata",
+            "distribution": "Ubuntu",
+            "use_package_manager": true,
+            "sudo": true,
+            "env": { "DEBIAN_FRONTEND": "noninteractive" }
+        }))
+        .unwrap();
+        let setup = install.setup_command();
+        assert_eq!(setup.get_program(), "sudo");
+        let args: Vec<_> = setup.get_args().collect();
+        assert_eq!(args[..2], ["--preserve-env=DEBIAN_FRONTEND", "apt"]);
+        assert!(setup.get_envs().any(
+            |(key, value)| key == "DEBIAN_FRONTEND" && value == Some("noninteractive".as_ref())
+        ));
+
+        let shell: CommandStruct = serde_json::from_value(serde_json::json!({
+            "command": "apt-get install -y tzdata",
+            "sudo": true,
+            "env": { "DEBIAN_FRONTEND": "noninteractive" }
+        }))
+        .unwrap();
+        let setup = shell.setup_command();
+        if !is_root() {
+            assert!(setup
+                .get_args()
+                .any(|arg| arg == "--preserve-env=LSU_TMPDIR,DEBIAN_FRONTEND"));
+        }
+        assert!(setup.get_envs().any(|(key, _)| key == "DEBIAN_FRONTEND"));
+    }
+
+    #[test]
+    fn test_package_options_reach_the_package_manager() {
+        let install: CommandStruct = serde_json::from_value(serde_json::json!({
+            "command": "htop",
+            "distribution": "Rhel",
+            "use_package_manager": true,
+            "package_options": { "enable_repos": ["epel"] }
+        }))
+        .unwrap();
+        let args: Vec<_> = install
+            .setup_command()
+            .get_args()
+            .map(|arg| arg.to_owned())
+            .collect();
+        assert!(args.contains(&"--enablerepo=epel".into()));
+
+        let emerge: CommandStruct = serde_json::from_value(serde_json::json!({
+            "command": "dev-vcs/git",
+            "distribution": "Gentoo",
+            "use_package_manager": true,
+            "package_options": { "emerge_quiet_build": true }
+        }))
+        .unwrap();
+        let args: Vec<_> = emerge
+            .setup_command()
+            .get_args()
+            .map(|arg| arg.to_owned())
+            .collect();
+        assert!(args.ends_with(&[
+            "--ask=n".into(),
+            "--quiet-build".into(),
+            "--noreplace".into(),
+            "dev-vcs/git".into()
+        ]));
+    }
+
+    #[test]
+    fn test_bundled_packages_install_offline() {
+        let install: CommandStruct = serde_json::from_value(serde_json::json!({
+            "command": "git",
+            "distribution": "Ubuntu",
+            "use_package_manager": true
+        }))
+        .unwrap();
+        let dir = Path::new("/bundle/packages");
+        let download = install.download_packages(dir).unwrap();
+        assert!(download.get_args().any(|arg| arg == "--download-only"));
+
+        install.set_package_cache(dir);
+        let args: Vec<_> = install
+            .setup_command()
+            .get_args()
+            .map(|arg| arg.to_owned())
+            .collect();
+        assert!(args.contains(&"--no-download".into()));
+        assert!(args.contains(&"Dir::Cache::archives=/bundle/packages".into()));
+    }
+
+    #[test]
+    fn test_sudo_runs_the_shell_as_root() {
+        let command: CommandStruct =
+            serde_json::from_value(serde_json::json!({ "command": "id -u", "sudo": true }))
+                .unwrap();
+        let setup = command.setup_command();
+        let args: Vec<_> = setup.get_args().collect();
+        assert_eq!(args[args.len() - 2..], ["-c", "id -u"]);
+        if is_root() {
+            assert_eq!(setup.get_program(), "sh");
+        } else {
+            assert_eq!(setup.get_program(), "sudo");
+            assert_eq!(args[args.len() - 3], "sh");
+        }
+    }
+
+    #[test]
+    fn test_needs_root_is_not_de_escalated() {
+        let command: CommandStruct =
+            serde_json::from_value(serde_json::json!({ "command": "id -u", "needs_root": true }))
+                .unwrap();
+        command.set_run_as("nobody");
+        let setup = command.setup_command();
+        if is_root() {
+            assert_eq!(setup.get_program(), "sh");
+        } else {
+            let args: Vec<_> = setup.get_args().take(2).collect();
+            assert_eq!(
+                (setup.get_program(), args[0]),
+                ("sudo".as_ref(), "-n".as_ref())
+            );
+        }
+    }
+
+    #[test]
+    fn test_cwd_overrides_entry_working_dir() {
+        let command = |value: serde_json::Value| -> CommandStruct {
+            let command: CommandStruct = serde_json::from_value(value).unwrap();
+            command.set_default_cwd(Path::new("/tmp"));
+            command
+        };
+        let own = command(serde_json::json!({ "command": "test \"$(pwd)\" = /", "cwd": "/" }));
+        assert_eq!(own.execute(), Status::Success);
+        assert!(own.failure_context()[1].ends_with(": /"));
+
+        let inherited = command(serde_json::json!({ "command": "test \"$(pwd)\" = /tmp" }));
+        assert_eq!(inherited.execute(), Status::Success);
+    }
 }
@@ -1,4 +1,8 @@
 mod command_struct;
+mod requires_previous;
+mod retry;
 pub mod shell;
 
 pub use command_struct::CommandStruct;
+pub use requires_previous::RequiresPrevious;
+pub use retry::{Backoff, Retry, RetryPolicy};
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{SkipReason, Status};
+
+/// Which outcome of the preceding command in the entry lets a command run.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RequiresPrevious {
+    /// Runs whatever happened before.
+    #[default]
+    Any,
+    /// Runs only after the previous command succeeded or was already satisfied.
+    Success,
+    /// Runs only when the previous command failed, as its fallback; the failure then
+    /// no longer fails the entry.
+    Failure,
+}
+
+impl RequiresPrevious {
+    /// Why the command is skipped after the previous one ended with `previous`.
+    pub fn skip_reason(self, previous: &Status) -> Option<SkipReason> {
+        let failed = matches!(previous, Status::Failure);
+        match self {
+            RequiresPrevious::Success if failed => Some(SkipReason::PreviousFailed),
+            RequiresPrevious::Failure if !failed => Some(SkipReason::PreviousSucceeded),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::parse_duration;
+
+/// Upper bound for a single exponential pause, so late attempts still happen.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// How the pause between attempts grows.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Backoff {
+    /// The same pause every time.
+    #[default]
+    Constant,
+    /// Doubles the pause after each attempt: `base`, `2 * base`, `4 * base`, ...
+    Exponential,
+}
+
+/// `retry: { max: 5, backoff: exponential, base: 2s }` on a command.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct Retry {
+    /// Extra attempts after the first failure.
+    pub max: u32,
+    #[serde(default)]
+    pub backoff: Backoff,
+    /// The first pause, e.g. `2s`; defaults to no pause.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+}
+
+/// When a failed command is run again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub backoff: Backoff,
+    pub base: Duration,
+}
+
+impl RetryPolicy {
+    /// The pause before retry number `attempt`, counting from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        match self.backoff {
+            Backoff::Constant => self.base,
+            Backoff::Exponential => self
+                .base
+                .checked_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+                .map_or(MAX_BACKOFF, |delay| delay.min(MAX_BACKOFF)),
+        }
+    }
+}
+
+impl From<&Retry> for RetryPolicy {
+    fn from(retry: &Retry) -> Self {
+        RetryPolicy {
+            retries: retry.max,
+            backoff: retry.backoff,
+            base: retry
+                .base
+                .as_deref()
+                .and_then(parse_duration)
+                .unwrap_or(Duration::ZERO),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exponential_delay() {
+        let retry: Retry =
+            serde_json::from_str(r#"{ "max": 5, "backoff": "exponential", "base": "2s" }"#)
+                .unwrap();
+        let policy = RetryPolicy::from(&retry);
+        let delays: Vec<u64> = (1..=5).map(|n| policy.delay(n).as_secs()).collect();
+        assert_eq!(delays, [2, 4, 8, 16, 32]);
+        assert_eq!(policy.delay(40), MAX_BACKOFF);
+
+        let constant = RetryPolicy {
+            base: Duration::from_secs(3),
+            ..RetryPolicy::default()
+        };
+        assert_eq!(constant.delay(4), Duration::from_secs(3));
+    }
+}
@@ -1,8 +1,10 @@
 use std::fmt::Display;
+use std::{io, process};
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum Shell {
     Bash,
     Zsh,
@@ -21,3 +23,41 @@ impl Display for Shell {
         }
     }
 }
+
+impl Shell {
+    /// Checks that a custom shell exists and runs scripts passed with `-c`;
+    /// the built-in shells are assumed to be present.
+    pub fn verify(&self) -> Result<(), String> {
+        let Shell::Custom(shell) = self else {
+            return Ok(());
+        };
+
+        match process::Command::new(shell)
+            .args(["-c", "exit 0"])
+            .stdin(process::Stdio::null())
+            .output()
+        {
+            Ok(output) if output.status.success() => Ok(()),
+            Ok(_) => Err(format!("custom shell `{}` does not accept `-c`", shell)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(format!("custom shell `{}` not found", shell))
+            }
+            Err(e) => Err(format!("custom shell `{}` cannot run: {}", shell, e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_custom_shell() {
+        assert!(Shell::Custom("sh".to_string()).verify().is_ok());
+        assert_eq!(
+            Shell::Custom("no-such-shell".to_string()).verify(),
+            Err("custom shell `no-such-shell` not found".to_string())
+        );
+        assert!(Shell::Custom("false".to_string()).verify().is_err());
+    }
+}
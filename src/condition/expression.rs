@@ -0,0 +1,351 @@
+use super::Facts;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Str(String),
+    Ident(String),
+    LParen,
+    RParen,
+    Comma,
+    Not,
+    And,
+    Or,
+    Eq,
+    Ne,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some(ch) => value.push(ch),
+                        None => return Err(format!("unterminated string in `{}`", input)),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            '(' | ')' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    _ => Token::Comma,
+                });
+            }
+            '!' | '=' | '&' | '|' => {
+                chars.next();
+                let next = chars.peek().copied();
+                let token = match (c, next) {
+                    ('!', Some('=')) => Token::Ne,
+                    ('!', _) => Token::Not,
+                    ('=', Some('=')) => Token::Eq,
+                    ('&', Some('&')) => Token::And,
+                    ('|', Some('|')) => Token::Or,
+                    _ => return Err(format!("unexpected `{}` in `{}`", c, input)),
+                };
+                if token != Token::Not {
+                    chars.next();
+                }
+                tokens.push(token);
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if !(ch.is_ascii_alphanumeric() || ch == '_') {
+                        break;
+                    }
+                    ident.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            _ => return Err(format!("unexpected `{}` in `{}`", c, input)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(String),
+    Bool(bool),
+    Variable(String),
+    Flag(String),
+    Call(String, Vec<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Eq(Box<Expr>, Box<Expr>),
+    Ne(Box<Expr>, Box<Expr>),
+}
+
+const FUNCTIONS: [(&str, usize); 4] = [
+    ("file_exists", 1),
+    ("dir_exists", 1),
+    ("command_exists", 1),
+    ("env", 1),
+];
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            other => Err(format!("expected {:?}, found {:?}", expected, other)),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.primary()?;
+        match self.peek() {
+            Some(Token::Eq) => {
+                self.next();
+                Ok(Expr::Eq(Box::new(left), Box::new(self.primary()?)))
+            }
+            Some(Token::Ne) => {
+                self.next();
+                Ok(Expr::Ne(Box::new(left), Box::new(self.primary()?)))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Str(value)) => Ok(Expr::Literal(value)),
+            Some(Token::LParen) => {
+                let expr = self.or()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) if name == "true" || name == "false" => {
+                Ok(Expr::Bool(name == "true"))
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.next();
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::RParen) {
+                    args.push(self.or()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.next();
+                        args.push(self.or()?);
+                    }
+                }
+                self.expect(Token::RParen)?;
+
+                match FUNCTIONS.iter().find(|(function, _)| *function == name) {
+                    Some((_, arity)) if *arity == args.len() => Ok(Expr::Call(name, args)),
+                    Some((_, arity)) => Err(format!(
+                        "`{}` takes {} argument(s), got {}",
+                        name,
+                        arity,
+                        args.len()
+                    )),
+                    None => Err(format!("unknown function `{}`", name)),
+                }
+            }
+            Some(Token::Ident(name)) if Facts::VARIABLES.contains(&name.as_str()) => {
+                Ok(Expr::Variable(name))
+            }
+            Some(Token::Ident(name)) if Facts::FLAGS.contains(&name.as_str()) => {
+                Ok(Expr::Flag(name))
+            }
+            Some(Token::Ident(name)) => Err(format!("unknown fact `{}`", name)),
+            other => Err(format!("unexpected {:?}", other)),
+        }
+    }
+}
+
+enum Value {
+    Str(String),
+    Bool(bool),
+}
+
+impl Value {
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Bool(value) => *value,
+            Value::Str(value) => !value.is_empty(),
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Value::Bool(value) => value.to_string(),
+            Value::Str(value) => value.clone(),
+        }
+    }
+}
+
+/// A parsed `when` expression such as `file_exists('/usr/bin/docker') && env('CI') != 'true'`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl Condition {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+        };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected {:?} in `{}`", token, input));
+        }
+
+        Ok(Condition {
+            source: input.to_string(),
+            expr,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn evaluate(&self, facts: &Facts) -> Result<bool, String> {
+        Ok(Self::eval(&self.expr, facts)?.truthy())
+    }
+
+    fn eval(expr: &Expr, facts: &Facts) -> Result<Value, String> {
+        Ok(match expr {
+            Expr::Literal(value) => Value::Str(value.clone()),
+            Expr::Bool(value) => Value::Bool(*value),
+            Expr::Variable(name) => Value::Str(
+                facts
+                    .variable(name)
+                    .ok_or_else(|| format!("unknown fact `{}`", name))?,
+            ),
+            Expr::Flag(name) => Value::Bool(
+                facts
+                    .flag(name)
+                    .ok_or_else(|| format!("unknown fact `{}`", name))?,
+            ),
+            Expr::Call(name, args) => {
+                let arg = Self::eval(&args[0], facts)?.text();
+                match name.as_str() {
+                    "file_exists" => Value::Bool(facts.file_exists(&arg)),
+                    "dir_exists" => Value::Bool(facts.dir_exists(&arg)),
+                    "command_exists" => Value::Bool(facts.command_exists(&arg)),
+                    "env" => Value::Str(facts.env(&arg)),
+                    _ => return Err(format!("unknown function `{}`", name)),
+                }
+            }
+            Expr::Not(inner) => Value::Bool(!Self::eval(inner, facts)?.truthy()),
+            Expr::And(left, right) => {
+                Value::Bool(Self::eval(left, facts)?.truthy() && Self::eval(right, facts)?.truthy())
+            }
+            Expr::Or(left, right) => {
+                Value::Bool(Self::eval(left, facts)?.truthy() || Self::eval(right, facts)?.truthy())
+            }
+            Expr::Eq(left, right) => {
+                Value::Bool(Self::eval(left, facts)?.text() == Self::eval(right, facts)?.text())
+            }
+            Expr::Ne(left, right) => {
+                Value::Bool(Self::eval(left, facts)?.text() != Self::eval(right, facts)?.text())
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DistributionType;
+
+    fn facts() -> Facts {
+        Facts {
+            distribution: DistributionType::ArchLinux,
+            id: Some("manjaro".to_string()),
+            version: None,
+            arch: "aarch64".to_string(),
+            wsl: true,
+            container: false,
+            raspberry_pi: false,
+        }
+    }
+
+    fn eval(input: &str) -> bool {
+        Condition::parse(input).unwrap().evaluate(&facts()).unwrap()
+    }
+
+    #[test]
+    fn test_evaluate_expressions() {
+        assert!(eval("distro == 'ArchLinux'"));
+        assert!(eval("distro_id == 'manjaro'"));
+        assert!(eval("arch == 'aarch64'"));
+        assert!(eval("distro != \"Ubuntu\" && !false"));
+        assert!(eval("file_exists('/no/such/file') || dir_exists('/')"));
+        assert!(!eval("env('LSU_CONDITION_UNSET') == 'true'"));
+        assert!(eval("!(true && false)"));
+        assert!(eval("command_exists('sh')"));
+        assert!(eval("wsl && wsl == 'true'"));
+        assert!(!eval("!wsl"));
+        assert!(eval("!container"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Condition::parse("file_exists(").is_err());
+        assert!(Condition::parse("reboot('now')").is_err());
+        assert!(Condition::parse("env('A', 'B')").is_err());
+        assert!(Condition::parse("'open").is_err());
+        assert!(Condition::parse("true true").is_err());
+        assert!(Condition::parse("distro = 'x'").is_err());
+        assert!(Condition::parse("kernel == 'x'").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use std::{env, path::Path, process};
+
+use crate::distribution::{
+    cpu_architecture, distribution_id, distribution_version, identify_linux_distribution,
+    is_container, is_raspberry_pi, is_wsl,
+};
+use crate::utils::expand_path;
+use crate::DistributionType;
+
+/// System facts that `when` conditions can query.
+#[derive(Debug, Clone)]
+pub struct Facts {
+    pub distribution: DistributionType,
+    /// The precise os-release `ID`, e.g. `manjaro` where `distribution` is `ArchLinux`.
+    pub id: Option<String>,
+    /// The release, e.g. `24.04`; `None` on rolling releases.
+    pub version: Option<String>,
+    /// The CPU architecture, e.g. `aarch64`.
+    pub arch: String,
+    /// Running under Windows Subsystem for Linux.
+    pub wsl: bool,
+    /// Running inside Docker, Podman, LXC or another container runtime.
+    pub container: bool,
+    /// Running on a Raspberry Pi board, under Raspberry Pi OS or any other distribution.
+    pub raspberry_pi: bool,
+}
+
+impl Facts {
+    /// Names accepted by `variable`.
+    pub const VARIABLES: &'static [&'static str] =
+        &["distro", "distro_id", "distro_version", "arch"];
+
+    /// Names accepted by `flag`; they evaluate to booleans, e.g. `when: "!wsl"`.
+    pub const FLAGS: &'static [&'static str] = &["wsl", "container", "raspberry_pi"];
+
+    pub fn gather() -> Self {
+        Facts {
+            distribution: identify_linux_distribution(),
+            id: distribution_id(),
+            version: distribution_version(),
+            arch: cpu_architecture().to_string(),
+            wsl: is_wsl(),
+            container: is_container(),
+            raspberry_pi: is_raspberry_pi(),
+        }
+    }
+
+    /// Resolves a fact referenced by name in a condition, e.g. `distro`.
+    pub fn variable(&self, name: &str) -> Option<String> {
+        match name {
+            "distro" => Some(match &self.distribution {
+                DistributionType::Custom(name) => name.clone(),
+                distribution => format!("{:?}", distribution),
+            }),
+            "distro_id" => Some(self.id.clone().unwrap_or_default()),
+            "distro_version" => Some(self.version.clone().unwrap_or_default()),
+            "arch" => Some(self.arch.clone()),
+            _ => None,
+        }
+    }
+
+    /// Resolves a boolean fact referenced by name in a condition, e.g. `wsl`.
+    pub fn flag(&self, name: &str) -> Option<bool> {
+        match name {
+            "wsl" => Some(self.wsl),
+            "container" => Some(self.container),
+            "raspberry_pi" => Some(self.raspberry_pi),
+            _ => None,
+        }
+    }
+
+    pub fn file_exists(&self, path: &str) -> bool {
+        expand_path(path).is_file()
+    }
+
+    pub fn dir_exists(&self, path: &str) -> bool {
+        expand_path(path).is_dir()
+    }
+
+    pub fn env(&self, name: &str) -> String {
+        env::var(name).unwrap_or_default()
+    }
+
+    pub fn command_exists(&self, name: &str) -> bool {
+        if name.contains('/') {
+            return Path::new(name).is_file();
+        }
+
+        process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("command -v '{}'", name.replace('\'', "")))
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+}
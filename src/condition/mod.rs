@@ -0,0 +1,13 @@
+mod expression;
+mod facts;
+
+pub use expression::Condition;
+pub use facts::Facts;
+
+/// Parses and evaluates `when` against freshly gathered facts.
+/// An invalid expression counts as not met, since manifests are validated when loaded.
+pub fn condition_met(when: &str) -> bool {
+    Condition::parse(when)
+        .and_then(|condition| condition.evaluate(&Facts::gather()))
+        .unwrap_or(false)
+}
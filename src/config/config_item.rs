@@ -3,8 +3,62 @@ use serde::{Deserialize, Serialize};
 use crate::{traits::ProcessRunner, utils::Status, CommandStruct, Configurator};
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Config {
+    /// Identifies the configuration for `ConfigRepository::revert_one`.
+    name: Option<String>,
     commands: Vec<CommandStruct>,
+    /// Commands that undo `commands`, run in order by `revert`.
+    revert: Option<Vec<CommandStruct>>,
+}
+
+impl Config {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Points `{{tmpdir}}` in every apply and revert command at `dir`.
+    pub(crate) fn set_tmpdir(&self, dir: &std::path::Path) {
+        let revert = self.revert.iter().flatten();
+        for command in self.commands.iter().chain(revert) {
+            command.set_tmpdir(dir);
+        }
+    }
+
+    pub(crate) fn set_run_as(&self, user: &str) {
+        let revert = self.revert.iter().flatten();
+        for command in self.commands.iter().chain(revert) {
+            command.set_run_as(user);
+        }
+    }
+
+    pub fn commands(&self) -> &[CommandStruct] {
+        &self.commands
+    }
+
+    /// The apply commands followed by the revert commands.
+    pub(crate) fn all_commands(&self) -> impl Iterator<Item = &CommandStruct> {
+        self.commands.iter().chain(self.revert.iter().flatten())
+    }
+
+    pub fn is_revertible(&self) -> bool {
+        self.revert.is_some()
+    }
+
+    /// Prints the revert commands without running them.
+    pub fn plan_revert(&self) -> Status {
+        let label = self.name().unwrap_or("configuration");
+        let Some(revert) = &self.revert else {
+            Status::Warning.print_message(&format!("{} has no revert commands", label));
+            return Status::Skipped;
+        };
+
+        Status::Normal.print_message(&format!("Would revert {}:", label));
+        for command in revert {
+            Status::Normal.print_message(&format!("  {}", command.command()));
+        }
+        Status::Success
+    }
 }
 
 impl Configurator for Config {
@@ -24,6 +78,22 @@ impl Configurator for Config {
     }
 
     fn revert(&self) -> Status {
-        todo!()
+        let label = self.name().unwrap_or("configuration");
+        let Some(revert) = &self.revert else {
+            Status::Warning.print_message(&format!("{} has no revert commands", label));
+            return Status::Skipped;
+        };
+
+        Status::Running.print_message(&format!("Reverting {}", label));
+        let failed = revert
+            .iter()
+            .filter(|command| command.execute() == Status::Failure)
+            .count();
+
+        if failed > 0 {
+            return Status::Failure;
+        }
+
+        Status::Success
     }
 }
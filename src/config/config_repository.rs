@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use crate::distribution::{declare_distributions, CustomDistribution};
+use crate::utils::Status;
+use crate::{Config, Configurator, Repository};
+
+/// The configurations of a registry, revertible one at a time or all together.
+#[derive(Debug, Default)]
+pub struct ConfigRepository<'a> {
+    configs: Vec<&'a Config>,
+    /// Declared while reverting, so revert commands can target them.
+    distributions: BTreeMap<String, CustomDistribution>,
+}
+
+impl<'a> ConfigRepository<'a> {
+    pub(crate) fn with_distributions(distributions: BTreeMap<String, CustomDistribution>) -> Self {
+        ConfigRepository {
+            configs: Vec::new(),
+            distributions,
+        }
+    }
+
+    pub fn configs(&self) -> &[&'a Config] {
+        &self.configs
+    }
+
+    pub fn find(&self, name: &str) -> Option<&'a Config> {
+        self.configs
+            .iter()
+            .copied()
+            .find(|config| config.name() == Some(name))
+    }
+
+    /// Reverts the configuration called `name`; with `dry_run` only prints what would run.
+    pub fn revert_one(&self, name: &str, dry_run: bool) -> Status {
+        let _distributions = declare_distributions(&self.distributions);
+        match self.find(name) {
+            Some(config) if dry_run => config.plan_revert(),
+            Some(config) => config.revert(),
+            None => {
+                Status::Failure.print_message(&format!("No configuration named {}", name));
+                Status::Failure
+            }
+        }
+    }
+
+    /// Reverts every configuration that has revert commands, latest first.
+    pub fn revert_all(&self, dry_run: bool) -> Status {
+        let _distributions = declare_distributions(&self.distributions);
+        let mut result = Status::Success;
+        for config in self.configs.iter().rev().filter(|c| c.is_revertible()) {
+            let status = if dry_run {
+                config.plan_revert()
+            } else {
+                config.revert()
+            };
+            if status == Status::Failure {
+                result = Status::Failure;
+            }
+        }
+        result
+    }
+}
+
+impl<'a> Repository<&'a Config> for ConfigRepository<'a> {
+    fn new() -> Self {
+        ConfigRepository::with_distributions(BTreeMap::new())
+    }
+
+    fn add(&mut self, item: &'a Config) {
+        self.configs.push(item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(value: serde_json::Value) -> Config {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_revert_one() {
+        let zsh = config(json!({
+            "name": "zsh",
+            "commands": [{ "command": "true" }],
+            "revert": [{ "command": "true" }]
+        }));
+        let git = config(json!({ "name": "git", "commands": [] }));
+
+        let mut repository = ConfigRepository::new();
+        repository.add(&zsh);
+        repository.add(&git);
+
+        assert_eq!(repository.revert_one("zsh", true), Status::Success);
+        assert_eq!(repository.revert_one("zsh", false), Status::Success);
+        assert_eq!(repository.revert_one("git", false), Status::Skipped);
+        assert_eq!(repository.revert_one("vim", false), Status::Failure);
+        assert_eq!(repository.revert_all(false), Status::Success);
+    }
+}
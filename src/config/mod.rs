@@ -1,3 +1,5 @@
 mod config_item;
+mod config_repository;
 
 pub use config_item::Config;
+pub use config_repository::ConfigRepository;
@@ -0,0 +1,69 @@
+use std::sync::{OnceLock, RwLock};
+use std::{env, fs, path::Path};
+
+use super::linux_distributor::{cached, recache};
+
+/// Markers in `/proc/1/cgroup` and the runtime each one stands for.
+const CGROUP_MARKERS: [(&str, &str); 4] = [
+    ("docker", "docker"),
+    ("libpod", "podman"),
+    ("lxc", "lxc"),
+    ("kubepods", "kubernetes"),
+];
+
+/// The runtime named by a `/proc/1/cgroup` line, e.g. `0::/docker/3f2a...`.
+fn runtime_from_cgroup(cgroup: &str) -> Option<&'static str> {
+    CGROUP_MARKERS
+        .iter()
+        .find(|(marker, _)| cgroup.lines().any(|line| line.contains(marker)))
+        .map(|(_, runtime)| *runtime)
+}
+
+static RUNTIME: OnceLock<RwLock<Option<String>>> = OnceLock::new();
+
+/// The container runtime this process runs under, such as `docker`, `podman` or `lxc`,
+/// from the runtimes' marker files, the `container` variable set by systemd-nspawn,
+/// LXC and podman, or the cgroup of PID 1. `None` on a regular host. Detected once,
+/// see `refresh`.
+pub fn container_runtime() -> Option<String> {
+    cached(&RUNTIME, detect_runtime)
+}
+
+pub(super) fn refresh() {
+    recache(&RUNTIME, detect_runtime);
+}
+
+fn detect_runtime() -> Option<String> {
+    if Path::new("/.dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    if let Some(runtime) = env::var("container").ok().filter(|value| !value.is_empty()) {
+        return Some(runtime);
+    }
+
+    let cgroup = fs::read_to_string("/proc/1/cgroup").ok()?;
+    runtime_from_cgroup(&cgroup).map(str::to_string)
+}
+
+pub fn is_container() -> bool {
+    container_runtime().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_from_cgroup() {
+        let docker = "12:cpuset:/docker/3f2a9c\n0::/docker/3f2a9c\n";
+        assert_eq!(runtime_from_cgroup(docker), Some("docker"));
+        assert_eq!(
+            runtime_from_cgroup("0::/machine.slice/libpod-4b1c.scope"),
+            Some("podman")
+        );
+        assert_eq!(runtime_from_cgroup("0::/init.scope\n"), None);
+    }
+}
@@ -0,0 +1,101 @@
+use std::env::consts;
+use std::process;
+use std::sync::OnceLock;
+
+use crate::utils::SkipReason;
+
+/// The architectures `arch` lists may name, spelled the way `cpu_architecture` reports them.
+pub const ARCHITECTURES: [&str; 12] = [
+    "x86_64",
+    "i686",
+    "aarch64",
+    "armv7",
+    "armv6",
+    "riscv64",
+    "ppc64le",
+    "ppc64",
+    "s390x",
+    "loongarch64",
+    "mips64",
+    "mips",
+];
+
+static MACHINE: OnceLock<String> = OnceLock::new();
+
+/// The machine's CPU architecture from `uname -m`, e.g. `x86_64`, `aarch64`, `armv7` or
+/// `armv6` (Pi Zero). A 32-bit userland on a 64-bit kernel is reported by the kernel, not
+/// the userland, so a 32-bit build of this tool still sees `aarch64` there. Falls back to
+/// the build target when `uname` cannot run.
+pub fn cpu_architecture() -> &'static str {
+    MACHINE.get_or_init(|| {
+        let machine = process::Command::new("uname")
+            .arg("-m")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|machine| !machine.is_empty())
+            .unwrap_or_else(|| consts::ARCH.to_string());
+        normalize_arch(&machine).to_string()
+    })
+}
+
+/// The name `cpu_architecture` uses for `name`, which may also be spelled the Debian
+/// or `uname -m` way, e.g. `amd64`, `arm64`, `armv7l` or `armv6l`.
+pub fn normalize_arch(name: &str) -> &str {
+    match name {
+        "amd64" | "x64" => "x86_64",
+        "i386" | "i486" | "i586" | "x86" => "i686",
+        "arm64" | "armv8" => "aarch64",
+        "armhf" | "armv7l" | "armv8l" => "armv7",
+        "armel" | "armv6l" => "armv6",
+        "ppc64el" => "ppc64le",
+        "loong64" => "loongarch64",
+        name => name,
+    }
+}
+
+/// Why something limited to the architectures in `targets` does not run here.
+pub fn arch_skip(targets: &[String]) -> Option<SkipReason> {
+    let detected = cpu_architecture();
+    let runs_here = targets.is_empty()
+        || targets
+            .iter()
+            .any(|target| normalize_arch(target) == detected);
+    (!runs_here).then(|| SkipReason::ArchitectureMismatch {
+        required: targets.to_vec(),
+        detected: detected.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_arch() {
+        assert_eq!(normalize_arch("amd64"), "x86_64");
+        assert_eq!(normalize_arch("arm64"), "aarch64");
+        assert_eq!(normalize_arch("armv7l"), "armv7");
+        assert_eq!(normalize_arch("armv6l"), "armv6");
+        assert_eq!(normalize_arch("riscv64"), "riscv64");
+        assert_eq!(normalize_arch(cpu_architecture()), cpu_architecture());
+    }
+
+    #[test]
+    fn test_arch_skip() {
+        assert_eq!(arch_skip(&[]), None);
+        assert_eq!(arch_skip(&[cpu_architecture().to_string()]), None);
+        let elsewhere = match cpu_architecture() {
+            "s390x" => "riscv64",
+            _ => "s390x",
+        };
+        assert_eq!(
+            arch_skip(&[elsewhere.to_string()]),
+            Some(SkipReason::ArchitectureMismatch {
+                required: vec![elsewhere.to_string()],
+                detected: cpu_architecture().to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::{self, Stdio};
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::linux_distributor::{refresh, PackageInstaller};
+
+/// Placeholder in the command templates for the packages, passed on as `"$@"`.
+const PACKAGE_PLACEHOLDER: &str = "{{package}}";
+
+/// The distributions of the registry being run; see `declare_distributions`.
+static DECLARED: RwLock<BTreeMap<String, CustomDistribution>> = RwLock::new(BTreeMap::new());
+
+/// A distribution the crate has no variant for, declared under the manifest's
+/// `distributions` and targeted by its name like the built-in ones.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct CustomDistribution {
+    /// Shell command that succeeds only on this distribution, e.g.
+    /// `grep -q '^ID=clear-linux-os' /etc/os-release`.
+    pub detect: String,
+    /// Installs `{{package}}`, e.g. `swupd bundle-add {{package}}`.
+    pub install: String,
+    /// Removes `{{package}}`.
+    pub remove: String,
+    /// Succeeds when `{{package}}` is installed; package checks fail without it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// Lists explicitly installed packages, one per line, for `export_from_system`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
+}
+
+impl CustomDistribution {
+    fn command(template: &str, package: &str, use_sudo: bool) -> process::Command {
+        let script = template.replace(PACKAGE_PLACEHOLDER, "\"$@\"");
+        let mut command: process::Command;
+
+        if use_sudo {
+            command = process::Command::new("sudo");
+            command.arg("sh");
+        } else {
+            command = process::Command::new("sh");
+        }
+
+        command.arg("-c").arg(script).arg("sh");
+        command.args(package.split_whitespace());
+
+        command
+    }
+}
+
+impl PackageInstaller for CustomDistribution {
+    fn install_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Self::command(&self.install, package, use_sudo)
+    }
+
+    fn remove_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Self::command(&self.remove, package, use_sudo)
+    }
+
+    fn list_installed_packages(&self) -> process::Command {
+        Self::command(self.list.as_deref().unwrap_or("false"), "", false)
+    }
+
+    fn query_package(&self, package: &str) -> process::Command {
+        Self::command(self.query.as_deref().unwrap_or("false"), package, false)
+    }
+
+    fn transaction_log(&self) -> Option<&'static Path> {
+        None
+    }
+
+    /// The templates run through `sh`, so there is no program to recognise.
+    fn programs() -> &'static [&'static str] {
+        &[]
+    }
+}
+
+/// Restores the distributions declared before `declare_distributions` when dropped.
+#[must_use]
+pub struct DeclaredDistributions {
+    previous: BTreeMap<String, CustomDistribution>,
+}
+
+fn replace_declared(
+    distributions: BTreeMap<String, CustomDistribution>,
+) -> BTreeMap<String, CustomDistribution> {
+    let mut declared = DECLARED.write().unwrap_or_else(|e| e.into_inner());
+    if *declared == distributions {
+        return distributions;
+    }
+    let previous = std::mem::replace(&mut *declared, distributions);
+    drop(declared);
+    refresh();
+    previous
+}
+
+/// Makes `distributions` the declared ones, and detects the distribution again with
+/// them, until the returned guard is dropped. Registries declare theirs only while
+/// they run, so loading or linting a manifest detects nothing.
+pub fn declare_distributions(
+    distributions: &BTreeMap<String, CustomDistribution>,
+) -> DeclaredDistributions {
+    DeclaredDistributions {
+        previous: replace_declared(distributions.clone()),
+    }
+}
+
+impl Drop for DeclaredDistributions {
+    fn drop(&mut self) {
+        replace_declared(std::mem::take(&mut self.previous));
+    }
+}
+
+/// The distribution called `name` declared by the running registry.
+pub fn custom_distribution(name: &str) -> Option<CustomDistribution> {
+    DECLARED
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+        .cloned()
+}
+
+/// The first declared distribution whose `detect` command succeeds.
+pub(super) fn detect_custom() -> Option<String> {
+    let declared = DECLARED.read().unwrap_or_else(|e| e.into_inner());
+    declared
+        .iter()
+        .find(|(_, distribution)| {
+            process::Command::new("sh")
+                .arg("-c")
+                .arg(&distribution.detect)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+        })
+        .map(|(name, _)| name.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_commands() {
+        let clear: CustomDistribution = serde_json::from_value(serde_json::json!({
+            "detect": "false",
+            "install": "swupd bundle-add {{package}}",
+            "remove": "swupd bundle-remove {{package}}"
+        }))
+        .unwrap();
+        let install = clear.install_package("git vim", true);
+        assert_eq!(install.get_program(), "sudo");
+        assert_eq!(
+            install.get_args().collect::<Vec<_>>(),
+            ["sh", "-c", "swupd bundle-add \"$@\"", "sh", "git", "vim"]
+        );
+        let query = clear.query_package("git");
+        assert_eq!(
+            query.get_args().collect::<Vec<_>>(),
+            ["-c", "false", "sh", "git"]
+        );
+    }
+}
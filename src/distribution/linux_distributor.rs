@@ -1,32 +1,188 @@
 use std::{
+    cell::RefCell,
+    env,
     fmt::{Debug, Display},
     fs,
-    path::PathBuf,
+    path::{Path, PathBuf},
     process,
+    sync::{OnceLock, RwLock},
 };
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+use super::custom::{custom_distribution, detect_custom};
+use super::raspberry_pi::is_raspberry_pi;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum DistributionType {
     Ubuntu,
     ArchLinux,
+    Fedora,
+    OpenSuse,
+    Debian,
+    /// Targets Debian and Ubuntu alike; never detected itself.
+    DebianLike,
+    NixOs,
+    Alpine,
+    Gentoo,
+    /// Debian for Raspberry Pi boards; commands targeting `Debian` run on it too.
+    RaspberryPiOs,
+    /// Red Hat Enterprise Linux and its rebuilds, Rocky Linux, AlmaLinux and CentOS.
+    Rhel,
+    Void,
     Unknown,
+    /// A distribution declared in the manifest's `distributions`, by its name.
+    #[serde(untagged)]
+    Custom(String),
+}
+
+impl DistributionType {
+    /// The distribution an os-release(5) `ID` or `ID_LIKE` word stands for.
+    fn from_os_release_id(id: &str) -> Option<Self> {
+        match id {
+            "ubuntu" => Some(DistributionType::Ubuntu),
+            "arch" => Some(DistributionType::ArchLinux),
+            "fedora" => Some(DistributionType::Fedora),
+            "suse" => Some(DistributionType::OpenSuse),
+            id if id.starts_with("opensuse") => Some(DistributionType::OpenSuse),
+            "debian" => Some(DistributionType::Debian),
+            "nixos" => Some(DistributionType::NixOs),
+            "alpine" => Some(DistributionType::Alpine),
+            "gentoo" => Some(DistributionType::Gentoo),
+            "raspbian" => Some(DistributionType::RaspberryPiOs),
+            "rhel" => Some(DistributionType::Rhel),
+            "void" => Some(DistributionType::Void),
+            // Derivatives that are known to leave out `ID_LIKE` in some releases.
+            "manjaro" | "endeavouros" | "garuda" | "arcolinux" => Some(DistributionType::ArchLinux),
+            "linuxmint" | "pop" | "elementary" | "zorin" | "neon" => Some(DistributionType::Ubuntu),
+            "rocky" | "almalinux" | "centos" | "ol" => Some(DistributionType::Rhel),
+            _ => None,
+        }
+    }
+
+    /// Identifies the distribution from os-release(5) content: `ID` when it is known,
+    /// otherwise the first known word of `ID_LIKE`, which lists parents closest first,
+    /// so Manjaro counts as Arch Linux and Pop!_OS as Ubuntu.
+    pub fn from_os_release(content: &str) -> Self {
+        let id = os_release_field(content, "ID").into_iter();
+        let like = os_release_field(content, "ID_LIKE")
+            .into_iter()
+            .flat_map(str::split_whitespace);
+        id.chain(like)
+            .find_map(Self::from_os_release_id)
+            .unwrap_or(DistributionType::Unknown)
+    }
+
+    /// Whether a command targeting `self` runs on the `detected` distribution.
+    pub fn matches(&self, detected: &DistributionType) -> bool {
+        match self {
+            DistributionType::DebianLike => matches!(
+                detected,
+                DistributionType::Debian
+                    | DistributionType::Ubuntu
+                    | DistributionType::RaspberryPiOs
+                    | DistributionType::DebianLike
+            ),
+            DistributionType::Debian => matches!(
+                detected,
+                DistributionType::Debian | DistributionType::RaspberryPiOs
+            ),
+            _ => self == detected,
+        }
+    }
+
+    /// The package manager of this distribution, chosen at runtime; `None` for
+    /// `Unknown`. Debian-like targets use apt.
+    pub fn package_installer(&self) -> Option<Box<dyn PackageInstaller>> {
+        match self {
+            DistributionType::ArchLinux => Some(Box::new(ArchLinux::package_manager())),
+            DistributionType::Ubuntu => Some(Box::new(Ubuntu::package_manager())),
+            DistributionType::Debian
+            | DistributionType::DebianLike
+            | DistributionType::RaspberryPiOs => Some(Box::new(Debian::package_manager())),
+            DistributionType::Fedora => Some(Box::new(Fedora::package_manager())),
+            DistributionType::OpenSuse => Some(Box::new(OpenSuse::package_manager())),
+            DistributionType::NixOs => Some(Box::new(NixOs::package_manager())),
+            DistributionType::Alpine => Some(Box::new(Alpine::package_manager())),
+            DistributionType::Gentoo => Some(Box::new(Gentoo::package_manager())),
+            DistributionType::Rhel => Some(Box::new(Rhel::package_manager())),
+            DistributionType::Void => Some(Box::new(Void::package_manager())),
+            DistributionType::Custom(name) => custom_distribution(name)
+                .map(|distribution| Box::new(distribution) as Box<dyn PackageInstaller>),
+            DistributionType::Unknown => None,
+        }
+    }
+}
+
+/// The value of `key` in os-release(5) content, without surrounding quotes.
+pub(super) fn os_release_field<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        (name.trim() == key).then(|| value.trim().trim_matches(|c| c == '"' || c == '\''))
+    })
 }
 
 pub trait LinuxDistributor {
     fn check() -> Self;
 }
 
+/// Where os-release(5) may live; `/etc` takes precedence over the vendor copy.
+const OS_RELEASE_PATHS: [&str; 2] = ["/etc/os-release", "/usr/lib/os-release"];
+
+/// The content of os-release(5), if the system has one; read once, see `refresh`.
+pub(super) fn read_os_release() -> Option<String> {
+    cached(&OS_RELEASE, detect_os_release)
+}
+
+fn detect_os_release() -> Option<String> {
+    OS_RELEASE_PATHS
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+}
+
+/// The precise os-release `ID`, e.g. `manjaro` or `pop`, for conditions that need
+/// more than the parent distribution. `None` while the distribution is overridden,
+/// since the host's `ID` would not match it.
+pub fn distribution_id() -> Option<String> {
+    if is_overridden() {
+        return None;
+    }
+    read_os_release().and_then(|content| os_release_field(&content, "ID").map(str::to_string))
+}
+
 impl LinuxDistributor for DistributionType {
+    /// Runs the `detect` commands of declared distributions first, then reads
+    /// os-release and falls back to the distribution-specific release files for
+    /// systems without it or with an `ID` nothing here knows.
     fn check() -> Self {
         let arch_path: PathBuf = PathBuf::from("/etc/arch-release");
+        let gentoo_path: PathBuf = PathBuf::from("/etc/gentoo-release");
         let lsb_path: PathBuf = PathBuf::from("/etc/lsb-release");
 
+        if let Some(name) = detect_custom() {
+            return DistributionType::Custom(name);
+        }
+
+        if let Some(content) = read_os_release() {
+            let detected = Self::from_os_release(&content);
+            // The 64-bit Raspberry Pi OS calls itself plain Debian.
+            if detected == DistributionType::Debian && is_raspberry_pi() {
+                return DistributionType::RaspberryPiOs;
+            }
+            if detected != DistributionType::Unknown {
+                return detected;
+            }
+        }
+
         if arch_path.exists() {
             return DistributionType::ArchLinux;
         }
 
+        if gentoo_path.exists() {
+            return DistributionType::Gentoo;
+        }
+
         if let Ok(content) = fs::read_to_string(lsb_path) {
             if content.contains("Ubuntu") {
                 return DistributionType::Ubuntu;
@@ -42,23 +198,214 @@ impl Display for DistributionType {
         match self {
             DistributionType::Ubuntu => write!(f, "Ubuntu"),
             DistributionType::ArchLinux => write!(f, "Arch Linux"),
+            DistributionType::Fedora => write!(f, "Fedora"),
+            DistributionType::OpenSuse => write!(f, "openSUSE"),
+            DistributionType::Debian => write!(f, "Debian"),
+            DistributionType::DebianLike => write!(f, "Debian-like"),
+            DistributionType::NixOs => write!(f, "NixOS"),
+            DistributionType::Alpine => write!(f, "Alpine Linux"),
+            DistributionType::Gentoo => write!(f, "Gentoo"),
+            DistributionType::RaspberryPiOs => write!(f, "Raspberry Pi OS"),
+            DistributionType::Rhel => write!(f, "RHEL"),
+            DistributionType::Void => write!(f, "Void Linux"),
             DistributionType::Unknown => write!(f, "Unknown"),
+            DistributionType::Custom(name) => write!(f, "{}", name),
         }
     }
 }
 
-/// Identifies the Linux distribution by calling the `check` method of `DistributionType`.
+/// Environment variable that overrides detection, e.g. `LSU_FORCE_DISTRO=ArchLinux`.
+pub const FORCE_DISTRO_VAR: &str = "LSU_FORCE_DISTRO";
+
+thread_local! {
+    static OVERRIDE: RefCell<Option<DistributionType>> = const { RefCell::new(None) };
+}
+
+/// Detection results, kept for the life of the process; see `refresh`.
+static DETECTED: OnceLock<RwLock<DistributionType>> = OnceLock::new();
+static OS_RELEASE: OnceLock<RwLock<Option<String>>> = OnceLock::new();
+static ARCH_PACKAGE_MANAGER: OnceLock<RwLock<ArchLinux>> = OnceLock::new();
+static NIX_PACKAGE_MANAGER: OnceLock<RwLock<NixOs>> = OnceLock::new();
+static RHEL_TOOL: OnceLock<RwLock<RhelTool>> = OnceLock::new();
+
+pub(super) fn cached<T: Clone>(cell: &OnceLock<RwLock<T>>, detect: impl FnOnce() -> T) -> T {
+    cell.get_or_init(|| RwLock::new(detect()))
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+pub(super) fn recache<T>(cell: &OnceLock<RwLock<T>>, detect: impl FnOnce() -> T) {
+    if let Some(lock) = cell.get() {
+        *lock.write().unwrap_or_else(|e| e.into_inner()) = detect();
+    }
+}
+
+/// Detects the system again instead of reusing the cached results, e.g. after a test
+/// changed what is installed: the distribution and its release files, the hardware,
+/// container and WSL markers, and which package manager tool is in use.
+pub fn refresh() {
+    recache(&OS_RELEASE, detect_os_release);
+    super::release::refresh();
+    super::raspberry_pi::refresh();
+    super::container::refresh();
+    super::wsl::refresh();
+    recache(&DETECTED, DistributionType::check);
+    recache(&ARCH_PACKAGE_MANAGER, ArchLinux::detect);
+    recache(&NIX_PACKAGE_MANAGER, NixOs::detect);
+    recache(&RHEL_TOOL, RhelTool::detect);
+}
+
+/// Puts back what the thread reported before `override_distribution` when dropped.
+#[must_use]
+pub struct DistributionOverride {
+    previous: Option<DistributionType>,
+}
+
+impl Drop for DistributionOverride {
+    fn drop(&mut self) {
+        OVERRIDE.set(self.previous.take());
+    }
+}
+
+/// Makes `identify_linux_distribution` on this thread report `distribution` instead of
+/// detecting it until the returned guard is dropped, so Arch-specific entries can be
+/// planned or tested on Ubuntu. Takes precedence over `LSU_FORCE_DISTRO`.
+pub fn override_distribution(distribution: DistributionType) -> DistributionOverride {
+    DistributionOverride {
+        previous: OVERRIDE.replace(Some(distribution)),
+    }
+}
+
+/// The override in effect on this thread, for handing on to worker threads.
+pub fn current_override() -> Option<DistributionType> {
+    OVERRIDE.with_borrow(Clone::clone)
+}
+
+/// The distribution named by `LSU_FORCE_DISTRO`, spelled as in manifests; names that
+/// are neither built in nor declared are ignored.
+fn forced_distribution(value: Option<&str>) -> Option<DistributionType> {
+    let value = value?.trim();
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .ok()
+        .filter(|distribution| match distribution {
+            DistributionType::Custom(name) => custom_distribution(name).is_some(),
+            _ => true,
+        })
+}
+
+/// The distribution reported instead of the detected one: the thread's override,
+/// else `forced`, the value of `LSU_FORCE_DISTRO`.
+fn overridden(forced: Option<&str>) -> Option<DistributionType> {
+    current_override().or_else(|| forced_distribution(forced))
+}
+
+/// Whether an override or `LSU_FORCE_DISTRO` bypasses detection.
+pub(super) fn is_overridden() -> bool {
+    overridden(env::var(FORCE_DISTRO_VAR).ok().as_deref()).is_some()
+}
+
+fn identify(forced: Option<&str>) -> DistributionType {
+    overridden(forced).unwrap_or_else(|| cached(&DETECTED, DistributionType::check))
+}
+
+/// Identifies the Linux distribution: the override when one is set, otherwise the
+/// result of the `check` method of `DistributionType`, which runs only once.
 pub fn identify_linux_distribution() -> DistributionType {
-    DistributionType::check()
+    identify(env::var(FORCE_DISTRO_VAR).ok().as_deref())
+}
+
+/// Whether `program` belongs to a built-in package manager; see `PackageInstaller::programs`.
+pub fn is_package_manager(program: &str) -> bool {
+    [
+        ArchLinux::programs(),
+        Ubuntu::programs(),
+        Debian::programs(),
+        Fedora::programs(),
+        OpenSuse::programs(),
+        NixOs::programs(),
+        Alpine::programs(),
+        Gentoo::programs(),
+        Rhel::programs(),
+        Void::programs(),
+    ]
+    .into_iter()
+    .flatten()
+    .any(|known| *known == program)
 }
 
+/// Options for `use_package_manager` commands that only some package managers take,
+/// usually set once per distribution under `defaults`, e.g.
+/// `"Rhel": { "package_options": { "enable_repos": ["epel"] } }`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct PackageOptions {
+    /// Repositories that are disabled by default, such as `epel` or `crb`, enabled for
+    /// dnf and yum installs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enable_repos: Vec<String>,
+    /// Lets emerge confirm each merge (`--ask=y`) instead of never asking.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub emerge_ask: bool,
+    /// Passes `--quiet-build` to emerge, hiding compiler output for long builds.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub emerge_quiet_build: bool,
+}
+
+/// A distribution's package manager. `DistributionType::package_installer` picks one
+/// at runtime, so callers need not know the concrete type.
 pub trait PackageInstaller: Debug {
-    fn install_package(package: &str, use_sudo: bool) -> process::Command;
-    fn remove_package(package: &str, use_sudo: bool) -> process::Command;
-    fn package_manager() -> Self;
+    fn install_package(&self, package: &str, use_sudo: bool) -> process::Command;
+    fn remove_package(&self, package: &str, use_sudo: bool) -> process::Command;
+    /// Lists explicitly installed packages, one name per line.
+    fn list_installed_packages(&self) -> process::Command;
+    /// Succeeds when `package` is installed.
+    fn query_package(&self, package: &str) -> process::Command;
+    /// The log the package manager appends each transaction to, if it keeps one.
+    fn transaction_log(&self) -> Option<&'static Path>;
+    /// Every program of this package manager that takes its lock, e.g. `pacman` and
+    /// `yay`, so inline commands that run one are recognised.
+    fn programs() -> &'static [&'static str]
+    where
+        Self: Sized;
+    /// Adopts a command's `package_options`; package managers without such options
+    /// ignore them.
+    fn configure(&mut self, options: &PackageOptions) {
+        let _ = options;
+    }
+    /// Downloads `package` and the dependencies missing on this machine into `dir`
+    /// without installing them, for an offline bundle; `None` when the package manager
+    /// cannot install from such a directory later.
+    fn download_package(
+        &self,
+        package: &str,
+        dir: &Path,
+        use_sudo: bool,
+    ) -> Option<process::Command> {
+        let _ = (package, dir, use_sudo);
+        None
+    }
+    /// Installs `package` from what `download_package` left in `dir`.
+    fn install_downloaded(
+        &self,
+        package: &str,
+        dir: &Path,
+        use_sudo: bool,
+    ) -> Option<process::Command> {
+        let _ = (package, dir, use_sudo);
+        None
+    }
+    /// The package manager in use on this system; the default value unless overridden.
+    fn package_manager() -> Self
+    where
+        Self: Sized + Default,
+    {
+        Self::default()
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub enum ArchLinux {
     #[default]
     Pacman,
@@ -66,41 +413,106 @@ pub enum ArchLinux {
 }
 
 impl PackageInstaller for ArchLinux {
-    fn install_package(package: &str, use_sudo: bool) -> process::Command {
+    fn install_package(&self, package: &str, use_sudo: bool) -> process::Command {
         let _ = use_sudo;
         let mut command: process::Command;
-        match Self::package_manager() {
+        match self {
             ArchLinux::Pacman => {
                 command = process::Command::new("pacman");
                 command.arg("-S");
                 command.args(["--noconfirm", "--needed"]);
-                command.arg(package);
+                command.args(package.split_whitespace());
             }
             ArchLinux::Yay => {
                 command = process::Command::new("yay");
                 command.arg("-S");
                 command.args(["--noconfirm", "--overwrite"]);
-                command.arg(package);
+                command.args(package.split_whitespace());
             }
         };
 
         command
     }
 
-    fn remove_package(package: &str, use_sudo: bool) -> process::Command {
-        todo!()
+    fn remove_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        let _ = use_sudo;
+        let mut command = process::Command::new(match self {
+            ArchLinux::Pacman => "pacman",
+            ArchLinux::Yay => "yay",
+        });
+        command.args(["-Rs", "--noconfirm"]);
+        command.args(package.split_whitespace());
+        command
+    }
+
+    fn list_installed_packages(&self) -> process::Command {
+        let mut command = process::Command::new("pacman");
+        command.arg("-Qqe");
+        command
+    }
+
+    fn query_package(&self, package: &str) -> process::Command {
+        let mut command = process::Command::new("pacman");
+        command.arg("-Q").arg(package);
+        command
+    }
+
+    fn transaction_log(&self) -> Option<&'static Path> {
+        Some(Path::new("/var/log/pacman.log"))
     }
 
+    /// Always pacman, which keeps the packages in `dir` as its cache; AUR packages are
+    /// built rather than downloaded, so they cannot be bundled.
+    fn download_package(
+        &self,
+        package: &str,
+        dir: &Path,
+        use_sudo: bool,
+    ) -> Option<process::Command> {
+        let _ = use_sudo;
+        let mut command = process::Command::new("pacman");
+        command.args(["-Sw", "--noconfirm", "--cachedir"]).arg(dir);
+        command.args(package.split_whitespace());
+        Some(command)
+    }
+
+    fn install_downloaded(
+        &self,
+        package: &str,
+        dir: &Path,
+        use_sudo: bool,
+    ) -> Option<process::Command> {
+        let _ = use_sudo;
+        let mut command = process::Command::new("pacman");
+        command
+            .args(["-S", "--noconfirm", "--needed", "--cachedir"])
+            .arg(dir);
+        command.args(package.split_whitespace());
+        Some(command)
+    }
+
+    fn programs() -> &'static [&'static str] {
+        &["pacman", "yay", "paru"]
+    }
+
+    /// Checks for yay once; `refresh` checks again.
     fn package_manager() -> Self {
-        let ouput = process::Command::new("yay")
+        cached(&ARCH_PACKAGE_MANAGER, Self::detect)
+    }
+}
+
+impl ArchLinux {
+    fn detect() -> Self {
+        let has_yay = process::Command::new("yay")
             .arg("--version")
             .output()
-            .expect("Failed to check for yay");
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("yay v"));
 
-        String::from_utf8_lossy(&ouput.stdout)
-            .contains("yay v")
-            .then(|| ArchLinux::Yay)
-            .unwrap_or(ArchLinux::Pacman)
+        if has_yay {
+            ArchLinux::Yay
+        } else {
+            ArchLinux::Pacman
+        }
     }
 }
 
@@ -110,8 +522,27 @@ pub enum Ubuntu {
     Apt,
 }
 
+impl Ubuntu {
+    /// `apt-get install` with `archives` as the directory packages are kept in.
+    fn apt_get(args: &[&str], package: &str, archives: &Path, use_sudo: bool) -> process::Command {
+        let mut command = if use_sudo {
+            let mut command = process::Command::new("sudo");
+            command.arg("apt-get");
+            command
+        } else {
+            process::Command::new("apt-get")
+        };
+        command.args(["install", "-y"]).args(args);
+        command
+            .arg("-o")
+            .arg(format!("Dir::Cache::archives={}", archives.display()));
+        command.args(package.split_whitespace());
+        command
+    }
+}
+
 impl PackageInstaller for Ubuntu {
-    fn install_package(package: &str, use_sudo: bool) -> process::Command {
+    fn install_package(&self, package: &str, use_sudo: bool) -> process::Command {
         let mut command: process::Command;
 
         if use_sudo {
@@ -122,17 +553,673 @@ impl PackageInstaller for Ubuntu {
         }
 
         command.args(["install", "-y"]);
-        command.arg(package);
+        command.args(package.split_whitespace());
 
         command
     }
 
-    fn remove_package(package: &str, use_sudo: bool) -> process::Command {
-        todo!()
+    fn remove_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        let mut command: process::Command;
+
+        if use_sudo {
+            command = process::Command::new("sudo");
+            command.arg("apt");
+        } else {
+            command = process::Command::new("apt");
+        }
+
+        command.args(["remove", "-y"]);
+        command.args(package.split_whitespace());
+
+        command
+    }
+
+    fn list_installed_packages(&self) -> process::Command {
+        let mut command = process::Command::new("apt-mark");
+        command.arg("showmanual");
+        command
+    }
+
+    fn query_package(&self, package: &str) -> process::Command {
+        // dpkg also knows removed packages whose config files are left behind.
+        let mut command = process::Command::new("sh");
+        command
+            .arg("-c")
+            .arg("dpkg-query -W -f='${db:Status-Status}' \"$1\" 2>/dev/null | grep -qx installed")
+            .args(["sh", package]);
+        command
+    }
+
+    fn transaction_log(&self) -> Option<&'static Path> {
+        Some(Path::new("/var/log/apt/history.log"))
+    }
+
+    /// `dir` becomes apt's archive directory, which needs a `partial` directory inside.
+    fn download_package(
+        &self,
+        package: &str,
+        dir: &Path,
+        use_sudo: bool,
+    ) -> Option<process::Command> {
+        Some(Self::apt_get(&["--download-only"], package, dir, use_sudo))
+    }
+
+    fn install_downloaded(
+        &self,
+        package: &str,
+        dir: &Path,
+        use_sudo: bool,
+    ) -> Option<process::Command> {
+        Some(Self::apt_get(&["--no-download"], package, dir, use_sudo))
+    }
+
+    fn programs() -> &'static [&'static str] {
+        &["apt", "apt-get", "dpkg", "snap"]
+    }
+
+    fn package_manager() -> Self {
+        Ubuntu::Apt
+    }
+}
+
+/// Debian proper, which shares apt and dpkg with Ubuntu.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub enum Debian {
+    #[default]
+    Apt,
+}
+
+impl PackageInstaller for Debian {
+    fn install_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Ubuntu::Apt.install_package(package, use_sudo)
+    }
+
+    fn remove_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Ubuntu::Apt.remove_package(package, use_sudo)
+    }
+
+    fn list_installed_packages(&self) -> process::Command {
+        Ubuntu::Apt.list_installed_packages()
+    }
+
+    fn query_package(&self, package: &str) -> process::Command {
+        Ubuntu::Apt.query_package(package)
+    }
+
+    fn transaction_log(&self) -> Option<&'static Path> {
+        Ubuntu::Apt.transaction_log()
+    }
+
+    fn download_package(
+        &self,
+        package: &str,
+        dir: &Path,
+        use_sudo: bool,
+    ) -> Option<process::Command> {
+        Ubuntu::Apt.download_package(package, dir, use_sudo)
+    }
+
+    fn install_downloaded(
+        &self,
+        package: &str,
+        dir: &Path,
+        use_sudo: bool,
+    ) -> Option<process::Command> {
+        Ubuntu::Apt.install_downloaded(package, dir, use_sudo)
+    }
+
+    fn programs() -> &'static [&'static str] {
+        &["apt", "apt-get", "dpkg"]
+    }
+
+    fn package_manager() -> Self {
+        Debian::Apt
+    }
+}
+
+/// Installs into the user's Nix profile; the system itself is changed through
+/// `configuration.nix`, not by this crate.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub enum NixOs {
+    /// `nix-env -iA nixos.<package>`, for channel-based profiles.
+    #[default]
+    NixEnv,
+    /// `nix profile install nixpkgs#<package>`, for profiles created by `nix profile`.
+    NixProfile,
+}
+
+impl NixOs {
+    fn command(&self, install: bool, package: &str) -> process::Command {
+        let packages = package.split_whitespace();
+        let mut command: process::Command;
+        match self {
+            NixOs::NixEnv => {
+                command = process::Command::new("nix-env");
+                match install {
+                    true => {
+                        command.arg("-iA");
+                        command.args(packages.map(|name| format!("nixos.{}", name)));
+                    }
+                    false => {
+                        command.arg("-e");
+                        command.args(packages);
+                    }
+                }
+            }
+            NixOs::NixProfile => {
+                command = process::Command::new("nix");
+                match install {
+                    true => {
+                        command.args(["profile", "install"]);
+                        command.args(packages.map(|name| format!("nixpkgs#{}", name)));
+                    }
+                    false => {
+                        command.args(["profile", "remove"]);
+                        command.args(packages);
+                    }
+                }
+            }
+        }
+        command
+    }
+}
+
+impl PackageInstaller for NixOs {
+    fn install_package(&self, package: &str, _use_sudo: bool) -> process::Command {
+        self.command(true, package)
+    }
+
+    fn remove_package(&self, package: &str, _use_sudo: bool) -> process::Command {
+        self.command(false, package)
+    }
+
+    fn list_installed_packages(&self) -> process::Command {
+        match self {
+            NixOs::NixEnv => {
+                let mut command = process::Command::new("nix-env");
+                command.arg("-q");
+                command
+            }
+            NixOs::NixProfile => {
+                let mut command = process::Command::new("sh");
+                command
+                    .arg("-c")
+                    .arg("nix profile list | sed -n 's/^Name: *//p'");
+                command
+            }
+        }
+    }
+
+    fn query_package(&self, package: &str) -> process::Command {
+        match self {
+            NixOs::NixEnv => {
+                let mut command = process::Command::new("nix-env");
+                command.arg("-q").arg(package);
+                command
+            }
+            NixOs::NixProfile => {
+                let mut command = process::Command::new("sh");
+                command
+                    .arg("-c")
+                    .arg("nix profile list | grep -qx \"Name: *$1\"")
+                    .args(["sh", package]);
+                command
+            }
+        }
+    }
+
+    /// Nix keeps generations instead of a transaction log, so there is nothing to parse.
+    fn transaction_log(&self) -> Option<&'static Path> {
+        None
+    }
+
+    fn programs() -> &'static [&'static str] {
+        &["nix-env", "nix"]
     }
 
     fn package_manager() -> Self {
-        todo!()
+        cached(&NIX_PACKAGE_MANAGER, Self::detect)
+    }
+}
+
+impl NixOs {
+    fn detect() -> Self {
+        let manifest =
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".nix-profile/manifest.json"));
+        if manifest.is_some_and(|manifest| manifest.exists()) {
+            NixOs::NixProfile
+        } else {
+            NixOs::NixEnv
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub enum Alpine {
+    #[default]
+    Apk,
+}
+
+impl Alpine {
+    fn apk(args: &[&str], package: &str, use_sudo: bool) -> process::Command {
+        let mut command: process::Command;
+
+        if use_sudo {
+            command = process::Command::new("sudo");
+            command.arg("apk");
+        } else {
+            command = process::Command::new("apk");
+        }
+
+        command.args(args);
+        command.args(package.split_whitespace());
+
+        command
+    }
+}
+
+impl PackageInstaller for Alpine {
+    /// `--no-cache` keeps the index out of the image when provisioning containers.
+    fn install_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Self::apk(&["add", "--no-cache"], package, use_sudo)
+    }
+
+    fn remove_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Self::apk(&["del"], package, use_sudo)
+    }
+
+    /// `/etc/apk/world` lists the packages that were asked for, not their dependencies.
+    fn list_installed_packages(&self) -> process::Command {
+        let mut command = process::Command::new("cat");
+        command.arg("/etc/apk/world");
+        command
+    }
+
+    fn query_package(&self, package: &str) -> process::Command {
+        let mut command = process::Command::new("apk");
+        command.args(["info", "-e"]).arg(package);
+        command
+    }
+
+    /// apk keeps no transaction log, so there is nothing to parse.
+    fn transaction_log(&self) -> Option<&'static Path> {
+        None
+    }
+
+    fn programs() -> &'static [&'static str] {
+        &["apk"]
+    }
+
+    fn package_manager() -> Self {
+        Alpine::Apk
+    }
+}
+
+/// emerge options, from the command's `package_options`; by default emerge never asks
+/// and shows build output.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Gentoo {
+    /// Passes `--ask=y` instead of `--ask=n`, to confirm each merge.
+    pub ask: bool,
+    /// Passes `--quiet-build`, hiding compiler output for long builds.
+    pub quiet_build: bool,
+}
+
+impl Gentoo {
+    fn emerge(&self, args: &[&str], package: &str, use_sudo: bool) -> process::Command {
+        let mut command: process::Command;
+
+        if use_sudo {
+            command = process::Command::new("sudo");
+            command.arg("emerge");
+        } else {
+            command = process::Command::new("emerge");
+        }
+
+        command.arg(if self.ask { "--ask=y" } else { "--ask=n" });
+        if self.quiet_build {
+            command.arg("--quiet-build");
+        }
+        command.args(args);
+        command.args(package.split_whitespace());
+
+        command
+    }
+}
+
+impl PackageInstaller for Gentoo {
+    /// `--noreplace` leaves packages that are already merged alone.
+    fn install_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        self.emerge(&["--noreplace"], package, use_sudo)
+    }
+
+    fn remove_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        self.emerge(&["--depclean"], package, use_sudo)
+    }
+
+    /// The world file lists the packages that were asked for, not their dependencies.
+    fn list_installed_packages(&self) -> process::Command {
+        let mut command = process::Command::new("cat");
+        command.arg("/var/lib/portage/world");
+        command
+    }
+
+    fn query_package(&self, package: &str) -> process::Command {
+        // The package database has one directory per merged `category/name-version`.
+        let mut command = process::Command::new("sh");
+        command
+            .arg("-c")
+            .arg("ls -d /var/db/pkg/*/\"${1#*/}\"-[0-9]* >/dev/null 2>&1")
+            .args(["sh", package]);
+        command
+    }
+
+    fn transaction_log(&self) -> Option<&'static Path> {
+        Some(Path::new("/var/log/emerge.log"))
+    }
+
+    fn programs() -> &'static [&'static str] {
+        &["emerge"]
+    }
+
+    fn configure(&mut self, options: &PackageOptions) {
+        self.ask = options.emerge_ask;
+        self.quiet_build = options.emerge_quiet_build;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub enum Fedora {
+    #[default]
+    Dnf,
+}
+
+impl Fedora {
+    fn dnf(action: &str, package: &str, use_sudo: bool) -> process::Command {
+        let mut command: process::Command;
+
+        if use_sudo {
+            command = process::Command::new("sudo");
+            command.arg("dnf");
+        } else {
+            command = process::Command::new("dnf");
+        }
+
+        command.args([action, "-y"]);
+        command.args(package.split_whitespace());
+
+        command
+    }
+}
+
+impl PackageInstaller for Fedora {
+    fn install_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Self::dnf("install", package, use_sudo)
+    }
+
+    fn remove_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Self::dnf("remove", package, use_sudo)
+    }
+
+    fn list_installed_packages(&self) -> process::Command {
+        let mut command = process::Command::new("dnf");
+        command.args([
+            "repoquery",
+            "--userinstalled",
+            "--queryformat",
+            "%{name}\\n",
+        ]);
+        command
+    }
+
+    fn query_package(&self, package: &str) -> process::Command {
+        let mut command = process::Command::new("rpm");
+        command.args(["-q", "--quiet"]).arg(package);
+        command
+    }
+
+    fn transaction_log(&self) -> Option<&'static Path> {
+        Some(Path::new("/var/log/dnf.rpm.log"))
+    }
+
+    fn programs() -> &'static [&'static str] {
+        &["dnf", "rpm"]
+    }
+
+    fn package_manager() -> Self {
+        Fedora::Dnf
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub enum OpenSuse {
+    #[default]
+    Zypper,
+}
+
+impl OpenSuse {
+    fn zypper(action: &str, package: &str, use_sudo: bool) -> process::Command {
+        let mut command: process::Command;
+
+        if use_sudo {
+            command = process::Command::new("sudo");
+            command.arg("zypper");
+        } else {
+            command = process::Command::new("zypper");
+        }
+
+        command.args(["--non-interactive", action]);
+        command.args(package.split_whitespace());
+
+        command
+    }
+}
+
+impl PackageInstaller for OpenSuse {
+    fn install_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Self::zypper("install", package, use_sudo)
+    }
+
+    fn remove_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Self::zypper("remove", package, use_sudo)
+    }
+
+    fn list_installed_packages(&self) -> process::Command {
+        // zypper has no "explicitly installed" query; it records the automatic ones.
+        let mut command = process::Command::new("sh");
+        command.arg("-c").arg(
+            "rpm -qa --queryformat '%{NAME}\\n' | sort -u | grep -vxFf /var/lib/zypp/AutoInstalled",
+        );
+        command
+    }
+
+    fn query_package(&self, package: &str) -> process::Command {
+        let mut command = process::Command::new("rpm");
+        command.args(["-q", "--quiet"]).arg(package);
+        command
+    }
+
+    fn transaction_log(&self) -> Option<&'static Path> {
+        Some(Path::new("/var/log/zypp/history"))
+    }
+
+    fn programs() -> &'static [&'static str] {
+        &["zypper", "rpm"]
+    }
+
+    fn package_manager() -> Self {
+        OpenSuse::Zypper
+    }
+}
+
+/// The tool that installs packages on RHEL and its rebuilds.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RhelTool {
+    /// RHEL 8 and later.
+    #[default]
+    Dnf,
+    /// RHEL and CentOS 7, which have no dnf.
+    Yum,
+}
+
+impl RhelTool {
+    fn detect() -> Self {
+        match Path::new("/usr/bin/dnf").exists() {
+            true => RhelTool::Dnf,
+            false => RhelTool::Yum,
+        }
+    }
+}
+
+/// RHEL, Rocky Linux, AlmaLinux and CentOS. Repositories that are disabled by
+/// default, such as `epel` or `crb`, are enabled per install from the command's
+/// `package_options`.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Rhel {
+    pub tool: RhelTool,
+    /// Passed as `--enablerepo=<repo>` to installs.
+    pub enable_repos: Vec<String>,
+}
+
+impl Rhel {
+    fn command(&self, action: &str, package: &str, use_sudo: bool) -> process::Command {
+        let program = match self.tool {
+            RhelTool::Dnf => "dnf",
+            RhelTool::Yum => "yum",
+        };
+        let mut command: process::Command;
+
+        if use_sudo {
+            command = process::Command::new("sudo");
+            command.arg(program);
+        } else {
+            command = process::Command::new(program);
+        }
+
+        command.args([action, "-y"]);
+        if action == "install" {
+            command.args(
+                self.enable_repos
+                    .iter()
+                    .map(|repo| format!("--enablerepo={}", repo)),
+            );
+        }
+        command.args(package.split_whitespace());
+
+        command
+    }
+}
+
+impl PackageInstaller for Rhel {
+    fn install_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        self.command("install", package, use_sudo)
+    }
+
+    fn remove_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        self.command("remove", package, use_sudo)
+    }
+
+    /// yum cannot tell explicitly installed packages apart, so it lists them all.
+    fn list_installed_packages(&self) -> process::Command {
+        match self.tool {
+            RhelTool::Dnf => Fedora::Dnf.list_installed_packages(),
+            RhelTool::Yum => {
+                let mut command = process::Command::new("rpm");
+                command.args(["-qa", "--queryformat", "%{NAME}\\n"]);
+                command
+            }
+        }
+    }
+
+    fn query_package(&self, package: &str) -> process::Command {
+        let mut command = process::Command::new("rpm");
+        command.args(["-q", "--quiet"]).arg(package);
+        command
+    }
+
+    /// Only dnf's rpm log is parsed; yum's transactions go unreported.
+    fn transaction_log(&self) -> Option<&'static Path> {
+        match self.tool {
+            RhelTool::Dnf => Some(Path::new("/var/log/dnf.rpm.log")),
+            RhelTool::Yum => None,
+        }
+    }
+
+    fn programs() -> &'static [&'static str] {
+        &["dnf", "yum", "rpm"]
+    }
+
+    fn configure(&mut self, options: &PackageOptions) {
+        self.enable_repos = options.enable_repos.clone();
+    }
+
+    fn package_manager() -> Self {
+        Rhel {
+            tool: cached(&RHEL_TOOL, RhelTool::detect),
+            enable_repos: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub enum Void {
+    #[default]
+    Xbps,
+}
+
+impl Void {
+    fn xbps(program: &str, flags: &str, package: &str, use_sudo: bool) -> process::Command {
+        let mut command: process::Command;
+
+        if use_sudo {
+            command = process::Command::new("sudo");
+            command.arg(program);
+        } else {
+            command = process::Command::new(program);
+        }
+
+        command.arg(flags);
+        command.args(package.split_whitespace());
+
+        command
+    }
+}
+
+impl PackageInstaller for Void {
+    /// `-S` syncs the repository index first, `-y` answers yes.
+    fn install_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Self::xbps("xbps-install", "-Sy", package, use_sudo)
+    }
+
+    /// `-R` also removes dependencies nothing else needs.
+    fn remove_package(&self, package: &str, use_sudo: bool) -> process::Command {
+        Self::xbps("xbps-remove", "-Ry", package, use_sudo)
+    }
+
+    fn list_installed_packages(&self) -> process::Command {
+        // `xbps-query -m` prints `name-version_revision`.
+        let mut command = process::Command::new("sh");
+        command.arg("-c").arg("xbps-query -m | sed 's/-[^-]*$//'");
+        command
+    }
+
+    fn query_package(&self, package: &str) -> process::Command {
+        let mut command = process::Command::new("xbps-query");
+        command.arg(package);
+        command
+    }
+
+    /// xbps logs to syslog only, so there is nothing to parse.
+    fn transaction_log(&self) -> Option<&'static Path> {
+        None
+    }
+
+    fn programs() -> &'static [&'static str] {
+        &["xbps-install", "xbps-remove"]
+    }
+
+    fn package_manager() -> Self {
+        Void::Xbps
     }
 }
 
@@ -144,16 +1231,341 @@ mod tests {
     fn test_display_linux_distribution() {
         assert_eq!(format!("{}", DistributionType::Ubuntu), "Ubuntu");
         assert_eq!(format!("{}", DistributionType::ArchLinux), "Arch Linux");
+        assert_eq!(format!("{}", DistributionType::Fedora), "Fedora");
+        assert_eq!(format!("{}", DistributionType::OpenSuse), "openSUSE");
+        assert_eq!(format!("{}", DistributionType::DebianLike), "Debian-like");
+        assert_eq!(format!("{}", DistributionType::NixOs), "NixOS");
+        assert_eq!(format!("{}", DistributionType::Alpine), "Alpine Linux");
+        assert_eq!(format!("{}", DistributionType::Gentoo), "Gentoo");
         assert_eq!(format!("{}", DistributionType::Unknown), "Unknown");
     }
 
+    #[test]
+    fn test_debian_like_matches_debian_and_ubuntu() {
+        let debian_like = DistributionType::DebianLike;
+        assert!(debian_like.matches(&DistributionType::Debian));
+        assert!(debian_like.matches(&DistributionType::Ubuntu));
+        assert!(!debian_like.matches(&DistributionType::ArchLinux));
+        assert!(!DistributionType::Debian.matches(&DistributionType::Ubuntu));
+        assert!(debian_like.matches(&DistributionType::RaspberryPiOs));
+        assert!(DistributionType::Debian.matches(&DistributionType::RaspberryPiOs));
+        assert!(!DistributionType::RaspberryPiOs.matches(&DistributionType::Debian));
+    }
+
+    #[test]
+    fn test_forced_distribution() {
+        assert_eq!(
+            forced_distribution(Some("ArchLinux")),
+            Some(DistributionType::ArchLinux)
+        );
+        assert_eq!(
+            forced_distribution(Some(" Fedora\n")),
+            Some(DistributionType::Fedora)
+        );
+        assert_eq!(forced_distribution(Some("Arch Linux")), None);
+        assert_eq!(forced_distribution(None), None);
+    }
+
+    #[test]
+    fn test_override_takes_precedence_and_is_restored() {
+        let detected = identify(None);
+        assert_eq!(identify(Some("Void")), DistributionType::Void);
+        {
+            let _arch = override_distribution(DistributionType::ArchLinux);
+            assert_eq!(identify(Some("Void")), DistributionType::ArchLinux);
+            {
+                let _gentoo = override_distribution(DistributionType::Gentoo);
+                assert_eq!(identify(None), DistributionType::Gentoo);
+            }
+            assert_eq!(identify(None), DistributionType::ArchLinux);
+            assert_eq!(distribution_id(), None);
+        }
+        assert_eq!(identify(None), detected);
+
+        // Other threads keep detecting.
+        let _arch = override_distribution(DistributionType::ArchLinux);
+        let elsewhere = std::thread::spawn(|| identify(None)).join().unwrap();
+        assert_eq!(elsewhere, detected);
+    }
+
+    #[test]
+    fn test_from_os_release() {
+        let detect = |content: &str| DistributionType::from_os_release(content);
+        assert_eq!(detect("ID=arch\n"), DistributionType::ArchLinux);
+        assert_eq!(
+            detect("ID=ubuntu\nID_LIKE=debian\n"),
+            DistributionType::Ubuntu
+        );
+        assert_eq!(
+            detect("ID=\"opensuse-tumbleweed\"\nID_LIKE=\"opensuse suse\"\n"),
+            DistributionType::OpenSuse
+        );
+        assert_eq!(
+            detect("ID=linuxmint\nID_LIKE=\"ubuntu debian\"\n"),
+            DistributionType::Ubuntu
+        );
+        assert_eq!(
+            detect("ID=endeavouros\nID_LIKE=arch\n"),
+            DistributionType::ArchLinux
+        );
+        assert_eq!(detect("ID=pop\n"), DistributionType::Ubuntu);
+        assert_eq!(
+            detect("ID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\n"),
+            DistributionType::Rhel
+        );
+        assert_eq!(detect("ID=\"almalinux\"\n"), DistributionType::Rhel);
+        assert_eq!(detect("ID=\"void\"\n"), DistributionType::Void);
+        assert_eq!(
+            detect("ID=raspbian\nID_LIKE=debian\n"),
+            DistributionType::RaspberryPiOs
+        );
+        assert_eq!(detect("ID=plan9\n"), DistributionType::Unknown);
+    }
+
+    #[test]
+    fn test_os_release_field() {
+        let content = "NAME=\"Fedora Linux\"\nVERSION_ID=40\nID=fedora\n";
+        assert_eq!(os_release_field(content, "ID"), Some("fedora"));
+        assert_eq!(os_release_field(content, "NAME"), Some("Fedora Linux"));
+        assert_eq!(os_release_field(content, "ID_LIKE"), None);
+    }
+
+    #[test]
+    fn test_package_installer() {
+        let installer = DistributionType::DebianLike.package_installer().unwrap();
+        let remove = installer.remove_package("git vim", true);
+        assert_eq!(
+            remove.get_args().collect::<Vec<_>>(),
+            ["apt", "remove", "-y", "git", "vim"]
+        );
+        assert_eq!(
+            installer.transaction_log(),
+            Some(Path::new("/var/log/apt/history.log"))
+        );
+
+        let alpine = DistributionType::Alpine.package_installer().unwrap();
+        assert_eq!(alpine.query_package("git").get_program(), "apk");
+        assert_eq!(alpine.transaction_log(), None);
+        assert!(DistributionType::Unknown.package_installer().is_none());
+    }
+
+    #[test]
+    fn test_install_commands_run_known_programs() {
+        let installers: [Box<dyn PackageInstaller>; 11] = [
+            Box::new(ArchLinux::Pacman),
+            Box::new(ArchLinux::Yay),
+            Box::new(Ubuntu::Apt),
+            Box::new(Debian::Apt),
+            Box::new(Fedora::Dnf),
+            Box::new(OpenSuse::Zypper),
+            Box::new(NixOs::NixEnv),
+            Box::new(Alpine::Apk),
+            Box::new(Gentoo::default()),
+            Box::new(Rhel::default()),
+            Box::new(Void::Xbps),
+        ];
+        for installer in installers {
+            for use_sudo in [false, true] {
+                let command = installer.install_package("git", use_sudo);
+                let mut words = std::iter::once(command.get_program()).chain(command.get_args());
+                let program = words.find(|word| *word != "sudo").unwrap();
+                assert!(
+                    is_package_manager(program.to_str().unwrap()),
+                    "{:?}",
+                    command
+                );
+            }
+        }
+        assert!(!is_package_manager("git"));
+    }
+
+    #[test]
+    fn test_install_several_packages() {
+        let pacman = ArchLinux::Pacman.install_package("git vim zsh", false);
+        assert_eq!(
+            pacman.get_args().collect::<Vec<_>>(),
+            ["-S", "--noconfirm", "--needed", "git", "vim", "zsh"]
+        );
+        let apt = Debian::Apt.install_package("git vim", true);
+        assert_eq!(
+            apt.get_args().collect::<Vec<_>>(),
+            ["apt", "install", "-y", "git", "vim"]
+        );
+    }
+
+    #[test]
+    fn test_download_packages_for_a_bundle() {
+        let dir = Path::new("/bundle/packages");
+        let download = Ubuntu::Apt.download_package("git vim", dir, true).unwrap();
+        assert_eq!(
+            download.get_args().collect::<Vec<_>>(),
+            [
+                "apt-get",
+                "install",
+                "-y",
+                "--download-only",
+                "-o",
+                "Dir::Cache::archives=/bundle/packages",
+                "git",
+                "vim"
+            ]
+        );
+        let install = ArchLinux::Yay
+            .install_downloaded("git", dir, false)
+            .unwrap();
+        assert_eq!(install.get_program(), "pacman");
+        assert_eq!(
+            install.get_args().collect::<Vec<_>>(),
+            [
+                "-S",
+                "--noconfirm",
+                "--needed",
+                "--cachedir",
+                "/bundle/packages",
+                "git"
+            ]
+        );
+        assert!(Alpine::Apk.download_package("git", dir, false).is_none());
+    }
+
+    #[test]
+    fn test_fedora_dnf_commands() {
+        let install = Fedora::Dnf.install_package("git zsh", true);
+        assert_eq!(install.get_program(), "sudo");
+        let args: Vec<_> = install.get_args().collect();
+        assert_eq!(args, ["dnf", "install", "-y", "git", "zsh"]);
+
+        let remove = Fedora::Dnf.remove_package("git", false);
+        assert_eq!(remove.get_program(), "dnf");
+        assert_eq!(
+            remove.get_args().collect::<Vec<_>>(),
+            ["remove", "-y", "git"]
+        );
+    }
+
+    #[test]
+    fn test_nix_commands() {
+        let install = NixOs::NixEnv.command(true, "ripgrep fd");
+        assert_eq!(install.get_program(), "nix-env");
+        let args: Vec<_> = install.get_args().collect();
+        assert_eq!(args, ["-iA", "nixos.ripgrep", "nixos.fd"]);
+
+        let install = NixOs::NixProfile.command(true, "ripgrep");
+        assert_eq!(install.get_program(), "nix");
+        let args: Vec<_> = install.get_args().collect();
+        assert_eq!(args, ["profile", "install", "nixpkgs#ripgrep"]);
+    }
+
+    #[test]
+    fn test_alpine_apk_commands() {
+        let install = Alpine::Apk.install_package("git openssh", false);
+        assert_eq!(install.get_program(), "apk");
+        let args: Vec<_> = install.get_args().collect();
+        assert_eq!(args, ["add", "--no-cache", "git", "openssh"]);
+    }
+
+    #[test]
+    fn test_gentoo_emerge_commands() {
+        let install = Gentoo::default().emerge(&["--noreplace"], "dev-vcs/git", true);
+        assert_eq!(install.get_program(), "sudo");
+        let args: Vec<_> = install.get_args().collect();
+        assert_eq!(args, ["emerge", "--ask=n", "--noreplace", "dev-vcs/git"]);
+
+        let quiet = Gentoo {
+            ask: true,
+            quiet_build: true,
+        };
+        let args: Vec<_> = quiet
+            .emerge(&["--depclean"], "git", false)
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(args, ["--ask=y", "--quiet-build", "--depclean", "git"]);
+    }
+
+    #[test]
+    fn test_opensuse_zypper_commands() {
+        let install = OpenSuse::Zypper.install_package("git", false);
+        assert_eq!(install.get_program(), "zypper");
+        let args: Vec<_> = install.get_args().collect();
+        assert_eq!(args, ["--non-interactive", "install", "git"]);
+    }
+
+    #[test]
+    fn test_rhel_commands() {
+        let rhel = Rhel {
+            tool: RhelTool::Dnf,
+            enable_repos: vec!["epel".to_string(), "crb".to_string()],
+        };
+        let install = rhel.install_package("htop", true);
+        assert_eq!(
+            install.get_args().collect::<Vec<_>>(),
+            [
+                "dnf",
+                "install",
+                "-y",
+                "--enablerepo=epel",
+                "--enablerepo=crb",
+                "htop"
+            ]
+        );
+        let remove = rhel.remove_package("htop", false);
+        assert_eq!(
+            remove.get_args().collect::<Vec<_>>(),
+            ["remove", "-y", "htop"]
+        );
+
+        let yum = Rhel {
+            tool: RhelTool::Yum,
+            enable_repos: Vec::new(),
+        };
+        assert_eq!(yum.install_package("git", false).get_program(), "yum");
+        assert_eq!(yum.transaction_log(), None);
+    }
+
+    #[test]
+    fn test_void_xbps_commands() {
+        let install = Void::Xbps.install_package("git curl", true);
+        assert_eq!(
+            install.get_args().collect::<Vec<_>>(),
+            ["xbps-install", "-Sy", "git", "curl"]
+        );
+        let remove = Void::Xbps.remove_package("git", false);
+        assert_eq!(remove.get_program(), "xbps-remove");
+        assert_eq!(remove.get_args().collect::<Vec<_>>(), ["-Ry", "git"]);
+    }
+
     #[test]
     fn test_identify_linux_distribution() {
         // This test is environment-dependent and may need to be adjusted based on the actual system
         let distro = identify_linux_distribution();
+        let facts = (
+            distribution_id(),
+            crate::distribution::distribution_version(),
+        );
+        refresh();
+        assert_eq!(identify_linux_distribution(), distro);
+        assert_eq!(
+            (
+                distribution_id(),
+                crate::distribution::distribution_version()
+            ),
+            facts
+        );
         assert!(matches!(
             distro,
-            DistributionType::Ubuntu | DistributionType::ArchLinux | DistributionType::Unknown
+            DistributionType::Ubuntu
+                | DistributionType::ArchLinux
+                | DistributionType::Fedora
+                | DistributionType::OpenSuse
+                | DistributionType::Debian
+                | DistributionType::NixOs
+                | DistributionType::Alpine
+                | DistributionType::Gentoo
+                | DistributionType::RaspberryPiOs
+                | DistributionType::Rhel
+                | DistributionType::Void
+                | DistributionType::Unknown
         ));
     }
 }
@@ -1,7 +1,42 @@
+mod container;
+mod cpu;
+mod custom;
 mod linux_distributor;
+mod raspberry_pi;
+mod release;
+mod release_upgrade;
+mod transaction;
+mod wsl;
 
+pub use container::{container_runtime, is_container};
+pub use cpu::{arch_skip, cpu_architecture, normalize_arch, ARCHITECTURES};
+pub use custom::{
+    custom_distribution, declare_distributions, CustomDistribution, DeclaredDistributions,
+};
+pub use linux_distributor::distribution_id;
 pub use linux_distributor::identify_linux_distribution;
+pub use linux_distributor::is_package_manager;
+pub use linux_distributor::Alpine;
 pub use linux_distributor::ArchLinux;
+pub use linux_distributor::Debian;
 pub use linux_distributor::DistributionType;
+pub use linux_distributor::Fedora;
+pub use linux_distributor::Gentoo;
+pub use linux_distributor::NixOs;
+pub use linux_distributor::OpenSuse;
 pub use linux_distributor::PackageInstaller;
+pub use linux_distributor::PackageOptions;
 pub use linux_distributor::Ubuntu;
+pub use linux_distributor::Void;
+pub use linux_distributor::{
+    current_override, override_distribution, refresh, DistributionOverride, FORCE_DISTRO_VAR,
+};
+pub use linux_distributor::{Rhel, RhelTool};
+pub use raspberry_pi::{is_raspberry_pi, raspberry_pi_model};
+pub use release::{distribution_version, release_skip};
+pub use release_upgrade::{release_upgrade_state, ReleaseUpgradeState};
+pub use transaction::{
+    parse_apt_history, parse_dnf_rpm_log, parse_emerge_log, parse_pacman_log, parse_zypp_history,
+    LogCursor, PackageOutcome, PackageResult,
+};
+pub use wsl::{is_wsl, windows_to_wsl_path};
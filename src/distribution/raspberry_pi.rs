@@ -0,0 +1,48 @@
+use std::fs;
+use std::sync::{OnceLock, RwLock};
+
+use super::linux_distributor::{cached, recache};
+
+/// The board model the firmware reports, NUL-terminated.
+const DEVICE_TREE_MODEL: &str = "/proc/device-tree/model";
+
+/// The model string with its trailing NUL removed, if it names a Raspberry Pi.
+fn pi_model(model: &str) -> Option<&str> {
+    let model = model.trim_end_matches('\0').trim();
+    model.starts_with("Raspberry Pi").then_some(model)
+}
+
+static MODEL: OnceLock<RwLock<Option<String>>> = OnceLock::new();
+
+/// The Raspberry Pi model this runs on, e.g. `Raspberry Pi 4 Model B Rev 1.4`, from the
+/// device tree. `None` on other hardware, whatever the distribution. Read once, see `refresh`.
+pub fn raspberry_pi_model() -> Option<String> {
+    cached(&MODEL, detect_model)
+}
+
+pub(super) fn refresh() {
+    recache(&MODEL, detect_model);
+}
+
+fn detect_model() -> Option<String> {
+    let model = fs::read_to_string(DEVICE_TREE_MODEL).ok()?;
+    pi_model(&model).map(str::to_string)
+}
+
+pub fn is_raspberry_pi() -> bool {
+    raspberry_pi_model().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pi_model() {
+        assert_eq!(
+            pi_model("Raspberry Pi 4 Model B Rev 1.4\0"),
+            Some("Raspberry Pi 4 Model B Rev 1.4")
+        );
+        assert_eq!(pi_model("Pine64 RockPro64 v2.1\0"), None);
+    }
+}
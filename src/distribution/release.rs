@@ -0,0 +1,68 @@
+use std::fs;
+use std::sync::{OnceLock, RwLock};
+
+use super::linux_distributor::{cached, is_overridden, os_release_field, read_os_release, recache};
+use crate::check::parse_version;
+use crate::utils::SkipReason;
+
+static VERSION: OnceLock<RwLock<Option<String>>> = OnceLock::new();
+
+/// The release of the running distribution, e.g. `24.04` or `40`, from os-release
+/// `VERSION_ID` or lsb-release `DISTRIB_RELEASE`. Rolling releases have none, and
+/// neither does an overridden distribution, whose release the host cannot tell.
+/// Read once, see `refresh`.
+pub fn distribution_version() -> Option<String> {
+    if is_overridden() {
+        return None;
+    }
+    cached(&VERSION, detect_version)
+}
+
+pub(super) fn refresh() {
+    recache(&VERSION, detect_version);
+}
+
+fn detect_version() -> Option<String> {
+    let os_release = read_os_release()
+        .and_then(|content| os_release_field(&content, "VERSION_ID").map(str::to_string));
+    os_release.or_else(|| {
+        let content = fs::read_to_string("/etc/lsb-release").ok()?;
+        os_release_field(&content, "DISTRIB_RELEASE").map(str::to_string)
+    })
+}
+
+/// Whether `version` lies within `min..=max`; an unknown version is in no range.
+fn in_range(version: Option<&str>, min: Option<&str>, max: Option<&str>) -> bool {
+    let Some(version) = version.and_then(parse_version) else {
+        return false;
+    };
+    min.and_then(parse_version).is_none_or(|min| version >= min)
+        && max.and_then(parse_version).is_none_or(|max| version <= max)
+}
+
+/// Why something limited to the releases `min_version..=max_version` does not run here.
+pub fn release_skip(min: Option<&str>, max: Option<&str>) -> Option<SkipReason> {
+    if min.is_none() && max.is_none() {
+        return None;
+    }
+    let detected = distribution_version();
+    (!in_range(detected.as_deref(), min, max)).then(|| SkipReason::ReleaseMismatch {
+        min: min.map(str::to_string),
+        max: max.map(str::to_string),
+        detected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_range() {
+        assert!(in_range(Some("24.04"), Some("22.04"), None));
+        assert!(in_range(Some("24.04"), Some("24.04"), Some("24.10")));
+        assert!(!in_range(Some("20.04"), Some("22.04"), None));
+        assert!(!in_range(Some("41"), None, Some("40")));
+        assert!(!in_range(None, Some("1"), None));
+    }
+}
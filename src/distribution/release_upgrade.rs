@@ -0,0 +1,126 @@
+use std::{fs, path::Path};
+
+const UPGRADE_AVAILABLE: &str = "/var/lib/ubuntu-release-upgrader/release-upgrade-available";
+/// Programs that perform the upgrade. update-notifier's `check-new-release` helpers, which
+/// only look for a new release, live next to them and must not count.
+const UPGRADERS: [&str; 2] = ["do-release-upgrade", "dist-upgrade.py"];
+/// Prefix of the directory `do-release-upgrade` unpacks the new release's frontend into.
+const UPGRADER_TMPDIR: &str = "ubuntu-release-upgrader-";
+
+/// Where an Ubuntu release upgrade stands on this machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReleaseUpgradeState {
+    InProgress,
+    /// A new release is available; holds the upgrader's notice when present.
+    Pending(String),
+    None,
+}
+
+/// Checks for a running release upgrader first, then for the notice left by `update-motd`.
+pub fn release_upgrade_state() -> ReleaseUpgradeState {
+    upgrade_state(upgrader_running(), Path::new(UPGRADE_AVAILABLE))
+}
+
+fn upgrader_running() -> bool {
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return false;
+    };
+    processes.flatten().any(|process| {
+        fs::read(process.path().join("cmdline")).is_ok_and(|cmdline| {
+            let cmdline = String::from_utf8_lossy(&cmdline);
+            let argv: Vec<&str> = cmdline.split('\0').filter(|arg| !arg.is_empty()).collect();
+            is_upgrader(&argv)
+        })
+    })
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|name| name.to_str())
+}
+
+/// Whether a process with this `argv` is the release upgrader: `do-release-upgrade`, the
+/// `dist-upgrade.py` frontend, or the frontend unpacked into `ubuntu-release-upgrader-*`,
+/// which runs under the new release's code name. Scripts count when run through python.
+fn is_upgrader(argv: &[&str]) -> bool {
+    let mut program = argv.first().map(Path::new);
+    if program
+        .and_then(file_name)
+        .is_some_and(|name| name.starts_with("python"))
+    {
+        program = argv[1..]
+            .iter()
+            .find(|arg| !arg.starts_with('-'))
+            .map(Path::new);
+    }
+    let Some(program) = program else {
+        return false;
+    };
+
+    file_name(program).is_some_and(|name| UPGRADERS.contains(&name))
+        || program
+            .parent()
+            .and_then(file_name)
+            .is_some_and(|dir| dir.starts_with(UPGRADER_TMPDIR))
+}
+
+/// The state given whether an upgrader is running and where its notice would be.
+fn upgrade_state(running: bool, notice: &Path) -> ReleaseUpgradeState {
+    if running {
+        return ReleaseUpgradeState::InProgress;
+    }
+
+    if notice.exists() {
+        let notice = fs::read_to_string(notice).unwrap_or_default();
+        return ReleaseUpgradeState::Pending(notice.trim().to_string());
+    }
+
+    ReleaseUpgradeState::None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_state() {
+        let notice = std::env::temp_dir().join(format!("lsu-upgrade-{}", std::process::id()));
+        assert_eq!(upgrade_state(false, &notice), ReleaseUpgradeState::None);
+
+        fs::write(&notice, "New release '24.04 LTS' available.\n").unwrap();
+        assert_eq!(
+            upgrade_state(false, &notice),
+            ReleaseUpgradeState::Pending("New release '24.04 LTS' available.".to_string())
+        );
+        assert_eq!(
+            upgrade_state(true, &notice),
+            ReleaseUpgradeState::InProgress
+        );
+        fs::remove_file(notice).unwrap();
+    }
+
+    #[test]
+    fn test_is_upgrader() {
+        assert!(is_upgrader(&[
+            "/usr/bin/python3",
+            "/usr/bin/do-release-upgrade"
+        ]));
+        assert!(is_upgrader(&["do-release-upgrade", "-d"]));
+        assert!(is_upgrader(&[
+            "/usr/bin/python3",
+            "-s",
+            "/tmp/ubuntu-release-upgrader-x1y2z3/noble",
+            "--mode=server",
+        ]));
+        assert!(is_upgrader(&["python3", "./dist-upgrade.py"]));
+
+        assert!(!is_upgrader(&[
+            "/usr/bin/python3",
+            "/usr/lib/ubuntu-release-upgrader/check-new-release-gtk",
+        ]));
+        assert!(!is_upgrader(&[
+            "vim",
+            "/usr/lib/ubuntu-release-upgrader/do-release-upgrade",
+        ]));
+        assert!(!is_upgrader(&[]));
+    }
+}
@@ -0,0 +1,382 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use super::DistributionType;
+
+/// What the package manager did to a package during one command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageOutcome {
+    Installed,
+    Upgraded {
+        from: String,
+    },
+    Reinstalled,
+    /// Requested, succeeded, but not in the transaction log: it was already installed.
+    AlreadyPresent,
+    /// Requested, but the command failed before the package was installed.
+    Failed,
+}
+
+/// The result for one requested or changed package, with its resulting version when logged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageResult {
+    pub name: String,
+    pub outcome: PackageOutcome,
+    pub version: Option<String>,
+}
+
+/// Remembers where a transaction log ended before a command ran, so only the lines
+/// that command appended are parsed afterwards.
+#[derive(Debug, Clone)]
+pub struct LogCursor {
+    distribution: DistributionType,
+    path: PathBuf,
+    offset: u64,
+}
+
+impl LogCursor {
+    pub fn open(distribution: &DistributionType) -> Option<Self> {
+        let path = distribution.package_installer()?.transaction_log()?;
+        let offset = path.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        Some(LogCursor {
+            distribution: distribution.clone(),
+            path: path.to_path_buf(),
+            offset,
+        })
+    }
+
+    fn appended(&self) -> io::Result<String> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut text = String::new();
+        file.read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    /// Per-package results for `requested`: logged changes, plus `AlreadyPresent`
+    /// (or `Failed` when `succeeded` is false) for requested packages the log never mentions.
+    pub fn results(&self, requested: &[&str], succeeded: bool) -> Vec<PackageResult> {
+        let appended = self.appended().unwrap_or_default();
+        let mut results = match self.distribution {
+            DistributionType::ArchLinux => parse_pacman_log(&appended),
+            DistributionType::Ubuntu
+            | DistributionType::Debian
+            | DistributionType::DebianLike
+            | DistributionType::RaspberryPiOs => parse_apt_history(&appended),
+            DistributionType::Fedora | DistributionType::Rhel => parse_dnf_rpm_log(&appended),
+            DistributionType::OpenSuse => parse_zypp_history(&appended),
+            DistributionType::Gentoo => parse_emerge_log(&appended),
+            DistributionType::NixOs
+            | DistributionType::Alpine
+            | DistributionType::Void
+            | DistributionType::Unknown
+            | DistributionType::Custom(_) => Vec::new(),
+        };
+
+        for name in requested {
+            if !results.iter().any(|result| result.name == *name) {
+                results.push(PackageResult {
+                    name: name.to_string(),
+                    outcome: if succeeded {
+                        PackageOutcome::AlreadyPresent
+                    } else {
+                        PackageOutcome::Failed
+                    },
+                    version: None,
+                });
+            }
+        }
+        results
+    }
+}
+
+/// Parses `[ALPM] installed git (2.43.0-1)` style lines from `/var/log/pacman.log`.
+pub fn parse_pacman_log(text: &str) -> Vec<PackageResult> {
+    text.lines()
+        .filter_map(|line| {
+            let (_, event) = line.split_once("[ALPM] ")?;
+            let (action, rest) = event.split_once(' ')?;
+            let (name, versions) = rest.split_once(" (")?;
+            let versions = versions.strip_suffix(')')?;
+            let (outcome, version) = match action {
+                "installed" => (PackageOutcome::Installed, versions),
+                "reinstalled" => (PackageOutcome::Reinstalled, versions),
+                "upgraded" | "downgraded" => {
+                    let (from, to) = versions.split_once(" -> ")?;
+                    (
+                        PackageOutcome::Upgraded {
+                            from: from.to_string(),
+                        },
+                        to,
+                    )
+                }
+                _ => return None,
+            };
+            Some(PackageResult {
+                name: name.to_string(),
+                outcome,
+                version: Some(version.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Splits `git:amd64 (1:2.34.1-1), zsh:amd64 (5.8-6, 5.8.1-1)` into names and
+/// the text inside each pair of parentheses.
+fn apt_packages(list: &str) -> Vec<(String, String)> {
+    let mut packages = Vec::new();
+    let mut rest = list.trim();
+    while let Some((name, tail)) = rest.split_once(" (") {
+        let Some((inside, tail)) = tail.split_once(')') else {
+            break;
+        };
+        let name = name.split(':').next().unwrap_or(name).trim();
+        packages.push((name.to_string(), inside.to_string()));
+        rest = tail.trim_start_matches(',').trim_start();
+    }
+    packages
+}
+
+/// Parses the `Install:`, `Upgrade:` and `Reinstall:` lines of `/var/log/apt/history.log`.
+pub fn parse_apt_history(text: &str) -> Vec<PackageResult> {
+    let mut results = Vec::new();
+    for line in text.lines() {
+        let Some((field, list)) = line.split_once(": ") else {
+            continue;
+        };
+        for (name, inside) in apt_packages(list) {
+            let mut versions = inside.split(", ").filter(|part| *part != "automatic");
+            let (outcome, version) = match field {
+                "Install" => (PackageOutcome::Installed, versions.next()),
+                "Reinstall" => (PackageOutcome::Reinstalled, versions.next()),
+                "Upgrade" | "Downgrade" => {
+                    let from = versions.next().unwrap_or_default().to_string();
+                    (PackageOutcome::Upgraded { from }, versions.next())
+                }
+                _ => continue,
+            };
+            results.push(PackageResult {
+                name,
+                outcome,
+                version: version.map(str::to_string),
+            });
+        }
+    }
+    results
+}
+
+/// Splits `git-2.44.0-1.fc40.x86_64` into the name and `version-release`.
+fn split_nevra(nevra: &str) -> Option<(&str, &str)> {
+    let (nevr, _arch) = nevra.rsplit_once('.')?;
+    let (nev, _release) = nevr.rsplit_once('-')?;
+    let (name, _version) = nev.rsplit_once('-')?;
+    Some((name, &nevr[name.len() + 1..]))
+}
+
+/// Parses the `Installed:`, `Upgrade:`/`Upgraded:` and `Reinstall:` lines of
+/// `/var/log/dnf.rpm.log`.
+pub fn parse_dnf_rpm_log(text: &str) -> Vec<PackageResult> {
+    let events: Vec<(&str, &str, &str)> = text
+        .lines()
+        .filter_map(|line| {
+            let (_, event) = line.split_once(" SUBDEBUG ")?;
+            let (action, nevra) = event.split_once(": ")?;
+            let (name, version) = split_nevra(nevra.trim())?;
+            Some((action, name, version))
+        })
+        .collect();
+
+    events
+        .iter()
+        .filter_map(|&(action, name, version)| {
+            let outcome = match action {
+                "Installed" => PackageOutcome::Installed,
+                "Reinstall" => PackageOutcome::Reinstalled,
+                "Upgrade" | "Downgrade" => {
+                    let from = events
+                        .iter()
+                        .find(|&&(old, old_name, _)| {
+                            matches!(old, "Upgraded" | "Downgraded") && old_name == name
+                        })
+                        .map(|&(_, _, from)| from.to_string())
+                        .unwrap_or_default();
+                    PackageOutcome::Upgraded { from }
+                }
+                _ => return None,
+            };
+            Some(PackageResult {
+                name: name.to_string(),
+                outcome,
+                version: Some(version.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Parses the `install` lines of `/var/log/zypp/history`, e.g.
+/// `2024-05-02 10:00:00|install|git|2.45.0-1.1|x86_64|root@host|repo-oss|...`.
+/// zypper logs upgrades as installs without the old version.
+pub fn parse_zypp_history(text: &str) -> Vec<PackageResult> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('|').skip(1);
+            if fields.next()?.trim() != "install" {
+                return None;
+            }
+            let name = fields.next()?.trim();
+            let version = fields.next()?.trim();
+            Some(PackageResult {
+                name: name.to_string(),
+                outcome: PackageOutcome::Installed,
+                version: Some(version.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Parses `1714550000:  ::: completed emerge (1 of 2) dev-vcs/git-2.44.0-r1 to /` lines
+/// of `/var/log/emerge.log`. The log does not say whether a merge replaced an older
+/// version, so every merge counts as installed; names drop their category.
+pub fn parse_emerge_log(text: &str) -> Vec<PackageResult> {
+    text.lines()
+        .filter_map(|line| {
+            let (_, merged) = line.split_once("::: completed emerge (")?;
+            let (_, atom) = merged.split_once(") ")?;
+            let atom = atom.split_whitespace().next()?;
+            let atom = atom.split_once('/').map_or(atom, |(_, atom)| atom);
+            let split = atom
+                .match_indices('-')
+                .find(|(index, _)| atom[index + 1..].starts_with(|c: char| c.is_ascii_digit()))?
+                .0;
+            Some(PackageResult {
+                name: atom[..split].to_string(),
+                outcome: PackageOutcome::Installed,
+                version: Some(atom[split + 1..].to_string()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pacman_log() {
+        let log = "\
+[2024-03-01T10:00:00+0000] [PACMAN] Running 'pacman -S --noconfirm --needed git'
+[2024-03-01T10:00:01+0000] [ALPM] installed perl-error (0.17029-5)
+[2024-03-01T10:00:01+0000] [ALPM] upgraded git (2.43.0-1 -> 2.44.0-1)
+[2024-03-01T10:00:02+0000] [ALPM] running '30-systemd-update.hook'...";
+
+        assert_eq!(
+            parse_pacman_log(log),
+            [
+                PackageResult {
+                    name: "perl-error".to_string(),
+                    outcome: PackageOutcome::Installed,
+                    version: Some("0.17029-5".to_string()),
+                },
+                PackageResult {
+                    name: "git".to_string(),
+                    outcome: PackageOutcome::Upgraded {
+                        from: "2.43.0-1".to_string()
+                    },
+                    version: Some("2.44.0-1".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_dnf_rpm_log() {
+        let log = "\
+2024-04-20T10:00:00+0000 SUBDEBUG Installed: perl-Error-1:0.17029-15.fc40.noarch
+2024-04-20T10:00:01+0000 SUBDEBUG Upgrade: git-2.45.0-1.fc40.x86_64
+2024-04-20T10:00:02+0000 SUBDEBUG Upgraded: git-2.44.0-1.fc40.x86_64
+2024-04-20T10:00:02+0000 INFO --- logging initialized ---";
+
+        assert_eq!(
+            parse_dnf_rpm_log(log),
+            [
+                PackageResult {
+                    name: "perl-Error".to_string(),
+                    outcome: PackageOutcome::Installed,
+                    version: Some("1:0.17029-15.fc40".to_string()),
+                },
+                PackageResult {
+                    name: "git".to_string(),
+                    outcome: PackageOutcome::Upgraded {
+                        from: "2.44.0-1.fc40".to_string()
+                    },
+                    version: Some("2.45.0-1.fc40".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_emerge_log() {
+        let log = "\
+1714550000: Started emerge on: May 01, 2024 08:00:00
+1714550100:  >>> emerge (1 of 1) dev-vcs/git-2.44.0-r1 to /
+1714550160:  ::: completed emerge (1 of 1) dev-vcs/git-2.44.0-r1 to /";
+
+        assert_eq!(
+            parse_emerge_log(log),
+            [PackageResult {
+                name: "git".to_string(),
+                outcome: PackageOutcome::Installed,
+                version: Some("2.44.0-r1".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_zypp_history() {
+        let log = "\
+# 2024-05-02 10:00:00 zypper --non-interactive install git
+2024-05-02 10:00:03|install|git|2.45.0-1.1|x86_64|root@tw|repo-oss|0f3b...|
+2024-05-02 10:00:04|remove |vim-small|9.1.0330-1.1|x86_64|root@tw|";
+
+        assert_eq!(
+            parse_zypp_history(log),
+            [PackageResult {
+                name: "git".to_string(),
+                outcome: PackageOutcome::Installed,
+                version: Some("2.45.0-1.1".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_apt_history() {
+        let log = "\
+Start-Date: 2024-03-01  10:00:00
+Commandline: apt install -y git
+Install: liberror-perl:amd64 (0.17029-1, automatic), git:amd64 (1:2.34.1-1ubuntu1.10)
+Upgrade: zsh:amd64 (5.8.1-1, 5.8.1-1ubuntu1)
+End-Date: 2024-03-01  10:00:05";
+
+        let results = parse_apt_history(log);
+        let summary: Vec<_> = results
+            .iter()
+            .map(|r| (r.name.as_str(), r.version.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("liberror-perl", Some("0.17029-1")),
+                ("git", Some("1:2.34.1-1ubuntu1.10")),
+                ("zsh", Some("5.8.1-1ubuntu1")),
+            ]
+        );
+        assert_eq!(
+            results[2].outcome,
+            PackageOutcome::Upgraded {
+                from: "5.8.1-1".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::{OnceLock, RwLock};
+use std::{env, fs};
+
+use super::linux_distributor::{cached, recache};
+
+static WSL: OnceLock<RwLock<bool>> = OnceLock::new();
+
+/// Detects Windows Subsystem for Linux via `WSL_DISTRO_NAME` or the kernel release string.
+/// Detected once, see `refresh`.
+pub fn is_wsl() -> bool {
+    cached(&WSL, detect_wsl)
+}
+
+pub(super) fn refresh() {
+    recache(&WSL, detect_wsl);
+}
+
+fn detect_wsl() -> bool {
+    if env::var_os("WSL_DISTRO_NAME").is_some() {
+        return true;
+    }
+
+    fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|release| {
+            let release = release.to_lowercase();
+            release.contains("microsoft") || release.contains("wsl")
+        })
+        .unwrap_or(false)
+}
+
+/// Converts a Windows path such as `C:\Users\me` into its `/mnt/c/Users/me` mount point.
+/// Paths that are not drive-qualified are returned unchanged.
+pub fn windows_to_wsl_path(path: &str) -> String {
+    let mut chars = path.chars();
+    match (chars.next(), chars.next()) {
+        (Some(drive), Some(':')) if drive.is_ascii_alphabetic() => {
+            let rest = chars.as_str().replace('\\', "/");
+            format!(
+                "/mnt/{}/{}",
+                drive.to_ascii_lowercase(),
+                rest.trim_start_matches('/')
+            )
+        }
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_to_wsl_path() {
+        assert_eq!(
+            windows_to_wsl_path(r"C:\Users\me\Documents"),
+            "/mnt/c/Users/me/Documents"
+        );
+        assert_eq!(windows_to_wsl_path("D:"), "/mnt/d/");
+        assert_eq!(windows_to_wsl_path("/mnt/c/tools"), "/mnt/c/tools");
+    }
+}
@@ -0,0 +1,255 @@
+use std::path::Path;
+
+use super::{RunObserver, TerminalTitle};
+use crate::condition::Facts;
+use crate::distribution::override_distribution;
+use crate::report::RunReport;
+use crate::setup::Plan;
+use crate::state::StateStore;
+use crate::utils::Status;
+use crate::{DistributionType, RegistryError, SetupEntry, SetupRegistry};
+
+fn selects(
+    entry: &SetupEntry,
+    profile: &Option<String>,
+    include: &[String],
+    exclude: &[String],
+) -> bool {
+    profile
+        .as_deref()
+        .is_none_or(|profile| entry.matches_profile(profile))
+        && entry.matches_tags(include, exclude)
+}
+
+/// The single entry point for embedding: load a manifest, plan, run with callbacks,
+/// and keep run history, without touching the individual modules.
+///
+/// ```no_run
+/// use linux_setup_ur::Engine;
+///
+/// let mut engine = Engine::discover()?.with_profile("laptop");
+/// println!("{}", engine.plan());
+/// let report = engine.run(&mut ());
+/// assert!(report.is_success());
+/// # Ok::<(), linux_setup_ur::RegistryError>(())
+/// ```
+#[derive(Debug)]
+pub struct Engine {
+    registry: SetupRegistry,
+    facts: Facts,
+    /// Reported instead of the detected distribution while planning and running.
+    distribution: Option<DistributionType>,
+    state: StateStore,
+    profile: Option<String>,
+    include_tags: Vec<String>,
+    exclude_tags: Vec<String>,
+    terminal_title: bool,
+}
+
+impl Engine {
+    pub fn new(registry: SetupRegistry) -> Self {
+        Engine {
+            registry,
+            facts: Facts::gather(),
+            distribution: None,
+            state: StateStore::default(),
+            profile: None,
+            include_tags: Vec::new(),
+            exclude_tags: Vec::new(),
+            terminal_title: true,
+        }
+    }
+
+    /// Loads and validates a manifest in any supported format.
+    pub fn load(path: &Path) -> Result<Self, RegistryError> {
+        Ok(Self::new(SetupRegistry::load(path)?))
+    }
+
+    /// Loads the manifest from the standard locations; a missing manifest is an error here.
+    pub fn discover() -> Result<Self, RegistryError> {
+        match SetupRegistry::load_discovered()? {
+            Some((registry, _)) => Ok(Self::new(registry)),
+            None => Err(RegistryError::Validation(
+                "no manifest found in the standard locations".to_string(),
+            )),
+        }
+    }
+
+    pub fn with_profile(mut self, profile: &str) -> Self {
+        self.profile = Some(profile.to_string());
+        self
+    }
+
+    pub fn with_tags(mut self, include: &[&str], exclude: &[&str]) -> Self {
+        self.include_tags = include.iter().map(|tag| tag.to_string()).collect();
+        self.exclude_tags = exclude.iter().map(|tag| tag.to_string()).collect();
+        self
+    }
+
+    /// Plans and runs as if on `distribution`, bypassing detection. The override only
+    /// holds while this engine plans or runs; the host's release and os-release `ID`
+    /// say nothing about `distribution`, so they are left unknown.
+    pub fn with_distribution(mut self, distribution: DistributionType) -> Self {
+        self.facts.distribution = distribution.clone();
+        self.facts.id = None;
+        self.facts.version = None;
+        self.distribution = Some(distribution);
+        self
+    }
+
+    /// Whether `run` shows progress in the terminal title; it is only ever
+    /// written when stdout is a terminal.
+    pub fn with_terminal_title(mut self, enabled: bool) -> Self {
+        self.terminal_title = enabled;
+        self
+    }
+
+    /// Where run history (durations, phase progress) is kept.
+    pub fn with_state(mut self, state: StateStore) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn registry(&self) -> &SetupRegistry {
+        &self.registry
+    }
+
+    pub fn registry_mut(&mut self) -> &mut SetupRegistry {
+        &mut self.registry
+    }
+
+    /// The system facts gathered when the engine was created.
+    pub fn facts(&self) -> &Facts {
+        &self.facts
+    }
+
+    pub fn state(&self) -> &StateStore {
+        &self.state
+    }
+
+    /// What the selected entries would do, with estimates from the run history.
+    pub fn plan(&self) -> Plan {
+        let _distribution = self.distribution.clone().map(override_distribution);
+        let history = self.state.load().unwrap_or_default();
+        let mut plan = self.registry.plan(&history);
+        let selected: Vec<bool> = self
+            .registry
+            .entries()
+            .iter()
+            .map(|entry| selects(entry, &self.profile, &self.include_tags, &self.exclude_tags))
+            .collect();
+        let mut flags = selected.iter();
+        plan.items.retain(|_| *flags.next().unwrap());
+        plan
+    }
+
+    /// Runs the selected entries, reporting progress to `observer`, and records
+    /// how long each took so later plans can estimate better.
+    pub fn run(&mut self, observer: &mut dyn RunObserver) -> RunReport {
+        let _distribution = self.distribution.clone().map(override_distribution);
+        let selected = |entry: &SetupEntry| {
+            selects(entry, &self.profile, &self.include_tags, &self.exclude_tags)
+        };
+        let report = if self.terminal_title {
+            self.registry
+                .run_observed(selected, &mut TerminalTitle::new(observer))
+        } else {
+            self.registry.run_observed(selected, observer)
+        };
+
+        let mut history = self.state.load().unwrap_or_default();
+        history.record_durations(&report);
+        if let Err(e) = self.state.save(&history) {
+            Status::Warning.print_message(&format!("Failed to save run history: {}", e));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::CURRENT_VERSION;
+    use crate::report::EntryResult;
+    use crate::setup::PlanAction;
+    use serde_json::json;
+
+    #[derive(Default)]
+    struct Recorder {
+        started: Vec<(String, usize, usize)>,
+        finished: usize,
+    }
+
+    impl RunObserver for Recorder {
+        fn entry_started(&mut self, entry: &SetupEntry, position: usize, total: usize) {
+            self.started
+                .push((entry.get_description().clone(), position, total));
+        }
+
+        fn entry_finished(&mut self, _result: &EntryResult) {
+            self.finished += 1;
+        }
+    }
+
+    #[test]
+    fn test_run_reports_progress_and_records_history() {
+        let registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [
+                { "description": "Rust", "commands": [{ "command": "true" }], "tags": ["dev"] },
+                { "description": "Steam", "commands": [{ "command": "true" }], "tags": ["gui"] },
+                { "description": "Git", "commands": [{ "command": "true" }], "tags": ["dev"] }
+            ]
+        }))
+        .unwrap();
+        let path = std::env::temp_dir().join(format!("lsu-engine-{}.json", std::process::id()));
+        let mut engine = Engine::new(registry)
+            .with_tags(&["dev"], &[])
+            .with_state(StateStore::new(&path));
+
+        assert_eq!(engine.plan().items.len(), 2);
+
+        let mut recorder = Recorder::default();
+        assert!(engine.run(&mut recorder).is_success());
+        assert_eq!(
+            recorder.started,
+            [("Rust".to_string(), 0, 2), ("Git".to_string(), 1, 2)]
+        );
+        assert_eq!(recorder.finished, 2);
+
+        let history = engine.state().load().unwrap();
+        assert!(history.duration_of("Git").is_some());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_distribution_override_is_scoped_to_the_engine() {
+        let manifest = || {
+            SetupRegistry::from_value(json!({
+                "version": CURRENT_VERSION,
+                "entries": [{
+                    "description": "xbps",
+                    "commands": [{ "command": "true", "distribution": "Void" }]
+                }]
+            }))
+            .unwrap()
+        };
+        let path = std::env::temp_dir().join(format!("lsu-override-{}.json", std::process::id()));
+        let mut void = Engine::new(manifest())
+            .with_distribution(DistributionType::Void)
+            .with_state(StateStore::new(&path));
+        assert_eq!(void.facts().id, None);
+        assert_eq!(void.facts().version, None);
+
+        assert_eq!(void.plan().items[0].action, PlanAction::Run);
+        let report = void.run(&mut ());
+        assert_eq!(report.entries[0].timings.len(), 1);
+
+        assert_eq!(crate::distribution::current_override(), None);
+        if crate::distribution::identify_linux_distribution() != DistributionType::Void {
+            let mut host = Engine::new(manifest()).with_state(StateStore::new(&path));
+            assert!(host.run(&mut ()).entries[0].timings.is_empty());
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
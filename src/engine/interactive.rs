@@ -0,0 +1,122 @@
+use std::io::{self, BufRead};
+
+use super::RunObserver;
+use crate::utils::{reporter, SkipReason};
+use crate::SetupEntry;
+
+/// Where `Confirmation` reads its answers from, a line at a time.
+pub trait ReadLine {
+    fn read_line(&mut self, line: &mut String) -> io::Result<usize>;
+}
+
+impl<R: BufRead> ReadLine for R {
+    fn read_line(&mut self, line: &mut String) -> io::Result<usize> {
+        BufRead::read_line(self, line)
+    }
+}
+
+/// Standard input, locked only while a line is read, so the prompts commands and
+/// steps show during the run can read it too.
+pub struct SharedStdin(io::Stdin);
+
+impl ReadLine for SharedStdin {
+    fn read_line(&mut self, line: &mut String) -> io::Result<usize> {
+        // `Stdin::read_line` takes the lock for this one line.
+        self.0.read_line(line)
+    }
+}
+
+/// Shows each entry with its commands and asks before running it:
+/// `y` runs it, `n` skips it, `a` skips it and every entry after it.
+pub struct Confirmation<R> {
+    input: R,
+    skip_all: bool,
+}
+
+impl Confirmation<SharedStdin> {
+    pub fn stdin() -> Self {
+        Confirmation::new(SharedStdin(io::stdin()))
+    }
+}
+
+impl<R: ReadLine> Confirmation<R> {
+    pub fn new(input: R) -> Self {
+        Confirmation {
+            input,
+            skip_all: false,
+        }
+    }
+
+    fn ask(&mut self) -> Option<SkipReason> {
+        loop {
+            reporter().prompt("Run this entry? [y]es / [n]o / skip [a]ll: ");
+            let mut answer = String::new();
+            // End of input cannot confirm anything, so treat it like skip-all.
+            if self.input.read_line(&mut answer).unwrap_or(0) == 0 {
+                self.skip_all = true;
+                return Some(SkipReason::Declined);
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => return None,
+                "n" | "no" => return Some(SkipReason::Declined),
+                "a" | "all" => {
+                    self.skip_all = true;
+                    return Some(SkipReason::Declined);
+                }
+                _ => continue,
+            }
+        }
+    }
+}
+
+impl<R: ReadLine> RunObserver for Confirmation<R> {
+    fn skip_entry(&mut self, entry: &SetupEntry) -> Option<SkipReason> {
+        if self.skip_all {
+            return Some(SkipReason::Declined);
+        }
+
+        reporter().line(&format!("\n{}", entry.get_description()));
+        for command in entry.commands() {
+            reporter().line(&format!("  $ {}", command.command()));
+        }
+        self.ask()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::CURRENT_VERSION;
+    use crate::utils::Status;
+    use crate::SetupRegistry;
+    use serde_json::json;
+
+    #[test]
+    fn test_confirmation_answers() {
+        let mut registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [
+                { "description": "One", "commands": [{ "command": "true" }] },
+                { "description": "Two", "commands": [{ "command": "true" }] },
+                { "description": "Three", "commands": [{ "command": "true" }] },
+                { "description": "Four", "commands": [{ "command": "true" }] }
+            ]
+        }))
+        .unwrap();
+
+        let mut confirmation = Confirmation::new("maybe\ny\nn\na\n".as_bytes());
+        let report = registry.run_observed(|_| true, &mut confirmation);
+
+        let statuses: Vec<_> = report.entries.iter().map(|e| e.status.clone()).collect();
+        assert_eq!(
+            statuses,
+            [
+                Status::Success,
+                Status::Skipped,
+                Status::Skipped,
+                Status::Skipped
+            ]
+        );
+        assert_eq!(report.entries[3].skip_reason, Some(SkipReason::Declined));
+    }
+}
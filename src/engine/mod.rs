@@ -0,0 +1,12 @@
+mod facade;
+mod interactive;
+mod observer;
+mod step_through;
+mod title;
+
+pub use facade::Engine;
+pub use interactive::{Confirmation, ReadLine, SharedStdin};
+pub use observer::RunObserver;
+pub(crate) use step_through::review;
+pub use step_through::DebugAction;
+pub use title::TerminalTitle;
@@ -0,0 +1,20 @@
+use crate::report::EntryResult;
+use crate::utils::SkipReason;
+use crate::SetupEntry;
+
+/// Receives progress while an `Engine` (or `SetupRegistry`) runs, e.g. to drive a GUI.
+/// Every method has a no-op default.
+pub trait RunObserver {
+    /// Called before `entry` runs; `position` counts from zero within `total` selected entries.
+    fn entry_started(&mut self, _entry: &SetupEntry, _position: usize, _total: usize) {}
+
+    /// Lets the observer skip an entry before it runs, e.g. because an earlier run completed it.
+    fn skip_entry(&mut self, _entry: &SetupEntry) -> Option<SkipReason> {
+        None
+    }
+
+    fn entry_finished(&mut self, _result: &EntryResult) {}
+}
+
+/// Observes nothing; used by the plain `execute` methods.
+impl RunObserver for () {}
@@ -0,0 +1,92 @@
+use std::io::BufRead;
+
+use crate::utils::{reporter, Status};
+use crate::CommandStruct;
+
+/// What to do after a command in step-through mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugAction {
+    Continue,
+    Retry,
+    Skip,
+    /// Run this command line instead, then review again.
+    Edit(String),
+    Abort,
+}
+
+fn read_answer(input: &mut dyn BufRead) -> Option<String> {
+    let mut answer = String::new();
+    match input.read_line(&mut answer) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(answer.trim().to_string()),
+    }
+}
+
+/// Shows what `command` printed and the `status` it ended with, then asks what to do.
+/// Running out of input aborts, so a closed stdin cannot loop forever.
+pub(crate) fn review(
+    command: &CommandStruct,
+    status: &Status,
+    input: &mut dyn BufRead,
+) -> DebugAction {
+    reporter().line(&format!("\n$ {}", command.script()));
+    match command.last_output() {
+        Some(output) => {
+            for (name, stream) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+                let text = String::from_utf8_lossy(stream);
+                if !text.trim().is_empty() {
+                    reporter().line(&format!("--- {} ---\n{}", name, text.trim_end()));
+                }
+            }
+        }
+        None => reporter().line("(output was not captured; see above)"),
+    }
+    reporter().line(&format!("Status: {:?}", status));
+
+    loop {
+        reporter().prompt("[c]ontinue, [r]etry, [s]kip, [e]dit, [a]bort? ");
+        let Some(answer) = read_answer(input) else {
+            return DebugAction::Abort;
+        };
+        match answer.to_lowercase().as_str() {
+            "c" | "continue" => return DebugAction::Continue,
+            "r" | "retry" => return DebugAction::Retry,
+            "s" | "skip" => return DebugAction::Skip,
+            "a" | "abort" => return DebugAction::Abort,
+            "e" | "edit" => {
+                reporter().prompt("New command: ");
+                match read_answer(input) {
+                    None => return DebugAction::Abort,
+                    Some(script) if script.is_empty() => continue,
+                    Some(script) => return DebugAction::Edit(script),
+                }
+            }
+            _ => continue,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_review_answers() {
+        let command: CommandStruct = serde_json::from_value(json!({ "command": "false" })).unwrap();
+        let mut input = "x\nedit\n\ne\necho fixed\nr\n".as_bytes();
+
+        assert_eq!(
+            review(&command, &Status::Failure, &mut input),
+            DebugAction::Edit("echo fixed".to_string())
+        );
+        assert_eq!(
+            review(&command, &Status::Failure, &mut input),
+            DebugAction::Retry
+        );
+        assert_eq!(
+            review(&command, &Status::Failure, &mut input),
+            DebugAction::Abort
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use super::RunObserver;
+use crate::report::EntryResult;
+use crate::utils::{pop_terminal_title, push_terminal_title, set_terminal_title, SkipReason};
+use crate::SetupEntry;
+
+/// Shows the current entry and overall progress in the terminal title, e.g.
+/// `[3/12] Rust - linux_setup_ur`, forwarding every event to `inner`.
+/// The previous title comes back when it is dropped.
+pub struct TerminalTitle<'a> {
+    inner: &'a mut dyn RunObserver,
+}
+
+impl<'a> TerminalTitle<'a> {
+    pub fn new(inner: &'a mut dyn RunObserver) -> Self {
+        push_terminal_title();
+        TerminalTitle { inner }
+    }
+}
+
+impl Drop for TerminalTitle<'_> {
+    fn drop(&mut self) {
+        pop_terminal_title();
+    }
+}
+
+impl RunObserver for TerminalTitle<'_> {
+    fn entry_started(&mut self, entry: &SetupEntry, position: usize, total: usize) {
+        set_terminal_title(&format!(
+            "[{}/{}] {} - linux_setup_ur",
+            position + 1,
+            total,
+            entry.get_description()
+        ));
+        self.inner.entry_started(entry, position, total);
+    }
+
+    fn skip_entry(&mut self, entry: &SetupEntry) -> Option<SkipReason> {
+        self.inner.skip_entry(entry)
+    }
+
+    fn entry_finished(&mut self, result: &EntryResult) {
+        self.inner.entry_finished(result);
+    }
+}
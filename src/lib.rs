@@ -1,13 +1,24 @@
+pub mod artifact;
+pub mod check;
 pub mod command;
+pub mod condition;
 pub mod config;
 pub mod distribution;
+pub mod engine;
+pub mod manifest;
+pub mod report;
 pub mod setup;
+pub mod state;
+pub mod step;
 pub mod traits;
 pub mod utils;
 
 pub use command::CommandStruct;
-pub use config::Config;
+pub use config::{Config, ConfigRepository};
 pub use distribution::DistributionType;
-pub use setup::{SetupEntry, SetupRegistry};
-pub use traits::{CommandRunner, Configurator, ErrorHandler, Repository};
+pub use engine::{Engine, RunObserver};
+pub use report::RunReport;
+pub use setup::{RegistryError, SetupEntry, SetupRegistry};
+pub use step::Step;
+pub use traits::{CheckProvider, CommandRunner, Configurator, ErrorHandler, Repository};
 pub use utils::Color;
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::walk::for_each_command;
+use crate::command::shell::Shell;
+use crate::command::Retry;
+use crate::distribution::{identify_linux_distribution, PackageOptions};
+use crate::utils::OnError;
+use crate::DistributionType;
+
+/// Command fields a manifest can set once under `defaults` instead of on every command.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct CommandDefaults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shell: Option<Shell>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sudo: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    on_error: Option<OnError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_delay: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry: Option<Retry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package_options: Option<PackageOptions>,
+    /// Overrides for commands of one distribution, e.g. `"Ubuntu": { "shell": "Bash" }`.
+    #[serde(default, skip_serializing)]
+    distributions: BTreeMap<String, CommandDefaults>,
+}
+
+impl CommandDefaults {
+    fn fields(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(fields)) => fields,
+            _ => Map::new(),
+        }
+    }
+}
+
+fn fill_missing(command: &mut Map<String, Value>, defaults: &Map<String, Value>) {
+    for (key, value) in defaults {
+        command.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+}
+
+/// Fills the top-level `defaults` into every command that does not set those fields
+/// itself, then drops `defaults` from the manifest. Per-distribution defaults win over
+/// the general ones; they follow the running distribution, or the command's first
+/// target when it does not run there.
+pub fn apply_defaults(value: &mut Value) -> Result<(), String> {
+    let defaults = match value
+        .as_object_mut()
+        .and_then(|root| root.remove("defaults"))
+    {
+        None => return Ok(()),
+        Some(defaults) => serde_json::from_value::<CommandDefaults>(defaults)
+            .map_err(|e| format!("invalid `defaults`: {}", e))?,
+    };
+    if let Some(name) = defaults
+        .distributions
+        .iter()
+        .find(|(_, nested)| !nested.distributions.is_empty())
+        .map(|(name, _)| name)
+    {
+        return Err(format!(
+            "`defaults.distributions.{}` cannot nest `distributions`",
+            name
+        ));
+    }
+    let declared = value.get("distributions").and_then(Value::as_object);
+    let is_known = |name: &str| {
+        let builtin = serde_json::from_value(Value::from(name))
+            .is_ok_and(|distribution| !matches!(distribution, DistributionType::Custom(_)));
+        builtin || declared.is_some_and(|declared| declared.contains_key(name))
+    };
+    if let Some(name) = defaults.distributions.keys().find(|name| !is_known(name)) {
+        return Err(format!(
+            "`defaults.distributions`: unknown distribution `{}`; declare it under `distributions`",
+            name
+        ));
+    }
+
+    let general = defaults.fields();
+    let per_distribution: BTreeMap<&str, Map<String, Value>> = defaults
+        .distributions
+        .iter()
+        .map(|(name, nested)| (name.as_str(), nested.fields()))
+        .collect();
+    let detected = serde_json::to_value(identify_linux_distribution())
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+
+    for_each_command(value, |_, command| {
+        let targets: Vec<&str> = command
+            .get("distribution")
+            .into_iter()
+            .chain(
+                command
+                    .get("distributions")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten(),
+            )
+            .filter_map(Value::as_str)
+            .collect();
+        let distribution = match targets.first() {
+            Some(first) if !targets.contains(&detected.as_str()) => first.to_string(),
+            _ => detected.clone(),
+        };
+        if let Some(specific) = per_distribution.get(distribution.as_str()) {
+            fill_missing(command, specific);
+        }
+        fill_missing(command, &general);
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_defaults() {
+        let mut manifest = json!({
+            "defaults": {
+                "shell": "Bash",
+                "retries": 2,
+                "distributions": {
+                    "ArchLinux": { "sudo": true, "shell": "Sh" },
+                    "Rhel": { "package_options": { "enable_repos": ["epel"] } }
+                }
+            },
+            "entries": [{
+                "description": "Tools",
+                "commands": [
+                    { "command": "pacman -S fd", "distribution": "ArchLinux" },
+                    { "command": "apt install fd-find", "distribution": "Ubuntu", "retries": 0 },
+                    { "command": "fd-find", "distribution": "Rhel", "use_package_manager": true }
+                ]
+            }]
+        });
+
+        apply_defaults(&mut manifest).unwrap();
+        assert!(manifest.get("defaults").is_none());
+        let commands = &manifest["entries"][0]["commands"];
+        assert_eq!(commands[0]["shell"], "Sh");
+        assert_eq!(commands[0]["sudo"], true);
+        assert_eq!(commands[0]["retries"], 2);
+        assert_eq!(commands[1]["shell"], "Bash");
+        assert_eq!(commands[1]["retries"], 0);
+        assert!(commands[1].get("sudo").is_none());
+        assert_eq!(
+            commands[2]["package_options"]["enable_repos"],
+            json!(["epel"])
+        );
+
+        let mut invalid = json!({ "defaults": { "shel": "Bash" }, "entries": [] });
+        assert!(apply_defaults(&mut invalid)
+            .unwrap_err()
+            .contains("unknown field `shel`"));
+
+        let mut typo = json!({
+            "defaults": { "distributions": { "Ubunto": { "sudo": true } } },
+            "entries": []
+        });
+        assert!(apply_defaults(&mut typo)
+            .unwrap_err()
+            .contains("unknown distribution `Ubunto`"));
+
+        let mut declared = json!({
+            "distributions": { "ClearLinux": { "detect": "true", "install": "true", "remove": "true" } },
+            "defaults": { "distributions": { "ClearLinux": { "sudo": true } } },
+            "entries": []
+        });
+        assert!(apply_defaults(&mut declared).is_ok());
+    }
+}
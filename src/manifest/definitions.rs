@@ -0,0 +1,97 @@
+use serde_json::{Map, Value};
+
+use super::walk::command_lists;
+
+/// Replaces every `{ "use": "<name>" }` command with the command block declared
+/// under the top-level `definitions` map, then drops `definitions` from the manifest.
+pub fn expand_definitions(value: &mut Value) -> Result<(), String> {
+    let definitions = match value
+        .as_object_mut()
+        .and_then(|root| root.remove("definitions"))
+    {
+        None => return Ok(()),
+        Some(Value::Object(definitions)) => definitions,
+        Some(_) => return Err("`definitions` must be a map of named command lists".to_string()),
+    };
+
+    let Some(entries) = value.get_mut("entries").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+
+    for (entry_index, entry) in entries.iter_mut().enumerate() {
+        for (list, commands) in command_lists(entry) {
+            let mut expanded = Vec::with_capacity(commands.len());
+            for command in commands.drain(..) {
+                match command.get("use").and_then(Value::as_str) {
+                    Some(name) => expanded.extend(
+                        resolve(&definitions, name)
+                            .map_err(|e| format!("entries[{}].{}: {}", entry_index, list, e))?,
+                    ),
+                    None => expanded.push(command),
+                }
+            }
+            *commands = expanded;
+        }
+    }
+
+    Ok(())
+}
+
+fn resolve(definitions: &Map<String, Value>, name: &str) -> Result<Vec<Value>, String> {
+    let block = definitions
+        .get(name)
+        .ok_or_else(|| format!("unknown definition `{}`", name))?;
+
+    let commands = match block {
+        Value::Array(commands) => commands.clone(),
+        Value::Object(_) => vec![block.clone()],
+        _ => return Err(format!("definition `{}` must be a command or a list", name)),
+    };
+
+    if commands.iter().any(|command| command.get("use").is_some()) {
+        return Err(format!(
+            "definition `{}` cannot reference other definitions",
+            name
+        ));
+    }
+
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_expand_definitions() {
+        let mut manifest = json!({
+            "definitions": {
+                "refresh": [
+                    { "command": "pacman -Sy", "distribution": "ArchLinux" },
+                    { "command": "apt update", "distribution": "Ubuntu" }
+                ]
+            },
+            "entries": [{
+                "description": "Docker",
+                "commands": [{ "use": "refresh" }, { "command": "docker" }]
+            }]
+        });
+
+        expand_definitions(&mut manifest).unwrap();
+        assert!(manifest.get("definitions").is_none());
+        let commands = manifest["entries"][0]["commands"].as_array().unwrap();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[1]["command"], json!("apt update"));
+    }
+
+    #[test]
+    fn test_unknown_definition() {
+        let mut manifest = json!({
+            "definitions": {},
+            "entries": [{ "description": "x", "commands": [{ "use": "missing" }] }]
+        });
+        let error = expand_definitions(&mut manifest).unwrap_err();
+        assert_eq!(error, "entries[0].commands: unknown definition `missing`");
+    }
+}
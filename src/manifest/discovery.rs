@@ -0,0 +1,65 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use super::ManifestFormat;
+
+const APP_DIR: &str = "linux_setup_ur";
+const MANIFEST_STEM: &str = "setup";
+
+fn candidate_dirs(xdg_config_home: Option<String>, home: Option<String>) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(xdg) = xdg_config_home.filter(|dir| !dir.is_empty()) {
+        dirs.push(Path::new(&xdg).join(APP_DIR));
+    }
+    if let Some(home) = home.filter(|dir| !dir.is_empty()) {
+        dirs.push(Path::new(&home).join(".config").join(APP_DIR));
+    }
+    dirs.push(Path::new("/etc").join(APP_DIR));
+    dirs.dedup();
+    dirs
+}
+
+/// Every path searched by `discover_manifest`, in priority order.
+pub fn manifest_candidates() -> Vec<PathBuf> {
+    candidate_dirs(env::var("XDG_CONFIG_HOME").ok(), env::var("HOME").ok())
+        .into_iter()
+        .flat_map(|dir| {
+            ManifestFormat::EXTENSIONS
+                .iter()
+                .map(move |ext| dir.join(format!("{}.{}", MANIFEST_STEM, ext)))
+        })
+        .collect()
+}
+
+/// Finds the manifest in `$XDG_CONFIG_HOME/linux_setup_ur/`, then `~/.config/linux_setup_ur/`,
+/// then `/etc/linux_setup_ur/`, skipping formats whose crate feature is disabled.
+pub fn discover_manifest() -> Option<PathBuf> {
+    manifest_candidates().into_iter().find(|path| {
+        path.is_file() && ManifestFormat::from_path(path).is_some_and(|f| f.is_enabled())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_dirs_order() {
+        let dirs = candidate_dirs(Some("/xdg".to_string()), Some("/home/me".to_string()));
+        assert_eq!(
+            dirs,
+            [
+                PathBuf::from("/xdg/linux_setup_ur"),
+                PathBuf::from("/home/me/.config/linux_setup_ur"),
+                PathBuf::from("/etc/linux_setup_ur"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_candidate_dirs_without_xdg() {
+        let dirs = candidate_dirs(None, Some("/home/me".to_string()));
+        assert_eq!(dirs[0], PathBuf::from("/home/me/.config/linux_setup_ur"));
+        assert_eq!(dirs.len(), 2);
+    }
+}
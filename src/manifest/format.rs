@@ -0,0 +1,48 @@
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+/// The serialization formats a manifest can be written in, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ManifestFormat {
+    pub const EXTENSIONS: [&'static str; 4] = ["json", "yaml", "yml", "toml"];
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(ManifestFormat::Json),
+            "yaml" | "yml" => Some(ManifestFormat::Yaml),
+            "toml" => Some(ManifestFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// Whether support for this format was compiled in.
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            ManifestFormat::Json => true,
+            ManifestFormat::Yaml => cfg!(feature = "yaml"),
+            ManifestFormat::Toml => cfg!(feature = "toml"),
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(&self, content: &str) -> Result<T, String> {
+        match self {
+            ManifestFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+            #[cfg(feature = "yaml")]
+            ManifestFormat::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+            #[cfg(feature = "toml")]
+            ManifestFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            #[allow(unreachable_patterns)]
+            format => Err(format!(
+                "{:?} manifests require the matching crate feature",
+                format
+            )),
+        }
+    }
+}
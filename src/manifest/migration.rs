@@ -0,0 +1,106 @@
+use serde_json::{json, Map, Value};
+
+use super::walk::for_each_command;
+
+/// The manifest format version produced by this crate.
+pub const CURRENT_VERSION: u64 = 1;
+
+type Migration = fn(&mut Value, &mut Vec<String>);
+
+/// Each migration upgrades a manifest from the paired version to the next one.
+const MIGRATIONS: &[(u64, Migration)] = &[(0, migrate_v0_to_v1)];
+
+fn rename_field(
+    command: &mut Map<String, Value>,
+    location: &str,
+    from: &str,
+    to: &str,
+    warnings: &mut Vec<String>,
+) {
+    if let Some(value) = command.remove(from) {
+        warnings.push(format!("{}: `{}` was renamed to `{}`", location, from, to));
+        command.entry(to).or_insert(value);
+    }
+}
+
+/// Version 0 manifests were unversioned, could be a bare list of entries and used `use_sudo`.
+fn migrate_v0_to_v1(value: &mut Value, warnings: &mut Vec<String>) {
+    if value.is_array() {
+        warnings.push("top-level entry list was wrapped in `entries`".to_string());
+        *value = json!({ "entries": value.take() });
+    }
+
+    for_each_command(value, |location, command| {
+        rename_field(command, location, "use_sudo", "sudo", warnings);
+    });
+}
+
+/// Upgrades `value` to `CURRENT_VERSION`, returning a warning for every change made.
+pub fn migrate(mut value: Value) -> Result<(Value, Vec<String>), String> {
+    let mut warnings = Vec::new();
+    let mut version = match value.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| format!("invalid manifest version: {}", version))?,
+    };
+
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "manifest version {} is newer than the supported version {}",
+            version, CURRENT_VERSION
+        ));
+    }
+
+    if version < CURRENT_VERSION {
+        warnings.push(format!(
+            "manifest version {} is outdated, migrating to version {}",
+            version, CURRENT_VERSION
+        ));
+    }
+
+    for (from, migration) in MIGRATIONS {
+        if version == *from {
+            migration(&mut value, &mut warnings);
+            version += 1;
+        }
+    }
+
+    if let Some(root) = value.as_object_mut() {
+        root.insert("version".to_string(), json!(version));
+    }
+
+    Ok((value, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_v0() {
+        let legacy = json!([
+            { "description": "Update", "commands": [{ "command": "apt update", "use_sudo": true }] }
+        ]);
+
+        let (value, warnings) = migrate(legacy).unwrap();
+        assert_eq!(value["version"], json!(CURRENT_VERSION));
+        assert_eq!(value["entries"][0]["commands"][0]["sudo"], json!(true));
+        assert!(value["entries"][0]["commands"][0].get("use_sudo").is_none());
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[2].starts_with("entries[0].commands[0]"));
+    }
+
+    #[test]
+    fn test_current_version_is_untouched() {
+        let manifest = json!({ "version": CURRENT_VERSION, "entries": [] });
+        let (value, warnings) = migrate(manifest.clone()).unwrap();
+        assert_eq!(value, manifest);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        assert!(migrate(json!({ "version": CURRENT_VERSION + 1, "entries": [] })).is_err());
+    }
+}
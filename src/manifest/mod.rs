@@ -0,0 +1,28 @@
+mod defaults;
+mod definitions;
+mod discovery;
+mod format;
+mod migration;
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "schema")]
+mod schema;
+mod secrets;
+mod source;
+#[cfg(feature = "templating")]
+mod template;
+mod walk;
+
+pub use defaults::apply_defaults;
+pub use definitions::expand_definitions;
+pub use discovery::{discover_manifest, manifest_candidates};
+pub use format::ManifestFormat;
+pub use migration::{migrate, CURRENT_VERSION};
+#[cfg(feature = "remote")]
+pub use remote::{fetch_manifest, sha256_hex};
+#[cfg(feature = "schema")]
+pub use schema::manifest_schema;
+pub use secrets::{decrypt_secret, is_encrypted, is_valid_secret_name, SecretKey};
+pub use source::Source;
+#[cfg(feature = "templating")]
+pub use template::render_template;
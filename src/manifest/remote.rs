@@ -0,0 +1,26 @@
+use std::io;
+
+pub use crate::utils::sha256_hex;
+
+/// Downloads a manifest over HTTP(S), verifying it against `expected_sha256` when given.
+pub fn fetch_manifest(url: &str, expected_sha256: Option<&str>) -> io::Result<String> {
+    let body = ureq::get(url)
+        .call()
+        .map_err(io::Error::other)?
+        .into_string()?;
+
+    if let Some(expected) = expected_sha256 {
+        let actual = sha256_hex(body.as_bytes());
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "checksum mismatch for {}: expected {}, got {}",
+                    url, expected, actual
+                ),
+            ));
+        }
+    }
+
+    Ok(body)
+}
@@ -0,0 +1,51 @@
+use serde_json::{json, Value};
+
+use crate::SetupRegistry;
+
+/// Returns the JSON Schema describing the manifest format, for editor completion and validation.
+pub fn manifest_schema() -> serde_json::Value {
+    let schema = schemars::schema_for!(SetupRegistry);
+    let mut schema = serde_json::to_value(schema).expect("schema is always serializable");
+    // schemars renders the untagged `Custom` variant as `{ "Custom": name }`, but
+    // manifests name a declared distribution directly. Built-in names then match two
+    // variants, so any one of them has to do.
+    let distribution = schema
+        .pointer_mut("/definitions/DistributionType")
+        .and_then(Value::as_object_mut);
+    if let Some(distribution) = distribution {
+        if let Some(Value::Array(mut variants)) = distribution.remove("oneOf") {
+            for variant in &mut variants {
+                if variant.pointer("/properties/Custom").is_some() {
+                    *variant = json!({
+                        "type": "string",
+                        "description": variant["description"].clone(),
+                    });
+                }
+            }
+            distribution.insert("anyOf".to_string(), Value::Array(variants));
+        }
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_schema() {
+        let schema = manifest_schema();
+        assert_eq!(schema["title"], "SetupRegistry");
+        assert!(schema["properties"]["entries"].is_object());
+        assert!(schema["definitions"]["CommandStruct"]["properties"]["command"].is_object());
+        assert!(schema["definitions"]["CommandStruct"]["properties"]
+            .get("status")
+            .is_none());
+        let distributions = schema["definitions"]["DistributionType"]["anyOf"]
+            .as_array()
+            .unwrap();
+        assert!(distributions
+            .iter()
+            .all(|variant| variant["type"] == "string"));
+    }
+}
@@ -0,0 +1,73 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+
+const AGE_HEADER: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+
+/// How the `age` CLI unlocks the manifest's `secrets`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretKey {
+    /// An age identity (private key) file, as produced by `age-keygen`.
+    IdentityFile(PathBuf),
+    /// Passphrase-encrypted values; `age` prompts for it on the terminal.
+    Passphrase,
+}
+
+/// Secret names become environment variables, so they must be valid variable names.
+pub fn is_valid_secret_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_uppercase() || c == '_')
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Catches plaintext values that would otherwise be committed alongside the manifest.
+pub fn is_encrypted(value: &str) -> bool {
+    value.trim_start().starts_with(AGE_HEADER)
+}
+
+/// Decrypts one ASCII-armored value with `age --decrypt`.
+pub fn decrypt_secret(ciphertext: &str, key: &SecretKey) -> io::Result<String> {
+    let mut command = process::Command::new("age");
+    command.arg("--decrypt");
+    if let SecretKey::IdentityFile(path) = key {
+        command.arg("--identity").arg(path);
+    }
+
+    let mut child = command
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(ciphertext.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_names_and_armor() {
+        assert!(is_valid_secret_name("GITHUB_TOKEN"));
+        assert!(is_valid_secret_name("_KEY2"));
+        assert!(!is_valid_secret_name("2FA"));
+        assert!(!is_valid_secret_name("github-token"));
+        assert!(!is_valid_secret_name(""));
+
+        assert!(is_encrypted(
+            "-----BEGIN AGE ENCRYPTED FILE-----\nYWdl\n-----END AGE ENCRYPTED FILE-----"
+        ));
+        assert!(!is_encrypted("ghp_plaintext"));
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt;
+
+/// Where an entry was defined: a file path or URL and, when it can be found, the line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    pub origin: String,
+    pub line: Option<usize>,
+}
+
+/// Whether `line` sets `description` to exactly `description`, as in `"description": "Git",`,
+/// `- description: Git` or `description = "Git"`.
+fn declares(line: &str, description: &str) -> bool {
+    let Some((key, value)) = line.split_once([':', '=']) else {
+        return false;
+    };
+    let key = key
+        .trim()
+        .trim_start_matches('-')
+        .trim()
+        .trim_matches(['"', '\'']);
+    let value = value
+        .trim()
+        .trim_end_matches(',')
+        .trim()
+        .trim_matches(['"', '\'']);
+    key == "description" && value == description
+}
+
+impl Source {
+    /// Locates the line declaring `description` in the raw manifest text.
+    /// This is a best-effort search that works for JSON, YAML and TOML alike.
+    pub fn locate(origin: &str, raw: &str, description: &str) -> Self {
+        let line = raw
+            .lines()
+            .position(|line| declares(line, description))
+            .map(|index| index + 1);
+
+        Source {
+            origin: origin.to_string(),
+            line,
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}", self.origin, line),
+            None => write!(f, "{}", self.origin),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let raw = "{\n  \"entries\": [\n    {\n      \"description\": \"Git Configuration\",\n";
+        let source = Source::locate("base.json", raw, "Git Configuration");
+        assert_eq!(source.line, Some(4));
+        assert_eq!(source.to_string(), "base.json:4");
+
+        let missing = Source::locate("https://example.com/setup.json", raw, "Rust");
+        assert_eq!(missing.to_string(), "https://example.com/setup.json");
+
+        let raw = "entries:\n  - description: Git Configuration\n  - description: Git\n";
+        assert_eq!(Source::locate("base.yaml", raw, "Git").line, Some(3));
+        let raw = "[[entries]]\ndescription = \"Git\"\n";
+        assert_eq!(Source::locate("base.toml", raw, "Git").line, Some(2));
+    }
+}
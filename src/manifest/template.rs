@@ -0,0 +1,43 @@
+use crate::distribution::{cpu_architecture, distribution_id, identify_linux_distribution};
+use crate::utils::TMPDIR_PLACEHOLDER;
+
+/// Renders a manifest template with Tera.
+/// The detected distribution is always available as `{{ distro }}`, and its precise
+/// os-release ID as `{{ distro_id }}` and the CPU architecture as `{{ arch }}`, unless
+/// the caller overrides them.
+/// `{{tmpdir}}` renders as itself, since it is only known once a run starts.
+pub fn render_template(template: &str, context: &serde_json::Value) -> Result<String, tera::Error> {
+    let mut tera_context = tera::Context::new();
+    tera_context.insert("distro", &identify_linux_distribution());
+    tera_context.insert("distro_id", &distribution_id().unwrap_or_default());
+    tera_context.insert("arch", cpu_architecture());
+    tera_context.insert("tmpdir", TMPDIR_PLACEHOLDER);
+
+    if let Some(values) = context.as_object() {
+        for (key, value) in values {
+            tera_context.insert(key.as_str(), value);
+        }
+    }
+
+    tera::Tera::one_off(template, &tera_context, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_loop() {
+        let template = r#"[{% for pkg in packages %}"{{ pkg }}"{% if not loop.last %},{% endif %}{% endfor %}]"#;
+        let rendered = render_template(template, &json!({ "packages": ["git", "zsh"] })).unwrap();
+        assert_eq!(rendered, r#"["git","zsh"]"#);
+    }
+
+    #[test]
+    fn test_render_distro_override() {
+        let template = r#"{% if distro == "ArchLinux" %}pacman{% else %}apt{% endif %}"#;
+        let rendered = render_template(template, &json!({ "distro": "ArchLinux" })).unwrap();
+        assert_eq!(rendered, "pacman");
+    }
+}
@@ -0,0 +1,60 @@
+use serde_json::{Map, Value};
+
+/// The command lists of a manifest entry: its own `commands` and hooks, and the `commands`
+/// and `revert` lists of its `config`.
+pub(crate) fn command_lists(entry: &mut Value) -> Vec<(&'static str, &mut Vec<Value>)> {
+    let Some(entry) = entry.as_object_mut() else {
+        return Vec::new();
+    };
+
+    let mut lists = Vec::new();
+    for (name, value) in entry.iter_mut() {
+        let location = match name.as_str() {
+            "commands" => "commands",
+            "pre_hooks" => "pre_hooks",
+            "post_hooks" => "post_hooks",
+            "config" => {
+                let Some(config) = value.as_object_mut() else {
+                    continue;
+                };
+                for (key, list) in config.iter_mut() {
+                    let location = match key.as_str() {
+                        "commands" => "config.commands",
+                        "revert" => "config.revert",
+                        _ => continue,
+                    };
+                    if let Some(commands) = list.as_array_mut() {
+                        lists.push((location, commands));
+                    }
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(commands) = value.as_array_mut() {
+            lists.push((location, commands));
+        }
+    }
+    lists
+}
+
+/// Calls `f` with the location and body of every command object in the manifest.
+pub(crate) fn for_each_command(
+    value: &mut Value,
+    mut f: impl FnMut(&str, &mut Map<String, Value>),
+) {
+    let Some(entries) = value.get_mut("entries").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    for (entry_index, entry) in entries.iter_mut().enumerate() {
+        for (list, commands) in command_lists(entry) {
+            for (command_index, command) in commands.iter_mut().enumerate() {
+                if let Some(command) = command.as_object_mut() {
+                    let location = format!("entries[{}].{}[{}]", entry_index, list, command_index);
+                    f(&location, command);
+                }
+            }
+        }
+    }
+}
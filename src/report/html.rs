@@ -0,0 +1,111 @@
+use std::{fs, io, path::Path};
+
+use super::RunReport;
+use crate::utils::{format_duration, Status};
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn status_class(status: &Status) -> &'static str {
+    match status {
+        Status::Failure | Status::Aborted => "failed",
+        Status::Skipped => "skipped",
+        _ => "passed",
+    }
+}
+
+impl RunReport {
+    /// Renders the report as a standalone HTML page with one row per entry, including
+    /// each entry's notes and documentation link so failures can be looked up.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Setup report</title>\n\
+             <style>.failed{color:#b00}.skipped{color:#777}.notes{white-space:pre-wrap}</style>\n\
+             </head>\n<body>\n",
+        );
+        html.push_str(&format!(
+            "<p>{} entries: {} failed, {} skipped</p>\n<table>\n\
+             <tr><th>Entry</th><th>Status</th><th>Duration</th><th>Details</th></tr>\n",
+            self.entries.len(),
+            self.count(Status::Failure) + self.count(Status::Aborted),
+            self.count(Status::Skipped),
+        ));
+
+        for entry in &self.entries {
+            let mut details = Vec::new();
+            for command in &entry.failed_commands {
+                details.push(format!("<code>{}</code>", escape_html(command)));
+            }
+            if let Some(reason) = &entry.skip_reason {
+                details.push(escape_html(&reason.to_string()));
+            }
+            if let Some(notes) = &entry.notes {
+                details.push(format!("<div class=\"notes\">{}</div>", escape_html(notes)));
+            }
+            // Other schemes, such as `javascript:`, are shown but not linked.
+            if let Some(url) = &entry.docs_url {
+                let linkable = url.starts_with("https://") || url.starts_with("http://");
+                let url = escape_html(url);
+                details.push(match linkable {
+                    true => format!("see: <a href=\"{}\">{}</a>", url, url),
+                    false => format!("see: {}", url),
+                });
+            }
+            html.push_str(&format!(
+                "<tr class=\"{}\"><td>{}</td><td>{:?}</td><td>{}</td><td>{}</td></tr>\n",
+                status_class(&entry.status),
+                escape_html(&entry.description),
+                entry.status,
+                format_duration(entry.duration),
+                details.join("<br>")
+            ));
+        }
+
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    pub fn write_html(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_html())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifest::CURRENT_VERSION;
+    use crate::SetupRegistry;
+    use serde_json::json;
+
+    #[test]
+    fn test_html_includes_notes_and_docs() {
+        let mut registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [{
+                "description": "VPN <corp>",
+                "docs_url": "https://wiki.example.org/vpn?os=linux&v=2",
+                "notes": "Needs the certificate from IT.",
+                "commands": [{ "command": "exit 1" }]
+            }, {
+                "description": "Proxy",
+                "docs_url": "javascript:alert(1)",
+                "commands": [{ "command": "exit 1" }]
+            }]
+        }))
+        .unwrap();
+        let explained = registry.explain("VPN <corp>").unwrap();
+        assert!(explained.contains("Docs: https://wiki.example.org/vpn?os=linux&v=2"));
+        assert!(explained.contains("  Needs the certificate from IT."));
+
+        let html = registry.execute().to_html();
+        assert!(html.contains("<tr class=\"failed\"><td>VPN &lt;corp&gt;</td><td>Failure</td>"));
+        assert!(html.contains("<div class=\"notes\">Needs the certificate from IT.</div>"));
+        assert!(html.contains("see: <a href=\"https://wiki.example.org/vpn?os=linux&amp;v=2\">"));
+        assert!(html.contains("see: javascript:alert(1)"));
+        assert!(!html.contains("href=\"javascript:"));
+    }
+}
@@ -0,0 +1,163 @@
+use std::{fs, io, path::Path};
+
+use super::RunReport;
+use crate::utils::Status;
+
+const SUITE_NAME: &str = "linux_setup_ur";
+
+/// Escapes `value` for an attribute. Line breaks and tabs become character references so
+/// parsers keep them, and control characters that XML 1.0 forbids are dropped.
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            c if c.is_control() => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl RunReport {
+    /// Renders the report as JUnit XML with one test case per entry.
+    pub fn to_junit_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites>\n  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">\n",
+            SUITE_NAME,
+            self.entries.len(),
+            self.count(Status::Failure) + self.count(Status::Aborted),
+            self.count(Status::Skipped),
+        ));
+
+        for entry in &self.entries {
+            let mut testcase = format!(
+                "<testcase name=\"{}\" classname=\"{}\"",
+                escape_xml(&entry.description),
+                SUITE_NAME
+            );
+            if let Some(source) = &entry.source {
+                testcase.push_str(&format!(" file=\"{}\"", escape_xml(&source.origin)));
+            }
+            match entry.status {
+                Status::Failure | Status::Aborted => {
+                    let mut message = if entry.status == Status::Aborted {
+                        "interrupted".to_string()
+                    } else if entry.failed_commands.is_empty() {
+                        "entry failed".to_string()
+                    } else {
+                        format!("failed commands: {}", entry.failed_commands.join("; "))
+                    };
+                    if let Some(source) = &entry.source {
+                        message.push_str(&format!(" (defined at {})", source));
+                    }
+                    xml.push_str(&format!(
+                        "    {}>\n      <failure message=\"{}\"/>\n    </testcase>\n",
+                        testcase,
+                        escape_xml(&message)
+                    ));
+                }
+                Status::Skipped => {
+                    let skipped = match &entry.skip_reason {
+                        Some(reason) => {
+                            format!("<skipped message=\"{}\"/>", escape_xml(&reason.to_string()))
+                        }
+                        None => "<skipped/>".to_string(),
+                    };
+                    xml.push_str(&format!(
+                        "    {}>\n      {}\n    </testcase>\n",
+                        testcase, skipped
+                    ));
+                }
+                _ => xml.push_str(&format!("    {}/>\n", testcase)),
+            }
+        }
+
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+
+    pub fn write_junit_xml(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_junit_xml())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::Source;
+    use crate::report::EntryResult;
+    use crate::utils::SkipReason;
+
+    #[test]
+    fn test_to_junit_xml() {
+        let mut report = RunReport::default();
+        report.push(EntryResult {
+            description: "Git <config>".to_string(),
+            status: Status::Success,
+            failed_commands: Vec::new(),
+            stderr_tails: Vec::new(),
+            source: None,
+            skip_reason: None,
+            duration: Default::default(),
+            timings: Vec::new(),
+            packages: Vec::new(),
+            changes: Vec::new(),
+            docs_url: None,
+            notes: None,
+        });
+        report.push(EntryResult {
+            description: "Docker".to_string(),
+            status: Status::Failure,
+            failed_commands: vec!["systemctl enable \"docker\"\n\x1b[0m".to_string()],
+            stderr_tails: vec![Vec::new()],
+            source: Some(Source {
+                origin: "dev.json".to_string(),
+                line: Some(12),
+            }),
+            skip_reason: None,
+            duration: Default::default(),
+            timings: Vec::new(),
+            packages: Vec::new(),
+            changes: Vec::new(),
+            docs_url: None,
+            notes: None,
+        });
+        report.push(EntryResult {
+            description: "CI only".to_string(),
+            status: Status::Skipped,
+            failed_commands: Vec::new(),
+            stderr_tails: Vec::new(),
+            source: None,
+            skip_reason: Some(SkipReason::Condition("env('CI') == 'true'".to_string())),
+            duration: Default::default(),
+            timings: Vec::new(),
+            packages: Vec::new(),
+            changes: Vec::new(),
+            docs_url: None,
+            notes: None,
+        });
+
+        let xml = report.to_junit_xml();
+        assert!(xml.contains("tests=\"3\" failures=\"1\" skipped=\"1\""));
+        assert!(xml.contains(
+            "<skipped message=\"condition not met: env(&apos;CI&apos;) == &apos;true&apos;\"/>"
+        ));
+        assert!(
+            xml.contains("<testcase name=\"Git &lt;config&gt;\" classname=\"linux_setup_ur\"/>")
+        );
+        assert!(xml
+            .contains("<testcase name=\"Docker\" classname=\"linux_setup_ur\" file=\"dev.json\">"));
+        assert!(xml.contains(
+            "<failure message=\"failed commands: systemctl enable &quot;docker&quot;&#10;[0m (defined at dev.json:12)\"/>"
+        ));
+    }
+}
@@ -0,0 +1,5 @@
+mod html;
+mod junit;
+mod run_report;
+
+pub use run_report::{CommandTiming, EntryResult, RunReport};
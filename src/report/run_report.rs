@@ -0,0 +1,215 @@
+use std::time::Duration;
+
+use crate::distribution::PackageResult;
+use crate::manifest::Source;
+use crate::utils::{format_duration, reporter, Color, SkipReason, Status};
+
+/// How many of the longest-running commands the summary lists.
+const SLOWEST_STEPS: usize = 5;
+
+/// Wall-clock time of one command that ran, for finding what slows a run down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandTiming {
+    pub command: String,
+    pub duration: Duration,
+}
+
+/// The outcome of a single `SetupEntry` within a run.
+#[derive(Debug, Clone)]
+pub struct EntryResult {
+    pub description: String,
+    pub status: Status,
+    pub failed_commands: Vec<String>,
+    /// The last lines of stderr of each failed command, in the order of `failed_commands`.
+    pub stderr_tails: Vec<Vec<String>>,
+    pub source: Option<Source>,
+    pub skip_reason: Option<SkipReason>,
+    /// Wall-clock time spent on the entry.
+    pub duration: Duration,
+    /// Time taken by each command that ran, hooks included, in the order they ran.
+    pub timings: Vec<CommandTiming>,
+    /// Per-package results of the entry's `use_package_manager` commands.
+    pub packages: Vec<PackageResult>,
+    /// Settings changed by the entry's steps, such as `git user.email: old -> new`.
+    pub changes: Vec<String>,
+    pub docs_url: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Results collected by `SetupRegistry::execute`, in execution order.
+#[derive(Debug, Clone, Default)]
+pub struct RunReport {
+    pub entries: Vec<EntryResult>,
+}
+
+impl RunReport {
+    pub fn push(&mut self, result: EntryResult) {
+        self.entries.push(result);
+    }
+
+    pub fn count(&self, status: Status) -> usize {
+        self.entries.iter().filter(|e| e.status == status).count()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.count(Status::Failure) == 0 && self.count(Status::Aborted) == 0
+    }
+
+    /// Time spent on entries; parallel runs overlap, so this can exceed wall-clock time.
+    pub fn duration(&self) -> Duration {
+        self.entries.iter().map(|entry| entry.duration).sum()
+    }
+
+    /// The longest-running commands of the run, slowest first, with their entry.
+    pub fn slowest_steps(&self, count: usize) -> Vec<(&str, &CommandTiming)> {
+        let mut steps: Vec<(&str, &CommandTiming)> = self
+            .entries
+            .iter()
+            .flat_map(|entry| {
+                entry
+                    .timings
+                    .iter()
+                    .map(|timing| (entry.description.as_str(), timing))
+            })
+            .collect();
+        steps.sort_by_key(|(_, timing)| std::cmp::Reverse(timing.duration));
+        steps.truncate(count);
+        steps
+    }
+
+    /// The end-of-run table: counts, total duration, the slowest commands, every failed
+    /// command with the end of its stderr, and an overall verdict.
+    pub fn summary(&self) -> String {
+        let skipped = self.count(Status::Skipped);
+        let failed = self.count(Status::Failure) + self.count(Status::Aborted);
+        let rows = [
+            ("Entries run", self.entries.len() - skipped),
+            ("Passed", self.entries.len() - skipped - failed),
+            ("Skipped", skipped),
+            ("Failed", failed),
+        ];
+        let mut lines = vec!["Summary".to_string()];
+        lines.extend(
+            rows.iter()
+                .map(|(label, count)| format!("  {:<12} {:>5}", label, count)),
+        );
+        lines.push(format!(
+            "  {:<12} {:>5}",
+            "Duration",
+            format_duration(self.duration())
+        ));
+
+        let slowest = self.slowest_steps(SLOWEST_STEPS);
+        if !slowest.is_empty() {
+            lines.push("Slowest steps:".to_string());
+        }
+        for (entry, timing) in slowest {
+            lines.push(format!(
+                "  {:>6}  {}: {}",
+                format_duration(timing.duration),
+                entry,
+                timing.command
+            ));
+        }
+
+        for entry in self
+            .entries
+            .iter()
+            .filter(|e| !e.failed_commands.is_empty())
+        {
+            lines.push(format!("Failed in {}:", entry.description));
+            for (index, command) in entry.failed_commands.iter().enumerate() {
+                lines.push(format!("  $ {}", command));
+                let tail = entry.stderr_tails.get(index).into_iter().flatten();
+                lines.extend(tail.map(|line| format!("      {}", line)));
+            }
+        }
+        lines.push(match self.is_success() {
+            true => "Result: success".to_string(),
+            false => format!(
+                "Result: {} of {} entries failed",
+                failed,
+                self.entries.len()
+            ),
+        });
+        lines.join("\n")
+    }
+
+    /// Prints `summary`, green on success and red otherwise. Runs leave this to the caller.
+    pub fn print_summary(&self) {
+        let color = match self.is_success() {
+            true => Color::Green,
+            false => Color::Red,
+        };
+        reporter().line(&format!("\n{}{}{}", color, self.summary(), Color::None));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(description: &str, status: Status, seconds: u64) -> EntryResult {
+        EntryResult {
+            description: description.to_string(),
+            status,
+            failed_commands: Vec::new(),
+            stderr_tails: Vec::new(),
+            source: None,
+            skip_reason: None,
+            duration: Duration::from_secs(seconds),
+            timings: Vec::new(),
+            packages: Vec::new(),
+            changes: Vec::new(),
+            docs_url: None,
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut report = RunReport::default();
+        let mut git = result("Git", Status::Success, 30);
+        git.timings = vec![
+            CommandTiming {
+                command: "pacman -S git".to_string(),
+                duration: Duration::from_secs(25),
+            },
+            CommandTiming {
+                command: "git config --global pull.rebase true".to_string(),
+                duration: Duration::from_millis(20),
+            },
+        ];
+        report.push(git);
+        report.push(result("CI only", Status::Skipped, 0));
+        let mut docker = result("Docker", Status::Failure, 45);
+        docker.failed_commands = vec!["systemctl enable docker".to_string()];
+        docker.stderr_tails = vec![vec!["Unit docker.service not found.".to_string()]];
+        docker.timings = vec![CommandTiming {
+            command: "systemctl enable docker".to_string(),
+            duration: Duration::from_secs(44),
+        }];
+        report.push(docker);
+
+        assert_eq!(
+            report.summary(),
+            [
+                "Summary",
+                "  Entries run      2",
+                "  Passed           1",
+                "  Skipped          1",
+                "  Failed           1",
+                "  Duration     1m15s",
+                "Slowest steps:",
+                "     44s  Docker: systemctl enable docker",
+                "     25s  Git: pacman -S git",
+                "      0s  Git: git config --global pull.rebase true",
+                "Failed in Docker:",
+                "  $ systemctl enable docker",
+                "      Unit docker.service not found.",
+                "Result: 1 of 3 entries failed",
+            ]
+            .join("\n")
+        );
+    }
+}
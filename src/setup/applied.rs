@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+
+use super::{SetupEntry, SetupRegistry};
+use crate::engine::RunObserver;
+use crate::report::{EntryResult, RunReport};
+use crate::state::{RunState, StateStore};
+use crate::utils::{fingerprint, SkipReason, Status};
+
+/// Skips entries whose definition is unchanged since they last succeeded, and records
+/// the fingerprint of every entry that succeeds, for `execute_incremental`.
+struct AppliedTracker<'a> {
+    store: &'a StateStore,
+    state: RunState,
+    /// Fingerprints of the entries that started, taken before they ran.
+    started: BTreeMap<String, String>,
+}
+
+impl RunObserver for AppliedTracker<'_> {
+    fn entry_started(&mut self, entry: &SetupEntry, _position: usize, _total: usize) {
+        self.started
+            .insert(entry.get_description().clone(), entry.fingerprint());
+    }
+
+    fn skip_entry(&mut self, entry: &SetupEntry) -> Option<SkipReason> {
+        (self.state.applied.get(entry.get_description())
+            == self.started.get(entry.get_description()))
+        .then_some(SkipReason::Unchanged)
+    }
+
+    fn entry_finished(&mut self, result: &EntryResult) {
+        if !matches!(
+            result.status,
+            Status::Success | Status::Passed | Status::Warning
+        ) {
+            return;
+        }
+        if let Some(fingerprint) = self.started.remove(&result.description) {
+            self.state
+                .applied
+                .insert(result.description.clone(), fingerprint);
+            if let Err(e) = self.store.save(&self.state) {
+                Status::Warning.print_message(&format!("Failed to save applied state: {}", e));
+            }
+        }
+    }
+}
+
+impl SetupEntry {
+    /// Identifies the entry's definition (commands, steps, config); runtime state is ignored.
+    pub fn fingerprint(&self) -> String {
+        fingerprint(self)
+    }
+}
+
+impl SetupRegistry {
+    /// Runs only the entries that never succeeded or whose definition changed since they
+    /// last did, as recorded in `store`, so the manifest can be re-applied routinely.
+    pub fn execute_incremental(&mut self, store: &StateStore) -> RunReport {
+        let state = match store.load() {
+            Ok(state) => state,
+            Err(e) => {
+                Status::Failure.print_message(&format!("Failed to read state: {}", e));
+                return RunReport::default();
+            }
+        };
+
+        let mut tracker = AppliedTracker {
+            store,
+            state,
+            started: BTreeMap::new(),
+        };
+        self.run_observed(|_| true, &mut tracker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::CURRENT_VERSION;
+    use serde_json::json;
+    use std::fs;
+
+    fn registry(git: &str) -> SetupRegistry {
+        SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [
+                { "description": "Rust", "commands": [{ "command": "true" }] },
+                { "description": "Git", "commands": [{ "command": git }] }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_only_changed_entries_rerun() {
+        let path = std::env::temp_dir().join(format!("lsu-applied-{}.json", std::process::id()));
+        let store = StateStore::new(&path);
+
+        let first = registry("true").execute_incremental(&store);
+        assert_eq!(first.count(Status::Success), 2);
+
+        let second = registry("true").execute_incremental(&store);
+        assert_eq!(second.count(Status::Skipped), 2);
+        assert_eq!(second.entries[0].skip_reason, Some(SkipReason::Unchanged));
+
+        let changed = registry("echo changed > /dev/null").execute_incremental(&store);
+        let statuses: Vec<_> = changed
+            .entries
+            .iter()
+            .map(|entry| entry.status.clone())
+            .collect();
+        assert_eq!(statuses, [Status::Skipped, Status::Success]);
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,276 @@
+use std::mem;
+use std::path::Path;
+use std::time::Instant;
+
+use tokio::task::{JoinSet, LocalSet};
+
+use super::setup_entry::CommandChain;
+use super::setup_registry::Schedule;
+use super::{SetupEntry, SetupRegistry};
+use crate::report::{EntryResult, RunReport};
+use crate::traits::ProcessRunner;
+use crate::utils::{Cancellation, InterruptGuard, SkipReason, Status};
+use crate::CommandStruct;
+
+/// Resource class of entries that use the package manager; pacman and apt hold an
+/// exclusive lock, so only one of them runs at a time.
+const PACKAGE_MANAGER_CLASS: &str = "package-manager";
+
+impl SetupEntry {
+    async fn run_hooks_async(hooks: &[CommandStruct], cancel: &Cancellation) -> Status {
+        let mut status = Status::Success;
+        for hook in hooks {
+            match hook.execute_async(cancel).await {
+                Status::Aborted => return Status::Aborted,
+                Status::Failure => status = Status::Failure,
+                _ => (),
+            }
+        }
+        status
+    }
+
+    async fn run_commands_async(&self, cancel: &Cancellation) -> Status {
+        let mut chain = CommandChain::new(self);
+        let mut commands = self.commands().iter().peekable();
+        while let Some(command) = commands.next() {
+            if chain.skips(command) {
+                continue;
+            }
+            let result = command.execute_async(cancel).await;
+            if let std::ops::ControlFlow::Break(status) =
+                chain.record(command, result, commands.peek())
+            {
+                return status;
+            }
+        }
+        chain.status
+    }
+
+    async fn setup_status_async(&mut self, cancel: &Cancellation) -> Status {
+        if let Err(status) = self.begin_setup() {
+            return status;
+        }
+
+        let status = match Self::run_hooks_async(self.pre_hooks(), cancel).await {
+            Status::Failure => Status::Failure,
+            Status::Aborted => return Status::Aborted,
+            _ => self.finish_run(self.run_commands_async(cancel).await),
+        };
+        if status == Status::Aborted {
+            return status;
+        }
+        match Self::run_hooks_async(self.post_hooks(), cancel).await {
+            Status::Failure => Status::Failure,
+            Status::Aborted => Status::Aborted,
+            _ => status,
+        }
+    }
+
+    /// `setup_with_result` with commands and hooks run through `execute_async`. Steps,
+    /// config and checks still run synchronously. Step-through mode is not supported.
+    pub async fn setup_async(
+        &mut self,
+        bundle_dir: Option<&Path>,
+        cancel: &Cancellation,
+    ) -> EntryResult {
+        let started = Instant::now();
+        let status = match self.prepare_run(bundle_dir) {
+            Ok(()) => self.setup_status_async(cancel).await,
+            Err(status) => status,
+        };
+        self.entry_result(status, started)
+    }
+}
+
+impl SetupRegistry {
+    /// `execute_parallel` on the async runtime: up to `jobs` entries run at once as
+    /// tasks on the current thread, so waiting on a child process blocks no thread.
+    /// After `cancel`, running commands are killed and the remaining entries skipped.
+    /// Printing the summary is left to the caller.
+    ///
+    /// Entries are not `Sync`, so the future is not `Send`; drive it with
+    /// `Runtime::block_on` or from a `LocalSet`.
+    pub async fn execute_async(&mut self, jobs: usize, cancel: &Cancellation) -> RunReport {
+        if !Self::release_upgrade_guard() {
+            return RunReport::default();
+        }
+        let dependencies = match self
+            .sort_by_dependencies()
+            .and_then(|_| self.dependency_indices())
+        {
+            Ok(dependencies) => dependencies,
+            Err(e) => {
+                Status::Failure.print_message(&e.to_string());
+                return RunReport::default();
+            }
+        };
+        let _sudo = match self.start_sudo_session(|_| true) {
+            Ok(session) => session,
+            Err(e) => {
+                Status::Failure.print_message(&e);
+                return RunReport::default();
+            }
+        };
+
+        self.inherit_on_error();
+
+        let _interrupts = InterruptGuard::install();
+        let _workspace = self.prepare_workspace();
+        let names: Vec<String> = self
+            .entries()
+            .iter()
+            .map(|e| e.name().to_string())
+            .collect();
+        let classes: Vec<Vec<(String, usize)>> = self
+            .entries()
+            .iter()
+            .map(|e| {
+                let mut classes: Vec<(String, usize)> = e
+                    .resource_classes()
+                    .map(|class| (class.to_string(), self.resource_limit(class)))
+                    .collect();
+                if e.uses_package_manager() {
+                    classes.push((PACKAGE_MANAGER_CLASS.to_string(), 1));
+                }
+                classes
+            })
+            .collect();
+        let bundle_dir = self.bundle_dir().map(Path::to_path_buf);
+        let max_failures = self.max_failures();
+        let mut slots: Vec<Option<SetupEntry>> = mem::take(self.entries_vec())
+            .into_iter()
+            .map(Some)
+            .collect();
+        let mut schedule = Schedule::new(slots.len());
+
+        LocalSet::new()
+            .run_until(async {
+                let mut running = JoinSet::new();
+                loop {
+                    while running.len() < jobs.max(1) {
+                        let Some(index) = schedule.start_next(&dependencies, &classes) else {
+                            break;
+                        };
+                        let mut entry = slots[index].take().expect("entry is not running");
+                        let skip_reason = match cancel.is_cancelled() {
+                            true => Some(SkipReason::Interrupted),
+                            false => schedule.skip_reason(&dependencies[index], &names),
+                        };
+                        if let Some(reason) = skip_reason {
+                            let result = entry.skip_with(reason);
+                            schedule.finish(index, &classes[index], &entry, result, max_failures);
+                            slots[index] = Some(entry);
+                            continue;
+                        }
+
+                        let bundle_dir = bundle_dir.clone();
+                        let cancel = cancel.clone();
+                        running.spawn_local(async move {
+                            let result = entry.setup_async(bundle_dir.as_deref(), &cancel).await;
+                            (index, entry, result)
+                        });
+                    }
+
+                    let Some(joined) = running.join_next().await else {
+                        break;
+                    };
+                    let (index, entry, result) =
+                        joined.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()));
+                    schedule.finish(index, &classes[index], &entry, result, max_failures);
+                    slots[index] = Some(entry);
+                }
+            })
+            .await;
+
+        *self.entries_vec() = slots.into_iter().flatten().collect();
+        schedule.into_report()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::CURRENT_VERSION;
+    use serde_json::json;
+    use std::fs;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("lsu-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_execute_async_runs_entries_concurrently() {
+        // A and B each wait for the other to start, so they only pass when run together.
+        let dir = scratch_dir("async-rendezvous");
+        let meet = |own: &str, other: &str| {
+            format!(
+                "touch {0}/{1} && until [ -e {0}/{2} ]; do sleep 0.01; done",
+                dir.display(),
+                own,
+                other
+            )
+        };
+        let mut registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [
+                { "description": "A", "commands": [{ "command": meet("a", "b"), "timeout": "10s" }] },
+                { "description": "B", "commands": [{ "command": meet("b", "a"), "timeout": "10s" }] },
+                { "description": "After A", "depends_on": ["A"], "commands": [{ "command": "true" }] }
+            ]
+        }))
+        .unwrap();
+
+        let report = runtime().block_on(registry.execute_async(2, &Cancellation::new()));
+        fs::remove_dir_all(dir).unwrap();
+        assert!(report.is_success());
+        let descriptions: Vec<_> = report
+            .entries
+            .iter()
+            .map(|e| e.description.as_str())
+            .collect();
+        assert_eq!(descriptions, ["A", "B", "After A"]);
+        assert_eq!(registry.entries().len(), 3);
+    }
+
+    #[test]
+    fn test_cancel_kills_running_commands() {
+        let dir = scratch_dir("async-cancel");
+        let started = dir.join("started");
+        // The grandchild keeps the output pipes open, so the entry only finishes once
+        // the whole tree is gone.
+        let slow = format!("touch {} && sh -c 'sleep 30'", started.display());
+        let mut registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [
+                { "description": "Slow", "commands": [{ "command": slow }] },
+                { "description": "Later", "depends_on": ["Slow"], "commands": [{ "command": "true" }] }
+            ]
+        }))
+        .unwrap();
+
+        let cancel = Cancellation::new();
+        let report = runtime().block_on(async {
+            let stop = cancel.clone();
+            let marker = started.clone();
+            tokio::spawn(async move {
+                while !marker.exists() {
+                    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                }
+                stop.cancel();
+            });
+            registry.execute_async(1, &cancel).await
+        });
+        fs::remove_dir_all(dir).unwrap();
+        assert_eq!(report.entries[0].status, Status::Aborted);
+        assert_eq!(report.entries[1].skip_reason, Some(SkipReason::Interrupted));
+    }
+}
@@ -0,0 +1,92 @@
+use std::process;
+
+use serde_json::{json, Value};
+
+use super::{RegistryError, SetupRegistry};
+use crate::distribution::identify_linux_distribution;
+use crate::manifest::CURRENT_VERSION;
+
+fn installed_packages(mut command: process::Command) -> Result<Vec<String>, RegistryError> {
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(RegistryError::Io(std::io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Removes `null` fields so exported manifests only contain what was set.
+fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => (),
+    }
+}
+
+impl SetupRegistry {
+    /// Builds a starter registry with one package-install entry for the packages
+    /// explicitly installed on this machine (`pacman -Qqe` / `apt-mark showmanual` /
+    /// `dnf repoquery --userinstalled`).
+    pub fn export_from_system() -> Result<Self, RegistryError> {
+        let distribution = identify_linux_distribution();
+        let installer = distribution.package_installer().ok_or_else(|| {
+            RegistryError::Validation(
+                "cannot export packages from an unknown distribution".to_string(),
+            )
+        })?;
+        let packages = installed_packages(installer.list_installed_packages())?;
+
+        SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [{
+                "description": format!("Installed packages ({})", distribution),
+                "commands": [{
+                    "distribution": distribution,
+                    "command": packages.join(" "),
+                    "sudo": true,
+                    "use_package_manager": true
+                }]
+            }]
+        }))
+    }
+
+    /// Serializes the registry as a pretty-printed manifest, omitting unset fields.
+    pub fn to_json(&self) -> Result<String, RegistryError> {
+        let mut value = serde_json::to_value(self)?;
+        strip_nulls(&mut value);
+        Ok(serde_json::to_string_pretty(&value)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json_round_trip() {
+        let registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [{
+                "description": "Packages",
+                "commands": [{ "command": "git zsh", "distribution": "ArchLinux", "use_package_manager": true }]
+            }]
+        }))
+        .unwrap();
+
+        let exported = registry.to_json().unwrap();
+        assert!(!exported.contains("null"));
+        let reloaded = SetupRegistry::from_value(serde_json::from_str(&exported).unwrap()).unwrap();
+        assert_eq!(reloaded.entries()[0].commands()[0].command(), "git zsh");
+    }
+}
@@ -0,0 +1,178 @@
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+
+use super::{RegistryError, SetupRegistry};
+
+impl SetupRegistry {
+    /// Resolves every `depends_on` reference to the index of the entry it names.
+    pub(crate) fn dependency_indices(&self) -> Result<Vec<Vec<usize>>, RegistryError> {
+        let mut problems = Vec::new();
+        let indices = self
+            .entries()
+            .iter()
+            .map(|entry| {
+                entry
+                    .depends_on()
+                    .iter()
+                    .filter_map(|reference| {
+                        let found = self
+                            .entries()
+                            .iter()
+                            .position(|other| other.is_referenced_by(reference));
+                        if found.is_none() {
+                            problems.push(format!(
+                                "{}: `depends_on` refers to unknown entry {:?}",
+                                entry.location(),
+                                reference
+                            ));
+                        }
+                        found
+                    })
+                    .collect()
+            })
+            .collect();
+
+        if problems.is_empty() {
+            Ok(indices)
+        } else {
+            Err(RegistryError::Validation(problems.join("\n")))
+        }
+    }
+
+    /// Orders entries so each runs after everything it `depends_on`. Among entries that
+    /// are ready, higher `priority` goes first, then manifest order. Fails with the
+    /// entries forming a cycle.
+    pub fn execution_order(&self) -> Result<Vec<usize>, RegistryError> {
+        let dependencies = self.dependency_indices()?;
+        let rank = |index: usize| (Reverse(self.entries()[index].priority()), index);
+        let mut remaining: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+        let mut ready: BTreeSet<(Reverse<i32>, usize)> = (0..remaining.len())
+            .filter(|&index| remaining[index] == 0)
+            .map(rank)
+            .collect();
+        let mut order = Vec::with_capacity(remaining.len());
+
+        while let Some((_, index)) = ready.pop_first() {
+            order.push(index);
+            for (dependent, deps) in dependencies.iter().enumerate() {
+                for _ in deps.iter().filter(|&&dep| dep == index) {
+                    remaining[dependent] -= 1;
+                    if remaining[dependent] == 0 {
+                        ready.insert(rank(dependent));
+                    }
+                }
+            }
+        }
+
+        if order.len() == dependencies.len() {
+            return Ok(order);
+        }
+
+        // Walk unfinished dependencies from an unfinished entry until one repeats.
+        let mut path = vec![(0..remaining.len()).find(|&i| remaining[i] > 0).unwrap()];
+        loop {
+            let current = *path.last().unwrap();
+            let next = dependencies[current]
+                .iter()
+                .copied()
+                .find(|&dep| remaining[dep] > 0)
+                .unwrap();
+            if let Some(start) = path.iter().position(|&index| index == next) {
+                let names: Vec<_> = path[start..]
+                    .iter()
+                    .chain(std::iter::once(&next))
+                    .map(|&index| format!("{:?}", self.entries()[index].name()))
+                    .collect();
+                return Err(RegistryError::Validation(format!(
+                    "dependency cycle: {}",
+                    names.join(" -> ")
+                )));
+            }
+            path.push(next);
+        }
+    }
+
+    /// Reorders the entries into `execution_order`.
+    pub(crate) fn sort_by_dependencies(&mut self) -> Result<(), RegistryError> {
+        let order = self.execution_order()?;
+        let mut entries: Vec<_> = std::mem::take(self.entries_vec())
+            .into_iter()
+            .map(Some)
+            .collect();
+        *self.entries_vec() = order
+            .into_iter()
+            .map(|index| entries[index].take().unwrap())
+            .collect();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::CURRENT_VERSION;
+    use serde_json::json;
+
+    fn parse(entries: serde_json::Value) -> Result<SetupRegistry, RegistryError> {
+        SetupRegistry::from_value(json!({ "version": CURRENT_VERSION, "entries": entries }))
+    }
+
+    #[test]
+    fn test_dependencies_run_first() {
+        let mut registry = parse(json!([
+            { "description": "cargo install tools", "commands": [], "depends_on": ["rustup"] },
+            { "description": "Git", "commands": [] },
+            { "description": "Install rustup", "name": "rustup", "commands": [] }
+        ]))
+        .unwrap();
+
+        registry.sort_by_dependencies().unwrap();
+        let names: Vec<_> = registry.entries().iter().map(|e| e.name()).collect();
+        assert_eq!(names, ["Git", "rustup", "cargo install tools"]);
+    }
+
+    #[test]
+    fn test_priority_orders_ready_entries() {
+        let mut registry = parse(json!([
+            { "description": "Dotfiles", "commands": [] },
+            { "description": "Fonts", "commands": [], "priority": -1 },
+            { "description": "Enable multilib", "commands": [], "priority": 10 },
+            { "description": "Neovim", "commands": [], "depends_on": ["Dotfiles"], "priority": 50 },
+            { "description": "System update", "commands": [], "priority": 100 }
+        ]))
+        .unwrap();
+
+        registry.sort_by_dependencies().unwrap();
+        let names: Vec<_> = registry.entries().iter().map(|e| e.name()).collect();
+        assert_eq!(
+            names,
+            [
+                "System update",
+                "Enable multilib",
+                "Dotfiles",
+                "Neovim",
+                "Fonts"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cycles_and_unknown_references_are_rejected() {
+        let cycle = parse(json!([
+            { "description": "a", "commands": [], "depends_on": ["b"] },
+            { "description": "b", "commands": [], "depends_on": ["c"] },
+            { "description": "c", "commands": [], "depends_on": ["b"] }
+        ]));
+        match cycle {
+            Err(RegistryError::Validation(message)) => {
+                assert_eq!(message, "dependency cycle: \"b\" -> \"c\" -> \"b\"")
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+
+        assert!(matches!(
+            parse(json!([{ "description": "a", "commands": [], "depends_on": ["missing"] }])),
+            Err(RegistryError::Validation(_))
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use super::SetupRegistry;
+use crate::check::Check;
+use crate::DistributionType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintSeverity {
+    /// Probably a mistake, but the manifest still runs.
+    Warning,
+    /// The manifest will misbehave or panic when this runs.
+    Error,
+}
+
+/// One finding of `SetupRegistry::lint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintResult {
+    pub severity: LintSeverity,
+    /// Short stable identifier, e.g. `duplicate-description`.
+    pub code: &'static str,
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            LintSeverity::Warning => "warning",
+            LintSeverity::Error => "error",
+        };
+        write!(
+            f,
+            "{}[{}]: {}: {}",
+            severity, self.code, self.location, self.message
+        )
+    }
+}
+
+/// Shell checks only pass when they print something, so bare tests never pass.
+fn check_never_passes(script: &str) -> bool {
+    let script = script.trim();
+    let first = script.split_whitespace().next().unwrap_or("");
+    script.is_empty()
+        || matches!(script, "true" | "false" | ":")
+        || (matches!(first, "test" | "[" | "[[")
+            && !script.contains("echo")
+            && !script.contains("printf"))
+}
+
+impl SetupRegistry {
+    /// Flags manifests that parse but are probably wrong.
+    pub fn lint(&self) -> Vec<LintResult> {
+        let mut results = Vec::new();
+        let mut push = |severity, code, location: String, message: String| {
+            results.push(LintResult {
+                severity,
+                code,
+                location,
+                message,
+            })
+        };
+
+        let mut descriptions = HashSet::new();
+        for entry in self.entries() {
+            if !descriptions.insert(entry.get_description()) {
+                push(
+                    LintSeverity::Warning,
+                    "duplicate-description",
+                    entry.location(),
+                    "another entry has the same description; only the last one survives a merge"
+                        .to_string(),
+                );
+            }
+
+            if let Some(deprecation) = entry.deprecation() {
+                push(
+                    LintSeverity::Warning,
+                    "deprecated-entry",
+                    entry.location(),
+                    format!("{}; revert it and remove it from the manifest", deprecation),
+                );
+            }
+
+            if let Some(replacement) = entry.replaced_by() {
+                if !self
+                    .entries()
+                    .iter()
+                    .any(|e| e.is_referenced_by(replacement))
+                {
+                    push(
+                        LintSeverity::Warning,
+                        "unknown-replacement",
+                        entry.location(),
+                        format!(
+                            "`replaced_by` names {:?}, which is not defined",
+                            replacement
+                        ),
+                    );
+                }
+            }
+
+            let lists = [
+                ("pre_hooks", entry.pre_hooks()),
+                ("commands", entry.commands()),
+                ("post_hooks", entry.post_hooks()),
+            ];
+            let commands = lists.into_iter().flat_map(|(list, commands)| {
+                commands
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, command)| (format!("{}[{}]", list, index), command))
+            });
+            for (position, command) in commands {
+                let location = format!("{}: {}", entry.location(), position);
+
+                if let Some(Check::Shell(script)) = command.check() {
+                    if check_never_passes(script) {
+                        push(
+                            LintSeverity::Warning,
+                            "check-never-passes",
+                            location.clone(),
+                            format!(
+                                "check `{}` prints nothing, so it can never pass; add `&& echo ok`",
+                                script
+                            ),
+                        );
+                    }
+                }
+
+                let targets = command.distributions();
+                if !targets.is_empty()
+                    && targets
+                        .iter()
+                        .all(|target| **target == DistributionType::Unknown)
+                {
+                    push(
+                        LintSeverity::Warning,
+                        "unknown-distribution",
+                        location.clone(),
+                        "restricted to an unknown distribution, so it is skipped everywhere"
+                            .to_string(),
+                    );
+                }
+
+                if command.command().trim_start().starts_with("sudo ") && !command.uses_sudo() {
+                    push(
+                        LintSeverity::Warning,
+                        "inline-sudo",
+                        location.clone(),
+                        "runs `sudo` inline; set `\"sudo\": true` instead".to_string(),
+                    );
+                }
+
+                if command.uses_package_manager() && targets.is_empty() {
+                    push(
+                        LintSeverity::Error,
+                        "package-manager-without-distribution",
+                        location,
+                        "`use_package_manager` needs a `distribution` to pick the package manager"
+                            .to_string(),
+                    );
+                }
+            }
+        }
+
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::CURRENT_VERSION;
+    use serde_json::json;
+
+    #[test]
+    fn test_lint() {
+        let registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [
+                { "description": "Git", "commands": [
+                    { "command": "pacman -S git", "check": "test -x /usr/bin/git" },
+                    { "command": "sudo apt install git", "distribution": "Unknown" }
+                ] },
+                { "description": "Git", "commands": [
+                    { "command": "git", "use_package_manager": true },
+                    { "command": "true", "check": "command -v git" }
+                ] },
+                { "description": "Vim", "commands": [], "deprecated": true, "replaced_by": "Git" },
+                { "description": "Docker", "commands": [],
+                  "post_hooks": [{ "command": "sudo systemctl enable docker" }] }
+            ]
+        }))
+        .unwrap();
+
+        let codes: Vec<_> = registry.lint().iter().map(|r| r.code).collect();
+        assert_eq!(
+            codes,
+            [
+                "check-never-passes",
+                "unknown-distribution",
+                "inline-sudo",
+                "duplicate-description",
+                "package-manager-without-distribution",
+                "deprecated-entry",
+                "inline-sudo"
+            ]
+        );
+        assert_eq!(
+            registry.lint().last().unwrap().location,
+            "\"Docker\": post_hooks[0]"
+        );
+    }
+}
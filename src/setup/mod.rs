@@ -1,5 +1,26 @@
+mod applied;
+#[cfg(feature = "async")]
+mod async_run;
+mod export;
+mod graph;
+mod lint;
+mod phases;
+mod plan;
+mod preflight;
+mod promotion;
+mod registry_error;
+mod relations;
+mod selector;
 mod setup_entry;
 mod setup_registry;
+mod validation;
 
-pub use setup_entry::SetupEntry;
+pub use lint::{LintResult, LintSeverity};
+pub use phases::PhaseOptions;
+pub use plan::{Plan, PlanAction, PlanItem};
+pub use preflight::{BinarySource, MissingBinary};
+pub use promotion::{ContainerTest, Reconciler, CONTAINER_MANIFEST};
+pub use registry_error::RegistryError;
+pub use selector::EntrySelector;
+pub use setup_entry::{Scope, SetupEntry};
 pub use setup_registry::SetupRegistry;
@@ -0,0 +1,258 @@
+use std::ops::Range;
+use std::path::Path;
+use std::process;
+
+use super::{SetupEntry, SetupRegistry};
+use crate::report::RunReport;
+use crate::state::StateStore;
+use crate::utils::{interrupted, sha256_hex, write_file, InterruptGuard, SkipReason, Status};
+
+const RESUME_UNIT: &str = "/etc/systemd/system/linux-setup-ur-resume.service";
+
+/// How `execute_phased` persists progress and continues after a reboot.
+#[derive(Debug, Clone, Default)]
+pub struct PhaseOptions {
+    pub state: StateStore,
+    /// When set, a oneshot systemd unit running this command is installed so the
+    /// next phase starts automatically after the reboot.
+    pub resume_command: Option<String>,
+    /// Runs only the entries for this profile, as `execute_profile` does.
+    pub profile: Option<String>,
+    /// Runs only the entries selected by these tags, as `execute_filtered` does.
+    pub include_tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+}
+
+impl PhaseOptions {
+    fn selects(&self, entry: &SetupEntry) -> bool {
+        self.profile
+            .as_deref()
+            .is_none_or(|profile| entry.matches_profile(profile))
+            && entry.matches_tags(&self.include_tags, &self.exclude_tags)
+    }
+}
+
+/// Splits entries into phases, each ending with an entry marked `reboot_after`.
+fn phase_bounds<'a>(entries: impl IntoIterator<Item = &'a SetupEntry>) -> Vec<Range<usize>> {
+    let mut bounds = Vec::new();
+    let mut start = 0;
+    let mut len = 0;
+    for (index, entry) in entries.into_iter().enumerate() {
+        len = index + 1;
+        if entry.reboot_after() {
+            bounds.push(start..len);
+            start = len;
+        }
+    }
+    if start < len || bounds.is_empty() {
+        bounds.push(start..len);
+    }
+    bounds
+}
+
+fn sudo(args: &[&str]) -> bool {
+    process::Command::new("sudo")
+        .args(args)
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+fn install_resume_unit(command: &str) -> bool {
+    let unit = format!(
+        "[Unit]\nDescription=Resume linux_setup_ur after reboot\nAfter=network-online.target\nWants=network-online.target\n\n\
+         [Service]\nType=oneshot\nExecStart={}\n\n[Install]\nWantedBy=multi-user.target\n",
+        command
+    );
+    write_file(Path::new(RESUME_UNIT), &unit, true).is_ok()
+        && sudo(&["systemctl", "daemon-reload"])
+        && sudo(&["systemctl", "enable", "linux-setup-ur-resume.service"])
+}
+
+fn remove_resume_unit() {
+    if Path::new(RESUME_UNIT).exists() {
+        sudo(&["systemctl", "disable", "linux-setup-ur-resume.service"]);
+        sudo(&["rm", "-f", RESUME_UNIT]);
+    }
+}
+
+impl SetupRegistry {
+    /// Identifies the manifest and selection that a persisted phase index counts through.
+    fn phase_run(&self, options: &PhaseOptions) -> String {
+        sha256_hex(
+            format!(
+                "{} {:?} {:?} {:?}",
+                self.fingerprint(),
+                options.profile,
+                options.include_tags,
+                options.exclude_tags
+            )
+            .as_bytes(),
+        )
+    }
+
+    /// Runs the next pending phase of the entries `options` selects. Entries marked
+    /// `reboot_after` end a phase: once it succeeds the next phase index is persisted and
+    /// the run stops so the machine can reboot. After the last phase, or once the
+    /// manifest or selection changes, the next run starts again from the first phase.
+    pub fn execute_phased(&mut self, options: &PhaseOptions) -> RunReport {
+        let mut report = RunReport::default();
+        if !Self::release_upgrade_guard() {
+            return report;
+        }
+        if let Err(e) = self.sort_by_dependencies() {
+            Status::Failure.print_message(&e.to_string());
+            return report;
+        }
+
+        let mut state = match options.state.load() {
+            Ok(state) => state,
+            Err(e) => {
+                Status::Failure.print_message(&format!("Failed to read state: {}", e));
+                return report;
+            }
+        };
+
+        let selected: Vec<usize> = (0..self.entries().len())
+            .filter(|&index| options.selects(&self.entries()[index]))
+            .collect();
+        let bounds = phase_bounds(selected.iter().map(|&index| &self.entries()[index]));
+        let phase_run = self.phase_run(options);
+        if state.phase_run.as_deref() != Some(phase_run.as_str()) || state.phase >= bounds.len() {
+            state.phase = 0;
+            state.phase_run = Some(phase_run);
+        }
+
+        Status::Running.print_message(&format!("Phase {} of {}", state.phase + 1, bounds.len()));
+        let _interrupts = InterruptGuard::install();
+        let _workspace = self.prepare_workspace();
+        let bundle_dir = self.bundle_dir().map(Path::to_path_buf);
+        for &index in &selected[bounds[state.phase].clone()] {
+            let entry = &mut self.entries_mut()[index];
+            report.push(if interrupted() {
+                entry.skip_with(SkipReason::Interrupted)
+            } else {
+                entry.setup_with_result(bundle_dir.as_deref())
+            });
+        }
+
+        state.record_durations(&report);
+        if !report.is_success() {
+            if let Err(e) = options.state.save(&state) {
+                Status::Failure.print_message(&format!("Failed to save state: {}", e));
+            }
+            Status::Failure.print_message("Phase failed; fix the errors and run it again");
+            return report;
+        }
+
+        state.phase += 1;
+        let finished = state.phase == bounds.len();
+        if finished {
+            state.phase = 0;
+            state.phase_run = None;
+        }
+        if let Err(e) = options.state.save(&state) {
+            Status::Failure.print_message(&format!("Failed to save state: {}", e));
+            return report;
+        }
+
+        if !finished {
+            if let Some(command) = &options.resume_command {
+                if !install_resume_unit(command) {
+                    Status::Warning.print_message("Failed to install the resume unit");
+                }
+            }
+            Status::Warning.print_message(&format!(
+                "Reboot required before phase {} of {}",
+                state.phase + 1,
+                bounds.len()
+            ));
+        } else {
+            remove_resume_unit();
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    fn entries(reboots: &[bool]) -> Vec<SetupEntry> {
+        reboots
+            .iter()
+            .enumerate()
+            .map(|(index, reboot)| {
+                serde_json::from_value(json!({
+                    "description": format!("entry {}", index),
+                    "commands": [],
+                    "reboot_after": reboot
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn bounds(entries: &[SetupEntry]) -> Vec<(usize, usize)> {
+        phase_bounds(entries)
+            .into_iter()
+            .map(|range| (range.start, range.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_phase_bounds() {
+        assert_eq!(bounds(&entries(&[false, false])), [(0, 2)]);
+        assert_eq!(bounds(&entries(&[false, true, false])), [(0, 2), (2, 3)]);
+        assert_eq!(bounds(&entries(&[true, true])), [(0, 1), (1, 2)]);
+        assert_eq!(bounds(&[]), [(0, 0)]);
+    }
+
+    fn registry() -> SetupRegistry {
+        SetupRegistry::from_value(json!({
+            "version": crate::manifest::CURRENT_VERSION,
+            "entries": [
+                { "description": "Driver", "commands": [{ "command": "true" }], "reboot_after": true },
+                { "description": "Steam", "commands": [{ "command": "true" }], "profiles": ["gaming"], "reboot_after": true },
+                { "description": "Tools", "commands": [{ "command": "true" }] }
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn phase(options: &PhaseOptions) -> Vec<String> {
+        registry()
+            .execute_phased(options)
+            .entries
+            .into_iter()
+            .map(|entry| entry.description)
+            .collect()
+    }
+
+    #[test]
+    fn test_phases_restart_after_the_last_one() {
+        let path = std::env::temp_dir().join(format!("lsu-phases-{}.json", std::process::id()));
+        let options = PhaseOptions {
+            state: StateStore::new(&path),
+            profile: Some("work".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(phase(&options), ["Driver"]);
+        assert_eq!(options.state.load().unwrap().phase, 1);
+        assert_eq!(phase(&options), ["Tools"]);
+        assert_eq!(options.state.load().unwrap().phase, 0);
+        assert_eq!(phase(&options), ["Driver"]);
+
+        let gaming = PhaseOptions {
+            profile: Some("gaming".to_string()),
+            ..options.clone()
+        };
+        assert_eq!(phase(&gaming), ["Driver"]);
+        assert_eq!(phase(&gaming), ["Steam"]);
+        assert_eq!(phase(&gaming), ["Tools"]);
+        fs::remove_file(path).unwrap();
+    }
+}
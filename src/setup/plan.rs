@@ -0,0 +1,307 @@
+use std::fmt;
+use std::time::Duration;
+
+use super::{SetupEntry, SetupRegistry};
+use crate::distribution::declare_distributions;
+use crate::state::RunState;
+use crate::utils::{env_changes, format_duration, Color, EnvChange, SkipReason};
+
+/// What a run would do with an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanAction {
+    /// Every applicable command would run.
+    Run,
+    /// Some commands are already satisfied; the rest would run.
+    Change,
+    /// Nothing would run.
+    Skip,
+}
+
+impl PlanAction {
+    fn prefix(&self) -> char {
+        match self {
+            PlanAction::Run => '+',
+            PlanAction::Change => '~',
+            PlanAction::Skip => '-',
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            PlanAction::Run => Color::Green,
+            PlanAction::Change => Color::Yellow,
+            PlanAction::Skip => Color::Red,
+        }
+    }
+}
+
+/// One entry of a `Plan`.
+#[derive(Debug, Clone)]
+pub struct PlanItem {
+    pub description: String,
+    pub action: PlanAction,
+    /// Why the entry is skipped or only partly run.
+    pub reason: Option<String>,
+    /// Measured duration from the run history, else the entry's declared `estimate`.
+    pub estimate: Option<Duration>,
+    /// Set for deprecated entries, with what replaces them and how to clean them up.
+    pub deprecated: Option<String>,
+    /// Variables and `PATH` entries the entry's steps would add or modify.
+    pub env: Vec<EnvChange>,
+}
+
+/// What a run would do and roughly how long it would take.
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    pub items: Vec<PlanItem>,
+}
+
+impl Plan {
+    fn pending(&self) -> impl Iterator<Item = &PlanItem> {
+        self.items
+            .iter()
+            .filter(|item| item.action != PlanAction::Skip)
+    }
+
+    /// Sum of the known estimates of entries that would run; others count as zero.
+    pub fn total(&self) -> Duration {
+        self.pending().filter_map(|item| item.estimate).sum()
+    }
+
+    /// Estimated time left once the first `completed` items have been handled.
+    pub fn eta(&self, completed: usize) -> Duration {
+        self.items
+            .iter()
+            .skip(completed)
+            .filter(|item| item.action != PlanAction::Skip)
+            .filter_map(|item| item.estimate)
+            .sum()
+    }
+
+    pub fn unestimated(&self) -> usize {
+        self.pending()
+            .filter(|item| item.estimate.is_none())
+            .count()
+    }
+
+    pub fn count(&self, action: PlanAction) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.action == action)
+            .count()
+    }
+
+    /// Terraform-style listing with `+`/`~`/`-` prefixes and aligned columns;
+    /// pass `color: false` for output that is piped or written to a file.
+    pub fn render(&self, color: bool) -> String {
+        let width = self
+            .items
+            .iter()
+            .map(|item| item.description.chars().count())
+            .max()
+            .unwrap_or(0);
+        let estimates: Vec<String> = self
+            .items
+            .iter()
+            .map(|item| match (item.action, item.estimate) {
+                (PlanAction::Skip, _) | (_, None) => String::new(),
+                (_, Some(estimate)) => format!("~{}", format_duration(estimate)),
+            })
+            .collect();
+        let estimate_width = estimates.iter().map(String::len).max().unwrap_or(0);
+
+        let mut out = String::new();
+        for (item, estimate) in self.items.iter().zip(&estimates) {
+            let mut line = format!(
+                "{} {:<width$}  {:<estimate_width$}",
+                item.action.prefix(),
+                item.description,
+                estimate,
+            );
+            if let Some(reason) = &item.reason {
+                line.push_str(&format!("  ({})", reason));
+            }
+            if let Some(deprecated) = &item.deprecated {
+                line.push_str(&format!("  [{}]", deprecated));
+            }
+            let line = line.trim_end();
+            if color {
+                out.push_str(&format!("{}{}{}\n", item.action.color(), line, Color::None));
+            } else {
+                out.push_str(line);
+                out.push('\n');
+            }
+            if item.action != PlanAction::Skip {
+                for change in &item.env {
+                    out.push_str(&format!("    {}\n", change));
+                }
+            }
+        }
+
+        out.push_str(&format!(
+            "Plan: {} to run, {} to change, {} to skip. Estimated total: {}",
+            self.count(PlanAction::Run),
+            self.count(PlanAction::Change),
+            self.count(PlanAction::Skip),
+            format_duration(self.total())
+        ));
+        match self.unestimated() {
+            0 => {}
+            1 => out.push_str(" (1 entry without an estimate)"),
+            count => out.push_str(&format!(" ({} entries without an estimate)", count)),
+        }
+        out
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+/// Runs the entry's skip conditions and checks without executing anything.
+fn audit(entry: &SetupEntry) -> (PlanAction, Option<String>) {
+    if let Some(reason) = entry.evaluate_skip() {
+        return (PlanAction::Skip, Some(reason.to_string()));
+    }
+
+    let applicable: Vec<_> = entry
+        .commands()
+        .iter()
+        .filter(|command| !command.should_skip())
+        .collect();
+    let satisfied = applicable
+        .iter()
+        .filter(|command| command.check_passes())
+        .count();
+
+    match satisfied {
+        0 => (PlanAction::Run, None),
+        n if n == applicable.len() && !entry.has_steps_or_config() => {
+            (PlanAction::Skip, Some(SkipReason::CheckPassed.to_string()))
+        }
+        n => (
+            PlanAction::Change,
+            Some(format!(
+                "{} of {} commands already satisfied",
+                n,
+                applicable.len()
+            )),
+        ),
+    }
+}
+
+/// The deprecation notice for the plan, suggesting a revert when the entry has one.
+fn deprecation_notice(entry: &SetupEntry) -> Option<String> {
+    let notice = entry.deprecation()?;
+    match entry.config() {
+        Some(config) if config.is_revertible() => Some(format!(
+            "{}; revert it to remove it from this machine",
+            notice
+        )),
+        _ => Some(notice),
+    }
+}
+
+impl SetupRegistry {
+    /// Audits every entry and lists what would run with its expected duration,
+    /// preferring timings from `history`. Checks are executed, commands are not.
+    pub fn plan(&self, history: &RunState) -> Plan {
+        let _distributions = declare_distributions(self.distributions());
+        let items = self
+            .entries()
+            .iter()
+            .map(|entry| {
+                let (action, reason) = audit(entry);
+                PlanItem {
+                    description: entry.get_description().clone(),
+                    action,
+                    reason,
+                    estimate: history
+                        .duration_of(entry.get_description())
+                        .or_else(|| entry.estimate()),
+                    deprecated: deprecation_notice(entry),
+                    env: env_changes(&entry.env_assignments()),
+                }
+            })
+            .collect();
+        Plan { items }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::CURRENT_VERSION;
+    use serde_json::json;
+
+    #[test]
+    fn test_plan() {
+        let registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [
+                { "description": "System Update", "commands": [], "estimate": "5m" },
+                { "description": "Rust", "estimate": "2m", "commands": [
+                    { "command": "true", "check": "echo installed" },
+                    { "command": "true" }
+                ] },
+                { "description": "Dotfiles", "commands": [], "deprecated": true,
+                  "replaced_by": "Stow", "config": { "commands": [], "revert": [] } },
+                { "description": "Git", "estimate": "1m", "commands": [
+                    { "command": "true", "check": "echo installed" }
+                ] }
+            ]
+        }))
+        .unwrap();
+        let mut history = RunState::default();
+        history.durations.insert("Rust".to_string(), 90_000);
+
+        let plan = registry.plan(&history);
+        assert_eq!(plan.total(), Duration::from_secs(390));
+        assert_eq!(plan.eta(1), Duration::from_secs(90));
+        assert_eq!(
+            plan.render(false),
+            "+ System Update  ~5m\n\
+             ~ Rust           ~1m30s  (1 of 2 commands already satisfied)\n\
+             + Dotfiles               [deprecated, replaced by \"Stow\"; revert it to remove it from this machine]\n\
+             - Git                    (check passed, already satisfied)\n\
+             Plan: 2 to run, 1 to change, 1 to skip. Estimated total: 6m30s (1 entry without an estimate)"
+        );
+        assert!(plan.render(true).starts_with("\x1b[32m+ System Update"));
+    }
+
+    #[test]
+    fn test_plan_shows_environment_changes() {
+        std::env::set_var("LSU_PLAN_EDITOR", "nano");
+        let registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [{ "description": "Shell", "commands": [], "steps": [
+                { "shell_fragment": { "name": "lsu-plan", "rc_files": ["/nonexistent/rc"], "content":
+                    "export LSU_PLAN_EDITOR=nvim\nexport LSU_PLAN_PAGER='less -R'\nexport PATH=\"$HOME/.lsu-plan/bin:$PATH\"\n" } }
+            ] }]
+        }))
+        .unwrap();
+
+        let plan = registry.plan(&RunState::default());
+        assert_eq!(
+            plan.items[0].env,
+            [
+                EnvChange::Set {
+                    name: "LSU_PLAN_EDITOR".to_string(),
+                    value: "nvim".to_string(),
+                    previous: Some("nano".to_string()),
+                },
+                EnvChange::Set {
+                    name: "LSU_PLAN_PAGER".to_string(),
+                    value: "less -R".to_string(),
+                    previous: None,
+                },
+                EnvChange::PathEntry("$HOME/.lsu-plan/bin".to_string()),
+            ]
+        );
+        assert!(plan
+            .render(false)
+            .contains("\n    ~ LSU_PLAN_EDITOR=nvim (was nano)\n    + LSU_PLAN_PAGER=less -R\n"));
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use super::{SetupEntry, SetupRegistry};
+use crate::check::Check;
+use crate::condition::Facts;
+use crate::distribution::is_package_manager;
+
+/// Words that start a shell command but are not programs to look up.
+const SHELL_WORDS: [&str; 22] = [
+    "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac",
+    "in", "function", "{", "}", "!", "[[", "]]", "sudo", "env", "exec",
+];
+
+/// Package manager verbs that come before the package names.
+const INSTALL_VERBS: [&str; 4] = ["install", "add", "-S", "-Syu"];
+
+/// Where a binary that is missing now would come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinarySource {
+    /// Installed by this entry, which runs first.
+    Earlier(String),
+    /// Only installed by this entry, which runs after the one that needs it.
+    Later(String),
+    /// No entry installs it.
+    Never,
+}
+
+/// A program a command needs that is not on this system yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingBinary {
+    pub binary: String,
+    /// Entry and command that run it, e.g. `Neovim: commands[1]`.
+    pub location: String,
+    pub source: BinarySource,
+}
+
+impl MissingBinary {
+    /// Whether the run is expected to fail on it.
+    pub fn is_problem(&self) -> bool {
+        !matches!(self.source, BinarySource::Earlier(_))
+    }
+}
+
+impl fmt::Display for MissingBinary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: `{}` is missing; ", self.location, self.binary)?;
+        match &self.source {
+            BinarySource::Earlier(entry) => {
+                write!(f, "it will be installed earlier by `{}`", entry)
+            }
+            BinarySource::Later(entry) => write!(
+                f,
+                "it is only installed by `{}`, which runs later; add it to `depends_on`",
+                entry
+            ),
+            BinarySource::Never => write!(f, "no entry installs it"),
+        }
+    }
+}
+
+/// The programs `script` starts: the first word of each command in a list or pipeline,
+/// past variable assignments and `sudo`. Expansions and paths into `$HOME` are skipped.
+fn leading_executables(script: &str) -> Vec<String> {
+    script
+        .replace(">&", ">")
+        .split(['\n', ';', '|', '&', '(', ')', '`'])
+        .filter_map(|command| {
+            command
+                .split_whitespace()
+                .find(|word| !word.contains('=') && !SHELL_WORDS.contains(word))
+        })
+        .filter(|word| !word.starts_with(['-', '$', '"', '\'', '#', '~', '.', '<', '>']))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Binaries the entry installs, judged by package manager commands (package names are
+/// assumed to match their binaries) and by what its checks look for.
+fn provided_binaries(entry: &SetupEntry) -> BTreeSet<String> {
+    let mut provided = BTreeSet::new();
+    for command in entry.all_commands() {
+        let mut words = command.command().split_whitespace();
+        if command.uses_package_manager() {
+            provided.extend(words.map(str::to_string));
+        } else if words
+            .find(|word| *word != "sudo")
+            .is_some_and(is_package_manager)
+        {
+            provided.extend(
+                words
+                    .filter(|word| !word.starts_with('-') && !INSTALL_VERBS.contains(word))
+                    .map(str::to_string),
+            );
+        }
+
+        match command.check() {
+            Some(Check::Shell(script)) => {
+                let mut words = script.split_whitespace();
+                while let Some(word) = words.next() {
+                    if matches!(word, "-v" | "which" | "type") {
+                        provided.extend(words.next().map(str::to_string));
+                    }
+                }
+            }
+            Some(Check::MinVersion { binary, .. }) => {
+                provided.insert(binary.clone());
+            }
+            Some(Check::Provider { kind, params }) if kind == "binary_exists" => {
+                provided.extend(
+                    params
+                        .get("binary")
+                        .and_then(|v| v.as_str())
+                        .map(Into::into),
+                );
+            }
+            _ => (),
+        }
+    }
+    provided
+}
+
+impl SetupRegistry {
+    /// Preflight for broken orderings: finds the programs and shells that commands and
+    /// checks run but that are not installed yet, and says whether an earlier entry,
+    /// only a later one, or no entry at all installs them.
+    pub fn missing_binaries(&self) -> Vec<MissingBinary> {
+        let entries = self.entries();
+        let order = self
+            .execution_order()
+            .unwrap_or_else(|_| (0..entries.len()).collect());
+        let provided: Vec<BTreeSet<String>> = entries.iter().map(provided_binaries).collect();
+        let facts = Facts::gather();
+        let mut installed = HashMap::new();
+        let mut missing = Vec::new();
+
+        for (position, &index) in order.iter().enumerate() {
+            let entry = &entries[index];
+            for (command_index, command) in entry.commands().iter().enumerate() {
+                let location = format!("{}: commands[{}]", entry.location(), command_index);
+                let mut binaries =
+                    vec![command.shell().map_or("sh".to_string(), |s| s.to_string())];
+                if !command.uses_package_manager() {
+                    binaries.extend(leading_executables(command.command()));
+                }
+                if let Some(script) = command.check().and_then(Check::script) {
+                    binaries.extend(leading_executables(script));
+                }
+
+                let mut seen = BTreeSet::new();
+                for binary in binaries {
+                    if !seen.insert(binary.clone())
+                        || provided[index].contains(&binary)
+                        || *installed
+                            .entry(binary.clone())
+                            .or_insert_with(|| facts.command_exists(&binary))
+                    {
+                        continue;
+                    }
+
+                    let provider = |range: &[usize]| {
+                        range
+                            .iter()
+                            .find(|&&other| provided[other].contains(&binary))
+                            .map(|&other| entries[other].get_description().clone())
+                    };
+                    let source = match provider(&order[..position]) {
+                        Some(entry) => BinarySource::Earlier(entry),
+                        None => provider(&order[position + 1..])
+                            .map_or(BinarySource::Never, BinarySource::Later),
+                    };
+                    missing.push(MissingBinary {
+                        binary,
+                        location: location.clone(),
+                        source,
+                    });
+                }
+            }
+        }
+        missing
+    }
+
+    /// Preflight for custom shells that are not installed or do not take `-c`, naming
+    /// the entry that uses them. Commands that would be skipped here are left out, since
+    /// a shell for another distribution need not exist on this one.
+    pub fn unusable_shells(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for entry in self.entries() {
+            let shells = entry
+                .all_commands()
+                .filter(|command| !command.should_skip())
+                .filter_map(|command| command.shell());
+            for shell in shells {
+                if let Err(e) = shell.verify() {
+                    problems.push(format!("{}: {}", entry.location(), e));
+                }
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::CURRENT_VERSION;
+    use serde_json::json;
+
+    #[test]
+    fn test_leading_executables() {
+        assert_eq!(
+            leading_executables(
+                "FOO=1 sudo lsu-a --flag 2>&1 | lsu-b && $HOME/bin/x; if lsu-c; then :; fi"
+            ),
+            ["lsu-a", "lsu-b", "lsu-c", ":"]
+        );
+    }
+
+    #[test]
+    fn test_missing_binaries() {
+        let registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [
+                { "description": "Plugins", "commands": [
+                    { "command": "lsu-fake-nvim --headless +PlugInstall" },
+                    { "command": "lsu-fake-tool", "check": "lsu-fake-probe" }
+                ] },
+                { "description": "Neovim", "commands": [
+                    { "command": "lsu-fake-nvim", "use_package_manager": true, "distribution": "ArchLinux" }
+                ] },
+                { "description": "Config", "depends_on": ["Neovim"], "commands": [
+                    { "command": "lsu-fake-nvim -es" }
+                ] }
+            ]
+        }))
+        .unwrap();
+
+        let missing: Vec<_> = registry
+            .missing_binaries()
+            .into_iter()
+            .map(|m| (m.binary, m.source))
+            .collect();
+        assert_eq!(
+            missing,
+            [
+                (
+                    "lsu-fake-nvim".to_string(),
+                    BinarySource::Later("Neovim".to_string())
+                ),
+                ("lsu-fake-tool".to_string(), BinarySource::Never),
+                ("lsu-fake-probe".to_string(), BinarySource::Never),
+                (
+                    "lsu-fake-nvim".to_string(),
+                    BinarySource::Earlier("Neovim".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unusable_shells() {
+        let registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [{ "description": "Fish", "commands": [
+                { "command": "true", "shell": { "Custom": "lsu-no-such-shell" } },
+                { "command": "true", "shell": { "Custom": "sh" } },
+                { "command": "true", "shell": { "Custom": "lsu-other-shell" }, "distribution": "Gentoo" }
+            ] }]
+        }))
+        .unwrap();
+
+        let problems = registry.unusable_shells();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].ends_with("custom shell `lsu-no-such-shell` not found"));
+    }
+}
@@ -0,0 +1,283 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use std::{fs, process, thread};
+
+use super::{LintSeverity, Plan, RegistryError, SetupRegistry};
+use crate::report::RunReport;
+use crate::state::{RunState, StateStore};
+use crate::utils::{fingerprint, interrupted, InterruptGuard, Status, Workspace};
+
+/// Where the manifest under test is mounted inside the container, read-only.
+pub const CONTAINER_MANIFEST: &str = "/lsu/manifest.json";
+
+/// A throwaway container that stands in for the team's machines while a manifest is
+/// tried out before `promote_in_container` promotes it.
+#[derive(Debug, Clone)]
+pub struct ContainerTest {
+    /// `docker` or `podman`.
+    pub runtime: String,
+    /// e.g. `archlinux:latest`.
+    pub image: String,
+    /// Applies the manifest at `CONTAINER_MANIFEST` inside the image, e.g.
+    /// `["lsu", "run", "/lsu/manifest.json"]`; the test passes when it exits successfully.
+    pub command: Vec<String>,
+}
+
+impl ContainerTest {
+    fn container_command(&self, dir: &Path) -> process::Command {
+        let mount = Path::new(CONTAINER_MANIFEST)
+            .parent()
+            .unwrap_or(Path::new("/"));
+        let mut command = process::Command::new(&self.runtime);
+        command
+            .args(["run", "--rm", "--volume"])
+            .arg(format!("{}:{}:ro", dir.display(), mount.display()))
+            .arg(&self.image)
+            .args(&self.command);
+        command
+    }
+
+    /// Applies `registry` in a fresh container from `image`.
+    fn run(&self, registry: &SetupRegistry) -> Result<(), RegistryError> {
+        let workspace = Workspace::create(false)?;
+        let manifest = serde_json::to_string_pretty(registry)?;
+        fs::write(workspace.path().join("manifest.json"), manifest)?;
+
+        let status = self.container_command(workspace.path()).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(RegistryError::Validation(format!(
+                "not promoted: the container test in {} failed ({})",
+                self.image, status
+            )))
+        }
+    }
+}
+
+impl SetupRegistry {
+    /// Identifies the manifest's content; changing any entry changes it.
+    pub fn fingerprint(&self) -> String {
+        fingerprint(self)
+    }
+
+    /// Whether this exact manifest is the one last promoted in `state`.
+    pub fn is_promoted(&self, state: &RunState) -> bool {
+        state.promoted.as_deref() == Some(self.fingerprint().as_str())
+    }
+
+    /// Canary step for shared setups: audits the manifest without running any command
+    /// (lint errors fail it, checks run to build the plan) and, once it passes,
+    /// records it in `store` as the promoted version. Returns the audited plan.
+    pub fn promote(&self, store: &StateStore) -> Result<Plan, RegistryError> {
+        self.audit()?;
+        self.record_promotion(store)
+    }
+
+    /// `promote`, but the manifest must also apply cleanly in `test`'s container
+    /// between the audit and the promotion.
+    pub fn promote_in_container(
+        &self,
+        store: &StateStore,
+        test: &ContainerTest,
+    ) -> Result<Plan, RegistryError> {
+        self.audit()?;
+        test.run(self)?;
+        self.record_promotion(store)
+    }
+
+    /// Fails with every lint error, if there are any.
+    fn audit(&self) -> Result<(), RegistryError> {
+        let errors: Vec<String> = self
+            .lint()
+            .iter()
+            .filter(|result| result.severity == LintSeverity::Error)
+            .map(ToString::to_string)
+            .collect();
+        if !errors.is_empty() {
+            return Err(RegistryError::Validation(format!(
+                "not promoted:\n{}",
+                errors.join("\n")
+            )));
+        }
+        Ok(())
+    }
+
+    fn record_promotion(&self, store: &StateStore) -> Result<Plan, RegistryError> {
+        let mut state = store.load()?;
+        let plan = self.plan(&state);
+        state.promoted = Some(self.fingerprint());
+        store.save(&state)?;
+        Status::Passed.print_message(&format!("Promoted manifest {}", self.fingerprint()));
+        Ok(plan)
+    }
+
+    /// Runs every entry, but only if this manifest was promoted, so unattended runs
+    /// never apply a change that has not passed `promote`.
+    pub fn execute_promoted(&mut self, store: &StateStore) -> Result<RunReport, RegistryError> {
+        if !self.is_promoted(&store.load()?) {
+            return Err(RegistryError::Validation(format!(
+                "manifest {} has not been promoted",
+                self.fingerprint()
+            )));
+        }
+        Ok(self.execute())
+    }
+}
+
+/// Keeps a machine in line with a shared manifest, re-reading and applying it on an
+/// interval, but only while the manifest on disk is the promoted one: a bad commit
+/// pulled in between two promotions is refused until it passes `promote`.
+#[derive(Debug, Clone)]
+pub struct Reconciler {
+    manifest: PathBuf,
+    store: StateStore,
+    interval: Duration,
+}
+
+impl Reconciler {
+    pub fn new(manifest: &Path, store: StateStore, interval: Duration) -> Self {
+        Reconciler {
+            manifest: manifest.to_path_buf(),
+            store,
+            interval,
+        }
+    }
+
+    /// One round: loads the manifest afresh and runs it if it is promoted. `Ok(None)`
+    /// when it was refused for not being promoted.
+    pub fn reconcile(&self) -> Result<Option<RunReport>, RegistryError> {
+        let mut registry = SetupRegistry::load(&self.manifest)?;
+        if !registry.is_promoted(&self.store.load()?) {
+            Status::Warning.print_message(&format!(
+                "Refusing to apply {}: manifest {} has not been promoted",
+                self.manifest.display(),
+                registry.fingerprint()
+            ));
+            return Ok(None);
+        }
+        Ok(Some(registry.execute()))
+    }
+
+    /// Reconciles every `interval` until SIGINT or SIGTERM, printing the summary of each
+    /// round. A round that fails to load the manifest or state is reported and retried
+    /// on the next one.
+    pub fn run(&self) {
+        let _interrupts = InterruptGuard::install();
+        while !interrupted() {
+            match self.reconcile() {
+                Ok(Some(report)) => report.print_summary(),
+                Ok(None) => {}
+                Err(e) => Status::Failure.print_message(&e.to_string()),
+            }
+            let next = Instant::now() + self.interval;
+            while !interrupted() && Instant::now() < next {
+                thread::sleep(
+                    next.saturating_duration_since(Instant::now())
+                        .min(Duration::from_millis(200)),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::CURRENT_VERSION;
+    use serde_json::json;
+    use std::fs;
+
+    fn registry(command: &str) -> SetupRegistry {
+        SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [{ "description": "Git", "commands": [{ "command": command }] }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_only_promoted_manifests_run() {
+        let path = std::env::temp_dir().join(format!("lsu-promote-{}.json", std::process::id()));
+        let store = StateStore::new(&path);
+        let mut blessed = registry("true");
+        let mut changed = registry("echo changed");
+        assert_ne!(blessed.fingerprint(), changed.fingerprint());
+
+        assert!(blessed.execute_promoted(&store).is_err());
+        blessed.promote(&store).unwrap();
+        assert!(blessed.execute_promoted(&store).unwrap().is_success());
+        assert!(changed.execute_promoted(&store).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_container_test_command() {
+        let test = ContainerTest {
+            runtime: "podman".to_string(),
+            image: "archlinux:latest".to_string(),
+            command: vec![
+                "lsu".to_string(),
+                "run".to_string(),
+                CONTAINER_MANIFEST.to_string(),
+            ],
+        };
+        let command = test.container_command(Path::new("/tmp/lsu-canary"));
+        assert_eq!(command.get_program(), "podman");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            [
+                "run",
+                "--rm",
+                "--volume",
+                "/tmp/lsu-canary:/lsu:ro",
+                "archlinux:latest",
+                "lsu",
+                "run",
+                "/lsu/manifest.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failed_container_test_does_not_promote() {
+        let path = std::env::temp_dir().join(format!("lsu-canary-{}.json", std::process::id()));
+        let store = StateStore::new(&path);
+        let test = ContainerTest {
+            runtime: "false".to_string(),
+            image: "archlinux:latest".to_string(),
+            command: Vec::new(),
+        };
+        assert!(registry("true")
+            .promote_in_container(&store, &test)
+            .is_err());
+        assert!(!registry("true").is_promoted(&store.load().unwrap()));
+    }
+
+    #[test]
+    fn test_reconciler_refuses_unpromoted_manifests() {
+        let dir = std::env::temp_dir().join(format!("lsu-reconcile-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let manifest = dir.join("setup.json");
+        let store = StateStore::new(&dir.join("state.json"));
+        let reconciler = Reconciler::new(&manifest, store.clone(), Duration::from_secs(60));
+        let write = |command: &str| {
+            let value = serde_json::to_string(&registry(command)).unwrap();
+            fs::write(&manifest, value).unwrap();
+        };
+
+        write("true");
+        assert!(reconciler.reconcile().unwrap().is_none());
+        SetupRegistry::load(&manifest)
+            .unwrap()
+            .promote(&store)
+            .unwrap();
+        assert!(reconciler.reconcile().unwrap().unwrap().is_success());
+
+        write("echo unreviewed");
+        assert!(reconciler.reconcile().unwrap().is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
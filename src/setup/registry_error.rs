@@ -0,0 +1,40 @@
+use std::{error, fmt, io};
+
+/// Errors raised while loading or validating a `SetupRegistry`.
+#[derive(Debug)]
+pub enum RegistryError {
+    Io(io::Error),
+    Parse(String),
+    Validation(String),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Io(e) => write!(f, "failed to read manifest: {}", e),
+            RegistryError::Parse(message) => write!(f, "failed to parse manifest: {}", message),
+            RegistryError::Validation(message) => write!(f, "invalid manifest: {}", message),
+        }
+    }
+}
+
+impl error::Error for RegistryError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RegistryError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RegistryError {
+    fn from(e: io::Error) -> Self {
+        RegistryError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for RegistryError {
+    fn from(e: serde_json::Error) -> Self {
+        RegistryError::Parse(e.to_string())
+    }
+}
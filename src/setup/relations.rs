@@ -0,0 +1,122 @@
+use super::{SetupEntry, SetupRegistry};
+
+/// Whether some profile selects both entries; entries without profiles run in every profile.
+fn may_run_together(a: &SetupEntry, b: &SetupEntry) -> bool {
+    match (a.profiles(), b.profiles()) {
+        (Some(a), Some(b)) => a.iter().any(|profile| b.contains(profile)),
+        _ => true,
+    }
+}
+
+/// Whether every profile that selects `entry` also selects `required`.
+fn always_selected_with(required: &SetupEntry, entry: &SetupEntry) -> bool {
+    match (required.profiles(), entry.profiles()) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(required), Some(entry)) => entry.iter().all(|profile| required.contains(profile)),
+    }
+}
+
+impl SetupRegistry {
+    /// Checks `requires` and `conflicts` against the entries each profile would select.
+    pub(crate) fn relation_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let find = |reference: &str| {
+            self.entries()
+                .iter()
+                .find(|entry| entry.is_referenced_by(reference))
+        };
+
+        for entry in self.entries() {
+            for reference in entry.requires() {
+                match find(reference) {
+                    None => problems.push(format!(
+                        "{}: requires unknown entry {:?}",
+                        entry.location(),
+                        reference
+                    )),
+                    Some(required) if !always_selected_with(required, entry) => {
+                        problems.push(format!(
+                            "{}: requires {:?}, which some of its profiles do not include",
+                            entry.location(),
+                            reference
+                        ))
+                    }
+                    Some(_) => (),
+                }
+            }
+
+            for reference in entry.conflicts() {
+                match find(reference) {
+                    None => problems.push(format!(
+                        "{}: conflicts with unknown entry {:?}",
+                        entry.location(),
+                        reference
+                    )),
+                    Some(other) if may_run_together(entry, other) => problems.push(format!(
+                        "{}: conflicts with {:?}, but both can run in the same profile",
+                        entry.location(),
+                        reference
+                    )),
+                    Some(_) => (),
+                }
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::CURRENT_VERSION;
+    use crate::RegistryError;
+    use serde_json::json;
+
+    fn parse(entries: serde_json::Value) -> Result<SetupRegistry, RegistryError> {
+        SetupRegistry::from_value(json!({ "version": CURRENT_VERSION, "entries": entries }))
+    }
+
+    #[test]
+    fn test_conflicts_need_disjoint_profiles() {
+        assert!(parse(json!([
+            { "description": "tlp", "commands": [], "conflicts": ["power-profiles-daemon"] },
+            { "description": "power-profiles-daemon", "commands": [] }
+        ]))
+        .is_err());
+
+        assert!(parse(json!([
+            { "description": "tlp", "commands": [], "profiles": ["laptop"],
+              "conflicts": ["power-profiles-daemon"] },
+            { "description": "power-profiles-daemon", "commands": [], "profiles": ["desktop"] }
+        ]))
+        .is_ok());
+
+        let unknown = parse(json!([
+            { "description": "tlp", "commands": [], "conflicts": ["power-profile-daemon"] }
+        ]));
+        assert!(matches!(unknown, Err(RegistryError::Validation(message))
+            if message.contains("conflicts with unknown entry \"power-profile-daemon\"")));
+    }
+
+    #[test]
+    fn test_requirements_must_be_selected_together() {
+        assert!(parse(json!([
+            { "description": "cargo tools", "commands": [], "requires": ["rustup"] }
+        ]))
+        .is_err());
+
+        assert!(parse(json!([
+            { "description": "cargo tools", "commands": [], "requires": ["rustup"] },
+            { "description": "rustup", "commands": [], "profiles": ["dev"] }
+        ]))
+        .is_err());
+
+        assert!(parse(json!([
+            { "description": "cargo tools", "commands": [], "profiles": ["dev"],
+              "requires": ["rustup"] },
+            { "description": "rustup", "commands": [] }
+        ]))
+        .is_ok());
+    }
+}
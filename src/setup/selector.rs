@@ -0,0 +1,24 @@
+/// Picks out a single entry: by its `name` or description, or by its position in the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntrySelector {
+    Name(String),
+    Index(usize),
+}
+
+impl From<&str> for EntrySelector {
+    fn from(name: &str) -> Self {
+        EntrySelector::Name(name.to_string())
+    }
+}
+
+impl From<String> for EntrySelector {
+    fn from(name: String) -> Self {
+        EntrySelector::Name(name)
+    }
+}
+
+impl From<usize> for EntrySelector {
+    fn from(index: usize) -> Self {
+        EntrySelector::Index(index)
+    }
+}
@@ -1,17 +1,37 @@
-use std::io::Write;
-use std::path::PathBuf;
+use std::cell::Cell;
+use std::ops::ControlFlow;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use std::{fs, io};
 
 use serde::{Deserialize, Serialize};
 
+use crate::artifact::{self, Artifact, BUNDLE_PACKAGES};
+use crate::command::RequiresPrevious;
+use crate::condition::condition_met;
+use crate::distribution::{
+    container_runtime, identify_linux_distribution, is_package_manager, release_skip,
+};
+use crate::engine::{review, DebugAction};
+use crate::manifest::Source;
+use crate::report::{CommandTiming, EntryResult};
 use crate::traits::executable_setup::ExecutableSetup;
 use crate::traits::ProcessRunner;
-use crate::Configurator;
+use crate::utils::reporter;
+use crate::utils::SkipReason;
+use crate::utils::{invoking_user, is_root, parse_duration, OnError, Workspace};
 use crate::{utils::Status, CommandStruct, Config};
+use crate::{Configurator, DistributionType, Step};
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 struct SetupItem {
     env_vars: Option<Vec<String>>,
+    #[serde(
+        default,
+        deserialize_with = "crate::utils::path::deserialize_expanded_path"
+    )]
     working_dir: Option<PathBuf>,
 }
 
@@ -20,7 +40,7 @@ impl SetupItem {
         if let Some(dir) = &self.working_dir {
             if !dir.exists() {
                 fs::create_dir_all(dir)?;
-                println!("Created directory: {:?}", dir);
+                reporter().line(&format!("Created directory: {:?}", dir));
             }
         }
         Ok(())
@@ -30,18 +50,23 @@ impl SetupItem {
         if let Some(vars) = &mut self.env_vars {
             for env_var in vars.iter() {
                 if std::env::var(env_var).is_err() {
-                    println!("Environment variable `{}` not set.", env_var);
+                    reporter().line(&format!("Environment variable `{}` not set.", env_var));
                     let input = Self::get_env_value(env_var)?;
 
-                    print!("You entered: {}. Is this correct? (y/n): ", input);
-                    io::stdout().flush()?;
+                    reporter().prompt(&format!("You entered: {}. Is this correct? (y/n): ", input));
                     let mut confirm = String::new();
                     io::stdin().read_line(&mut confirm)?;
                     if confirm.trim().to_lowercase() == "y" {
-                        println!("Environment variable {} set to: {}", env_var, input);
+                        reporter().line(&format!(
+                            "Environment variable {} set to: {}",
+                            env_var, input
+                        ));
                         std::env::set_var(env_var, input);
                     } else {
-                        println!("Skipping setting environment variable {}.", env_var);
+                        reporter().line(&format!(
+                            "Skipping setting environment variable {}.",
+                            env_var
+                        ));
                     }
                 }
             }
@@ -51,40 +76,665 @@ impl SetupItem {
 
     fn get_env_value(env_var: &String) -> Result<String, io::Error> {
         let mut input = String::new();
-        print!("Enter value for `{}`: ", env_var);
-        io::stdout().flush()?;
+        reporter().prompt(&format!("Enter value for `{}`: ", env_var));
         io::stdin().read_line(&mut input)?;
         let input = input.trim().to_string();
         Ok(input)
     }
 }
 
+/// Whose context an entry's commands need.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// System-wide changes; fine to run as root.
+    #[default]
+    System,
+    /// Touches the invoking user's `$HOME` (dotfiles, user services) and must not run as root.
+    User,
+}
+
+/// How much of each failed command's stderr an `EntryResult` keeps for the summary.
+const STDERR_TAIL_LINES: usize = 3;
+
+/// The state carried from one command of an entry to the next: `requires_previous`
+/// decides whether a command runs, and a failure followed by a `requires_previous:
+/// failure` fallback only fails the entry if the fallback fails too.
+pub(super) struct CommandChain<'a> {
+    entry: &'a SetupEntry,
+    previous: Option<(&'a CommandStruct, Status)>,
+    pub(super) status: Status,
+}
+
+impl<'a> CommandChain<'a> {
+    pub(super) fn new(entry: &'a SetupEntry) -> Self {
+        entry.prompt_answer.set(None);
+        CommandChain {
+            entry,
+            previous: None,
+            status: Status::Success,
+        }
+    }
+
+    /// Records `command` as skipped when the previous outcome does not allow it to run.
+    pub(super) fn skips(&self, command: &CommandStruct) -> bool {
+        let previous = self
+            .previous
+            .as_ref()
+            .map_or(Status::Success, |(_, status)| status.clone());
+        match command.requires_previous().skip_reason(&previous) {
+            Some(reason) => {
+                command.record_skip(Status::Skipped, reason);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Takes in how `command` ended; `Break` stops the entry with that status.
+    pub(super) fn record(
+        &mut self,
+        command: &'a CommandStruct,
+        result: Status,
+        next: Option<&&CommandStruct>,
+    ) -> ControlFlow<Status> {
+        if let Some((failed, Status::Failure)) = &self.previous {
+            if command.requires_previous() == RequiresPrevious::Failure
+                && !matches!(result, Status::Failure | Status::Aborted)
+            {
+                failed.mark_recovered();
+            }
+        }
+        self.previous = Some((command, result.clone()));
+        match result {
+            Status::Aborted => return ControlFlow::Break(Status::Aborted),
+            Status::Failure => (),
+            _ => return ControlFlow::Continue(()),
+        }
+        if self.entry.was_aborted() {
+            return ControlFlow::Break(Status::Failure);
+        }
+        if next.is_some_and(|next| next.requires_previous() == RequiresPrevious::Failure) {
+            return ControlFlow::Continue(());
+        }
+        self.status = Status::Failure;
+        let policy = command.on_error().unwrap_or(self.entry.on_error());
+        let proceed = policy.should_continue(&format!("Command `{}`", command.command()));
+        if policy == OnError::Prompt {
+            self.entry.prompt_answer.set(Some(proceed));
+        }
+        match proceed {
+            true => ControlFlow::Continue(()),
+            false => ControlFlow::Break(Status::Failure),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SetupEntry {
     commands: Vec<CommandStruct>,
+    /// Run before `commands`; a failing pre hook fails the entry without running it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pre_hooks: Vec<CommandStruct>,
+    /// Run after everything else, even when the entry failed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    post_hooks: Vec<CommandStruct>,
+    steps: Option<Vec<Step>>,
+    artifacts: Option<Vec<Artifact>>,
     config: Option<Config>,
     setup: Option<SetupItem>,
     description: String,
+    /// Short identifier for `depends_on`; the description is used when absent.
+    name: Option<String>,
+    /// Where to read more, e.g. the team wiki page; shown when the entry fails.
+    docs_url: Option<String>,
+    /// Longer explanation of why the entry exists and what to do when it breaks.
+    notes: Option<String>,
+    /// Resource class such as `apt` or `network`; `execute_parallel` caps how many
+    /// entries of a class run at once, see the registry's `resource_limits`.
+    lock: Option<String>,
+    /// Further resource classes the entry strains, e.g. `cpu-heavy` for an AUR build.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    resources: Vec<String>,
+    /// Entries with a higher priority run earlier, unless `depends_on` says otherwise;
+    /// equal priorities keep manifest order. Defaults to 0.
+    priority: Option<i32>,
+    /// Entries (by name or description) that must run first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<String>,
+    /// Entries that must also be part of any run that includes this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    requires: Vec<String>,
+    /// Entries that must never run alongside this one, e.g. `tlp` and `power-profiles-daemon`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    conflicts: Vec<String>,
+    profiles: Option<Vec<String>>,
+    tags: Option<Vec<String>>,
+    /// Rough duration such as `5m`, used for plans until real timings are recorded.
+    estimate: Option<String>,
+    when: Option<String>,
+    /// Oldest distribution release the entry applies to, e.g. `22.04` or `39`.
+    min_version: Option<String>,
+    /// Newest distribution release the entry applies to; package names and PPAs
+    /// change between releases.
+    max_version: Option<String>,
+    reboot_after: Option<bool>,
+    /// Skips the entry inside Docker, Podman or LXC, e.g. for systemd or GRUB setup
+    /// that only makes sense on a booted machine.
+    skip_in_container: Option<bool>,
+    /// `user` entries are de-escalated to `$SUDO_USER` (or refused) when run as root.
+    scope: Option<Scope>,
+    /// Gives the entry its own subdirectory of the run's temporary workspace.
+    own_tmpdir: Option<bool>,
+    /// What to do with the remaining commands, and the rest of the run, when this entry fails.
+    on_error: Option<OnError>,
+    /// On failure, revert applied steps and config and run `undo` commands in reverse order.
+    rollback: Option<bool>,
+    /// Kept only so existing machines can be cleaned up; new setups should not use it.
+    deprecated: Option<bool>,
+    /// The entry (by name or description) that supersedes a deprecated one.
+    replaced_by: Option<String>,
+    #[serde(skip)]
+    source: Option<Source>,
+    #[serde(skip)]
+    skip_reason: Option<SkipReason>,
+    /// Pause after each command to review it, see `SetupRegistry::execute_step_through`.
+    #[serde(skip)]
+    step_through: bool,
+    /// Set when the user aborted the run while stepping through this entry.
+    #[serde(skip)]
+    aborted: Cell<bool>,
+    /// How the user answered the `on_error` prompt for a failed command, so the entry's
+    /// own prompt does not ask about the same failure again.
+    #[serde(skip)]
+    pub(super) prompt_answer: Cell<Option<bool>>,
 }
 impl SetupEntry {
     pub fn get_description(&self) -> &String {
         &self.description
     }
 
-    fn run_commands(&self) -> Status {
-        let failed = self
-            .commands
-            .iter()
-            .filter(|command| command.execute() == Status::Failure)
-            .count();
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.description)
+    }
 
-        if failed > 0 {
-            return Status::Failure;
+    pub fn docs_url(&self) -> Option<&str> {
+        self.docs_url.as_deref()
+    }
+
+    pub fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+
+    /// Whether `reference` (from `depends_on` and similar) points at this entry.
+    pub fn is_referenced_by(&self, reference: &str) -> bool {
+        self.name.as_deref() == Some(reference) || self.description == reference
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority.unwrap_or(0)
+    }
+
+    pub fn lock(&self) -> Option<&str> {
+        self.lock.as_deref()
+    }
+
+    /// Every resource class the entry counts against: its `lock` and its `resources`.
+    pub fn resource_classes(&self) -> impl Iterator<Item = &str> {
+        self.lock
+            .as_deref()
+            .into_iter()
+            .chain(self.resources.iter().map(String::as_str))
+    }
+
+    pub fn depends_on(&self) -> &[String] {
+        &self.depends_on
+    }
+
+    pub fn requires(&self) -> &[String] {
+        &self.requires
+    }
+
+    pub fn conflicts(&self) -> &[String] {
+        &self.conflicts
+    }
+
+    pub fn profiles(&self) -> Option<&[String]> {
+        self.profiles.as_deref()
+    }
+
+    pub fn config(&self) -> Option<&Config> {
+        self.config.as_ref()
+    }
+
+    /// Resolves `{{tmpdir}}` for this entry's commands within the run's workspace.
+    pub(crate) fn use_workspace(&self, workspace: &Workspace) -> io::Result<()> {
+        let dir = if self.own_tmpdir.unwrap_or(false) {
+            workspace.entry_dir(self.name())?
+        } else {
+            workspace.path().to_path_buf()
+        };
+        for command in self.all_commands() {
+            command.set_tmpdir(&dir);
+        }
+        if let Some(config) = &self.config {
+            config.set_tmpdir(&dir);
+        }
+        Ok(())
+    }
+
+    pub fn scope(&self) -> Scope {
+        self.scope.unwrap_or_default()
+    }
+
+    /// Keeps user-scoped entries out of root's context: under `sudo` their commands are
+    /// de-escalated to the invoking user, otherwise the entry is refused.
+    fn guard_scope(&self) -> Result<(), String> {
+        if self.scope() != Scope::User || !is_root() {
+            return Ok(());
+        }
+
+        let user = invoking_user().ok_or_else(|| {
+            format!(
+                "{:?} is user-scoped; run it as your own user instead of root",
+                self.description
+            )
+        })?;
+        if self.steps.as_ref().is_some_and(|steps| !steps.is_empty()) {
+            return Err(format!(
+                "{:?} is user-scoped and has steps, which cannot run as {}; run without sudo",
+                self.description, user
+            ));
+        }
+
+        for command in self.all_commands() {
+            command.set_run_as(&user);
+        }
+        if let Some(config) = &self.config {
+            config.set_run_as(&user);
+        }
+        // The workspace was created by root; hand the entry's `{{tmpdir}}` to the user,
+        // and let them through the private workspace to reach their own subdirectory.
+        if let Some(dir) = self.commands.iter().find_map(CommandStruct::tmpdir) {
+            if self.own_tmpdir.unwrap_or(false) {
+                if let Some(workspace) = dir.parent() {
+                    let _ = fs::set_permissions(workspace, fs::Permissions::from_mode(0o711));
+                }
+            }
+            let _ = std::process::Command::new("chown")
+                .arg(format!("{}:", user))
+                .arg(dir)
+                .status();
+        }
+        Ok(())
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecated.unwrap_or(false)
+    }
+
+    pub fn replaced_by(&self) -> Option<&str> {
+        self.replaced_by.as_deref()
+    }
+
+    /// "deprecated" plus the replacement, if any; `None` for current entries.
+    pub fn deprecation(&self) -> Option<String> {
+        if !self.is_deprecated() {
+            return None;
+        }
+        Some(match &self.replaced_by {
+            Some(replacement) => format!("deprecated, replaced by {:?}", replacement),
+            None => "deprecated".to_string(),
+        })
+    }
+
+    pub fn reboot_after(&self) -> bool {
+        self.reboot_after.unwrap_or(false)
+    }
+
+    pub fn when(&self) -> Option<&str> {
+        self.when.as_deref()
+    }
+
+    pub fn min_version(&self) -> Option<&str> {
+        self.min_version.as_deref()
+    }
+
+    pub fn max_version(&self) -> Option<&str> {
+        self.max_version.as_deref()
+    }
+
+    /// Returns the reason the whole entry should not run, if any.
+    pub fn evaluate_skip(&self) -> Option<SkipReason> {
+        if let Some(when) = self.when.as_ref().filter(|when| !condition_met(when)) {
+            return Some(SkipReason::Condition(when.clone()));
+        }
+        if let Some(reason) = release_skip(self.min_version(), self.max_version()) {
+            return Some(reason);
+        }
+        if self.skip_in_container.unwrap_or(false) {
+            if let Some(runtime) = container_runtime() {
+                return Some(SkipReason::InContainer(runtime));
+            }
+        }
+        if identify_linux_distribution() == DistributionType::NixOs {
+            let config = self.config.iter().flat_map(Config::commands);
+            return self
+                .all_commands()
+                .chain(config)
+                .find(|command| !command.should_skip() && command.writes_etc())
+                .map(|command| SkipReason::ManagedByNixOs(command.command().to_string()));
+        }
+        None
+    }
+
+    pub fn source(&self) -> Option<&Source> {
+        self.source.as_ref()
+    }
+
+    pub fn set_source(&mut self, source: Source) {
+        self.source = Some(source);
+    }
+
+    /// The entry description followed by where it was defined, for error messages.
+    pub fn location(&self) -> String {
+        match &self.source {
+            Some(source) => format!("{:?} ({})", self.description, source),
+            None => format!("{:?}", self.description),
+        }
+    }
+
+    /// A human-readable summary of what this entry does and where it comes from.
+    pub fn explain(&self) -> String {
+        let mut lines = vec![format!("Entry: {}", self.description)];
+        if let Some(source) = &self.source {
+            lines.push(format!("Defined at: {}", source));
+        }
+        if let Some(profiles) = &self.profiles {
+            lines.push(format!("Profiles: {}", profiles.join(", ")));
+        }
+        if let Some(url) = &self.docs_url {
+            lines.push(format!("Docs: {}", url));
+        }
+        if let Some(notes) = &self.notes {
+            lines.extend(notes.lines().map(|line| format!("  {}", line)));
+        }
+        for command in &self.commands {
+            let targets: Vec<String> = command
+                .distributions()
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            match targets.is_empty() {
+                false => lines.push(format!(
+                    "  $ {} [{}]",
+                    command.command(),
+                    targets.join(", ")
+                )),
+                true => lines.push(format!("  $ {}", command.command())),
+            }
+        }
+        for artifact in self.artifacts() {
+            lines.push(format!("  artifact: {}", artifact.name()));
         }
+        lines.join("\n")
+    }
+
+    /// Entries without `profiles` are shared by every profile.
+    pub fn matches_profile(&self, profile: &str) -> bool {
+        match &self.profiles {
+            Some(profiles) => profiles.iter().any(|p| p == profile),
+            None => true,
+        }
+    }
+
+    pub fn estimate(&self) -> Option<Duration> {
+        self.estimate.as_deref().and_then(parse_duration)
+    }
+
+    pub(crate) fn raw_estimate(&self) -> Option<&str> {
+        self.estimate.as_deref()
+    }
+
+    /// Shell variables the entry's steps would set, in order.
+    pub fn env_assignments(&self) -> Vec<(String, String)> {
+        self.steps
+            .iter()
+            .flatten()
+            .flat_map(|step| step.env_assignments())
+            .collect()
+    }
+
+    pub(crate) fn has_steps_or_config(&self) -> bool {
+        self.steps.as_ref().is_some_and(|steps| !steps.is_empty()) || self.config.is_some()
+    }
+
+    pub fn tags(&self) -> Option<&[String]> {
+        self.tags.as_deref()
+    }
 
+    /// Ansible-style selection: with a non-empty `include` the entry needs at least one
+    /// of those tags, and any tag in `exclude` rules it out.
+    pub fn matches_tags<S: AsRef<str>>(&self, include: &[S], exclude: &[S]) -> bool {
+        let has = |tag: &S| {
+            self.tags()
+                .unwrap_or_default()
+                .iter()
+                .any(|t| t == tag.as_ref())
+        };
+        (include.is_empty() || include.iter().any(has)) && !exclude.iter().any(has)
+    }
+
+    pub fn artifacts(&self) -> &[Artifact] {
+        self.artifacts.as_deref().unwrap_or_default()
+    }
+
+    /// For pinning artifacts to the lockfile.
+    pub(crate) fn artifacts_mut(&mut self) -> &mut [Artifact] {
+        self.artifacts.as_deref_mut().unwrap_or_default()
+    }
+
+    /// Exports `$LSU_ARTIFACT_<NAME>` for each artifact, from `bundle_dir` when running offline.
+    pub fn prepare_artifacts(&self, bundle_dir: Option<&Path>) -> Status {
+        if self.artifacts().is_empty() {
+            return Status::Success;
+        }
+        let cache_dir = match artifact::cache_dir() {
+            Ok(dir) => dir,
+            Err(e) => {
+                Status::Failure.print_message(&format!("Artifact cache: {}", e));
+                return Status::Failure;
+            }
+        };
+        for artifact in self.artifacts() {
+            if let Err(e) = artifact.prepare(bundle_dir, &cache_dir) {
+                Status::Failure.print_message(&format!("Artifact {}: {}", artifact.name(), e));
+                return Status::Failure;
+            }
+        }
         Status::Success
     }
 
+    /// Whether the entry talks to the system package manager, which only allows one
+    /// process at a time; judged by `use_package_manager` or the command's program name.
+    pub fn uses_package_manager(&self) -> bool {
+        self.commands.iter().any(|command| {
+            let mut words = command.command().split_whitespace();
+            let program = match words.next() {
+                Some("sudo") => words.next(),
+                first => first,
+            };
+            command.uses_package_manager() || program.is_some_and(is_package_manager)
+        })
+    }
+
+    /// Whether any command runs with root rights: `needs_root`, `sudo` or an inline `sudo`.
+    pub fn needs_root(&self) -> bool {
+        self.all_commands().any(|command| {
+            command.needs_root()
+                || command.uses_sudo()
+                || command.command().trim_start().starts_with("sudo ")
+        })
+    }
+
+    pub fn commands(&self) -> &[CommandStruct] {
+        &self.commands
+    }
+
+    pub(super) fn pre_hooks(&self) -> &[CommandStruct] {
+        &self.pre_hooks
+    }
+
+    pub(super) fn post_hooks(&self) -> &[CommandStruct] {
+        &self.post_hooks
+    }
+
+    /// `commands` together with the pre and post hooks, in the order they run.
+    pub(crate) fn all_commands(&self) -> impl Iterator<Item = &CommandStruct> {
+        self.pre_hooks
+            .iter()
+            .chain(&self.commands)
+            .chain(&self.post_hooks)
+    }
+
+    /// `all_commands` plus the config's apply and revert commands, for validation.
+    pub(crate) fn every_command(&self) -> impl Iterator<Item = &CommandStruct> {
+        let config = self.config.iter().flat_map(Config::all_commands);
+        self.all_commands().chain(config)
+    }
+
+    /// How long each command of the last run took; skipped commands are left out.
+    pub fn timings(&self) -> Vec<CommandTiming> {
+        self.all_commands()
+            .filter_map(|command| {
+                command.duration().map(|duration| CommandTiming {
+                    command: command.script(),
+                    duration,
+                })
+            })
+            .collect()
+    }
+
+    pub fn failed_commands(&self) -> Vec<String> {
+        self.all_commands()
+            .filter(|command| command.status() == Status::Failure)
+            .map(|command| command.command().to_string())
+            .collect()
+    }
+
+    /// The end of each failed command's stderr, in the order of `failed_commands`.
+    fn failed_stderr_tails(&self) -> Vec<Vec<String>> {
+        self.all_commands()
+            .filter(|command| command.status() == Status::Failure)
+            .map(|command| command.stderr_tail(STDERR_TAIL_LINES))
+            .collect()
+    }
+
+    pub fn on_error(&self) -> OnError {
+        self.on_error.unwrap_or_default()
+    }
+
+    /// The answer already given to an `on_error` prompt for this entry's last run.
+    pub(super) fn prompt_answer(&self) -> Option<bool> {
+        self.prompt_answer.get()
+    }
+
+    /// Whether a failure in the entry would ask on stdin what to do.
+    pub(super) fn prompts_on_error(&self) -> bool {
+        self.on_error() == OnError::Prompt
+            || self
+                .all_commands()
+                .any(|command| command.on_error() == Some(OnError::Prompt))
+    }
+
+    /// Adopts the registry-wide policy unless the entry sets its own.
+    pub(crate) fn inherit_on_error(&mut self, policy: Option<OnError>) {
+        if self.on_error.is_none() {
+            self.on_error = policy;
+        }
+    }
+
+    fn run_hooks(hooks: &[CommandStruct]) -> Status {
+        let mut status = Status::Success;
+        for hook in hooks {
+            match hook.execute() {
+                Status::Aborted => return Status::Aborted,
+                Status::Failure => status = Status::Failure,
+                _ => (),
+            }
+        }
+        status
+    }
+
+    pub(crate) fn set_step_through(&mut self, enabled: bool) {
+        self.step_through = enabled;
+    }
+
+    /// Whether the user aborted the run from step-through mode in this entry.
+    pub fn was_aborted(&self) -> bool {
+        self.aborted.get()
+    }
+
+    /// Executes `command`, then in step-through mode lets the user review and redo it.
+    fn run_command(&self, command: &CommandStruct) -> Status {
+        let mut status = command.execute();
+        if !self.step_through {
+            return status;
+        }
+
+        loop {
+            // Locked per review only: the command may prompt on stdin itself when redone.
+            match review(command, &status, &mut io::stdin().lock()) {
+                DebugAction::Continue => return status,
+                DebugAction::Retry => status = command.execute(),
+                DebugAction::Edit(script) => {
+                    command.edit(&script);
+                    status = command.execute();
+                }
+                DebugAction::Skip => {
+                    command.record_skip(Status::Skipped, SkipReason::Declined);
+                    return Status::Skipped;
+                }
+                DebugAction::Abort => {
+                    self.aborted.set(true);
+                    return Status::Failure;
+                }
+            }
+        }
+    }
+
+    /// Runs the commands in order, see `CommandChain`.
+    fn run_commands(&self) -> Status {
+        let mut chain = CommandChain::new(self);
+        let mut commands = self.commands.iter().peekable();
+        while let Some(command) = commands.next() {
+            if chain.skips(command) {
+                continue;
+            }
+            let result = self.run_command(command);
+            if let ControlFlow::Break(status) = chain.record(command, result, commands.peek()) {
+                return status;
+            }
+        }
+        chain.status
+    }
+
+    /// Applies every step, returning the ones that made changes.
+    fn run_steps(&self) -> (Status, Vec<&Step>) {
+        let mut status = Status::Success;
+        let mut applied = Vec::new();
+        for step in self.steps.iter().flatten() {
+            match step.apply() {
+                Status::Failure => status = Status::Failure,
+                Status::Success => applied.push(step),
+                _ => (),
+            }
+        }
+        (status, applied)
+    }
+
     fn run_config(&self) -> Status {
         if let Some(config) = &self.config {
             return config.apply();
@@ -100,7 +750,8 @@ impl SetupEntry {
     pub fn clear_commands(&mut self) {
         let mut commands_to_remove = Vec::new();
         for (index, command) in self.commands.iter().enumerate() {
-            if command.should_skip() {
+            if let Some(reason) = command.decide_skip() {
+                command.record_skip(Status::Skipped, reason);
                 commands_to_remove.push(index);
             }
         }
@@ -111,37 +762,197 @@ impl SetupEntry {
     }
 
     pub fn run(&self) -> Status {
-        let mut process = Status::Running;
+        self.finish_run(self.run_commands())
+    }
 
-        if process != Status::Success {
-            process = self.run_commands();
+    /// Applies the steps and config once the commands ended with `process`, and rolls
+    /// back if the entry failed.
+    pub(super) fn finish_run(&self, mut process: Status) -> Status {
+        let mut applied_steps = Vec::new();
+        let mut config_run = false;
+
+        let stopped = |status: &Status| matches!(status, Status::Failure | Status::Aborted);
+        if self.steps.is_some() && !stopped(&process) {
+            (process, applied_steps) = self.run_steps();
         }
 
-        if self.config.is_some() && process != Status::Failure {
+        if self.config.is_some() && !stopped(&process) {
             process = self.run_config();
+            config_run = true;
+        }
+
+        if process == Status::Failure && self.rollback.unwrap_or(false) {
+            self.roll_back(&applied_steps, config_run);
         }
 
         process
     }
+
+    /// Takes back what a failed run applied, newest first: the config (even when it
+    /// failed half-way), steps that made changes, then `undo` for commands that ran.
+    fn roll_back(&self, applied_steps: &[&Step], config_run: bool) {
+        Status::Warning.print_message(&format!("Rolling back {:?}", self.description));
+        let mut failed = 0;
+
+        if let Some(config) = self.config.as_ref().filter(|_| config_run) {
+            failed += usize::from(config.revert() == Status::Failure);
+        }
+        for step in applied_steps.iter().rev() {
+            failed += usize::from(step.revert() == Status::Failure);
+        }
+        for command in self
+            .commands
+            .iter()
+            .rev()
+            .filter(|command| command.status() == Status::Success)
+        {
+            failed += usize::from(command.undo() == Status::Failure);
+        }
+
+        if failed > 0 {
+            Status::Failure.print_message(&format!(
+                "Rollback of {:?} left {} change(s) in place",
+                self.description, failed
+            ));
+        }
+    }
 }
 
-impl ExecutableSetup for SetupEntry {
-    fn setup(&mut self) -> Status {
-        self.clear_commands();
+impl SetupEntry {
+    /// Everything `setup` does before the hooks and commands: skipping, labelling the
+    /// commands and preparing `working_dir` and `env_vars`. `Err` ends the setup.
+    pub(super) fn begin_setup(&mut self) -> Result<(), Status> {
+        self.skip_reason = self.evaluate_skip();
+        if let Some(reason) = &self.skip_reason {
+            Status::Skipped.print_message(&format!("Setup: {:?} ({})", self.description, reason));
+            return Err(Status::Skipped);
+        }
 
         Status::Running.print_message(&format!("Setup: {:?}", self.description));
+        self.clear_commands();
+        let working_dir = self.setup.as_ref().and_then(|s| s.working_dir.as_deref());
+        for command in self.all_commands() {
+            command.set_label(self.name());
+            if let Some(dir) = working_dir {
+                command.set_default_cwd(dir);
+            }
+        }
+
         if let Some(setup) = &mut self.setup {
             if let Err(e) = setup.ensure_working_dir() {
-                eprintln!("Error creating working directory: {}", e);
-                return Status::Failure;
+                reporter().error(&format!("Error creating working directory: {}", e));
+                return Err(Status::Failure);
             }
 
             if let Err(e) = setup.ensure_env_vars() {
-                eprintln!("Error setting environment variables: {}", e);
-                return Status::Failure;
+                reporter().error(&format!("Error setting environment variables: {}", e));
+                return Err(Status::Failure);
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the entry as skipped without running anything.
+    pub fn skip_with(&mut self, reason: SkipReason) -> EntryResult {
+        Status::Skipped.print_message(&format!("Setup: {:?} ({})", self.description, reason));
+        self.skip_reason = Some(reason);
+        EntryResult {
+            description: self.description.clone(),
+            status: Status::Skipped,
+            failed_commands: Vec::new(),
+            stderr_tails: Vec::new(),
+            source: self.source.clone(),
+            skip_reason: self.skip_reason.clone(),
+            duration: Duration::ZERO,
+            timings: Vec::new(),
+            packages: Vec::new(),
+            changes: Vec::new(),
+            docs_url: self.docs_url.clone(),
+            notes: self.notes.clone(),
+        }
+    }
+
+    /// Prepares artifacts, runs `setup` and captures the outcome for the run report.
+    pub fn setup_with_result(&mut self, bundle_dir: Option<&Path>) -> EntryResult {
+        let started = Instant::now();
+        let status = match self.prepare_run(bundle_dir) {
+            Ok(()) => self.setup(),
+            Err(status) => status,
+        };
+        self.entry_result(status, started)
+    }
+
+    /// Checks the entry's scope and prepares its artifacts; an entry that will be
+    /// skipped needs neither. `Err` is the entry's status when it cannot run.
+    pub(super) fn prepare_run(&self, bundle_dir: Option<&Path>) -> Result<(), Status> {
+        if self.evaluate_skip().is_some() {
+            return Ok(());
+        }
+        if let Err(e) = self.guard_scope() {
+            Status::Failure.print_message(&e);
+            return Err(Status::Failure);
+        }
+        let packages = bundle_dir.map(|bundle| bundle.join(BUNDLE_PACKAGES));
+        if let Some(packages) = packages.filter(|dir| dir.is_dir()) {
+            for command in self.all_commands() {
+                command.set_package_cache(&packages);
             }
         }
+        match self.prepare_artifacts(bundle_dir) {
+            Status::Failure => Err(Status::Failure),
+            _ => Ok(()),
+        }
+    }
 
-        self.run()
+    /// The report line for a setup that ended with `status`.
+    pub(super) fn entry_result(&self, status: Status, started: Instant) -> EntryResult {
+        if let (Status::Failure, Some(url)) = (&status, &self.docs_url) {
+            reporter().error(&format!("see: {}", url));
+        }
+        EntryResult {
+            description: self.description.clone(),
+            status,
+            failed_commands: self.failed_commands(),
+            stderr_tails: self.failed_stderr_tails(),
+            source: self.source.clone(),
+            skip_reason: self.skip_reason.clone(),
+            duration: started.elapsed(),
+            timings: self.timings(),
+            packages: self
+                .commands
+                .iter()
+                .flat_map(CommandStruct::package_results)
+                .collect(),
+            changes: self
+                .steps
+                .iter()
+                .flatten()
+                .flat_map(Step::changes)
+                .collect(),
+            docs_url: self.docs_url.clone(),
+            notes: self.notes.clone(),
+        }
+    }
+}
+
+impl ExecutableSetup for SetupEntry {
+    fn setup(&mut self) -> Status {
+        if let Err(status) = self.begin_setup() {
+            return status;
+        }
+
+        let status = match Self::run_hooks(self.pre_hooks()) {
+            Status::Failure => Status::Failure,
+            Status::Aborted => return Status::Aborted,
+            _ => self.run(),
+        };
+        if status == Status::Aborted {
+            return status;
+        }
+        match Self::run_hooks(self.post_hooks()) {
+            Status::Failure => Status::Failure,
+            Status::Aborted => Status::Aborted,
+            _ => status,
+        }
     }
 }
@@ -1,34 +1,832 @@
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+use std::{env, fs, io, thread};
 
-use crate::setup::SetupEntry;
-use crate::traits::executable_setup::ExecutableSetup;
-use crate::Repository;
+use crate::artifact::{self, build_bundle, bundle_packages, Lockfile, BUNDLE_INDEX};
+use crate::distribution::{
+    current_override, declare_distributions, identify_linux_distribution, override_distribution,
+    release_upgrade_state, CustomDistribution, ReleaseUpgradeState,
+};
+use crate::engine::{Confirmation, RunObserver};
+use crate::manifest::{
+    apply_defaults, decrypt_secret, discover_manifest, expand_definitions, migrate, ManifestFormat,
+    SecretKey, Source, CURRENT_VERSION,
+};
+use crate::report::{EntryResult, RunReport};
+use crate::setup::{EntrySelector, RegistryError, SetupEntry};
+use crate::state::{RunState, StateStore};
+use crate::utils::{
+    interrupted, InterruptGuard, OnError, SharedTerminal, SkipReason, Status, SudoSession,
+    Workspace,
+};
+use crate::{ConfigRepository, DistributionType, Repository};
+
+/// Bookkeeping shared by the `execute_parallel` workers, indexed by entry.
+pub(super) struct Schedule {
+    pending: Vec<usize>,
+    /// `Some(false)` once an entry failed or was skipped for a failed dependency.
+    succeeded: Vec<Option<bool>>,
+    results: Vec<Option<EntryResult>>,
+    failures: usize,
+    /// Set once the run was aborted; nothing new is started after that.
+    aborted: Option<SkipReason>,
+    /// Entries currently running per resource class.
+    running: BTreeMap<String, usize>,
+}
+
+impl Schedule {
+    pub(super) fn new(len: usize) -> Self {
+        Schedule {
+            pending: (0..len).collect(),
+            succeeded: vec![None; len],
+            results: (0..len).map(|_| None).collect(),
+            failures: 0,
+            aborted: None,
+            running: BTreeMap::new(),
+        }
+    }
+
+    pub(super) fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Takes the first pending entry whose dependencies have finished and whose resource
+    /// classes have room, and counts it as running.
+    pub(super) fn start_next(
+        &mut self,
+        dependencies: &[Vec<usize>],
+        classes: &[Vec<(String, usize)>],
+    ) -> Option<usize> {
+        let position = self.pending.iter().position(|&index| {
+            dependencies[index]
+                .iter()
+                .all(|&dep| self.succeeded[dep].is_some())
+                && classes[index]
+                    .iter()
+                    .all(|(class, limit)| self.running.get(class).copied().unwrap_or(0) < *limit)
+        })?;
+        let index = self.pending.remove(position);
+        for (class, _) in &classes[index] {
+            *self.running.entry(class.clone()).or_default() += 1;
+        }
+        Some(index)
+    }
+
+    /// Why a started entry should be skipped instead of run: an abort, or a dependency
+    /// (named by `names`) that failed.
+    pub(super) fn skip_reason(
+        &self,
+        dependencies: &[usize],
+        names: &[String],
+    ) -> Option<SkipReason> {
+        if interrupted() {
+            return Some(SkipReason::Interrupted);
+        }
+        if let Some(reason) = &self.aborted {
+            return Some(reason.clone());
+        }
+        dependencies
+            .iter()
+            .find(|&&dep| self.succeeded[dep] == Some(false))
+            .map(|&dep| SkipReason::DependencyFailed(names[dep].clone()))
+    }
+
+    /// Records the result of a started entry and frees its resource classes.
+    pub(super) fn finish(
+        &mut self,
+        index: usize,
+        classes: &[(String, usize)],
+        entry: &SetupEntry,
+        result: EntryResult,
+        max_failures: Option<usize>,
+    ) {
+        for (class, _) in classes {
+            *self.running.entry(class.clone()).or_default() -= 1;
+        }
+        self.failures += usize::from(result.status == Status::Failure);
+        if self.aborted.is_none() {
+            self.aborted = SetupRegistry::abort_after(max_failures, entry, &result, self.failures);
+        }
+        self.succeeded[index] = Some(
+            result.status != Status::Failure
+                && !matches!(result.skip_reason, Some(SkipReason::DependencyFailed(_))),
+        );
+        self.results[index] = Some(result);
+    }
+
+    pub(super) fn into_report(self) -> RunReport {
+        let mut report = RunReport::default();
+        for result in self.results.into_iter().flatten() {
+            report.push(result);
+        }
+        report
+    }
+}
+
+/// Persists each successful entry for `execute_resumable` as soon as it finishes.
+struct ProgressRecorder<'a> {
+    store: &'a StateStore,
+    state: RunState,
+}
+
+impl ProgressRecorder<'_> {
+    fn save(&self) {
+        if let Err(e) = self.store.save(&self.state) {
+            Status::Warning.print_message(&format!("Failed to save progress: {}", e));
+        }
+    }
+}
+
+impl RunObserver for ProgressRecorder<'_> {
+    fn skip_entry(&mut self, entry: &SetupEntry) -> Option<SkipReason> {
+        self.state
+            .completed
+            .contains(entry.get_description())
+            .then_some(SkipReason::Completed)
+    }
+
+    fn entry_finished(&mut self, result: &EntryResult) {
+        if matches!(
+            result.status,
+            Status::Success | Status::Passed | Status::Warning
+        ) {
+            self.state.completed.insert(result.description.clone());
+            self.save();
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SetupRegistry {
+    version: u64,
     entries: Vec<SetupEntry>,
+    /// age-encrypted values, exported as environment variables by `unlock_secrets`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    secrets: BTreeMap<String, String>,
+    /// Default `on_error` for entries and commands that do not set one.
+    on_error: Option<OnError>,
+    /// Skips the rest of the run once this many entries have failed.
+    max_failures: Option<usize>,
+    /// How many entries of each resource class (`lock` or `resources`) `execute_parallel`
+    /// runs at once; classes not listed run one at a time.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    resource_limits: BTreeMap<String, usize>,
+    /// Distributions the crate has no variant for, by the name commands target them with.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    distributions: BTreeMap<String, CustomDistribution>,
+    #[serde(skip)]
+    bundle_dir: Option<PathBuf>,
+    #[serde(skip)]
+    keep_tmp: bool,
 }
 
 impl SetupRegistry {
-    pub fn load_from_json(path: &str) -> Self {
-        let file = File::open(path).expect("Failed to open file");
-        let reader = io::BufReader::new(file);
-        serde_json::from_reader(reader).expect("Failed to parse JSON")
+    /// Migrates older format versions, expands `definitions` and deserializes the manifest.
+    fn parse_value(value: serde_json::Value) -> Result<Self, RegistryError> {
+        let (mut value, warnings) = migrate(value).map_err(RegistryError::Validation)?;
+        for warning in warnings {
+            Status::Warning.print_message(&warning);
+        }
+        expand_definitions(&mut value).map_err(RegistryError::Validation)?;
+        apply_defaults(&mut value).map_err(RegistryError::Validation)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Builds and validates a registry from a parsed manifest.
+    pub fn from_value(value: serde_json::Value) -> Result<Self, RegistryError> {
+        Self::parse_value(value)?.validated()
+    }
+
+    fn validated(self) -> Result<Self, RegistryError> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Like `parse_value`, but records `origin` (and the declaring line within `raw`)
+    /// as the source of every entry.
+    fn parse_source(
+        value: serde_json::Value,
+        origin: &str,
+        raw: &str,
+    ) -> Result<Self, RegistryError> {
+        let mut registry = Self::parse_value(value)?;
+        for entry in registry.entries.iter_mut() {
+            let source = Source::locate(origin, raw, entry.get_description());
+            entry.set_source(source);
+        }
+        Ok(registry)
+    }
+
+    /// Reads a JSON manifest without validating it, for fragments that are merged first.
+    fn read_json(path: &str) -> Result<Self, RegistryError> {
+        let content = fs::read_to_string(path)?;
+        Self::parse_source(serde_json::from_str(&content)?, path, &content)
+    }
+
+    /// Reads a manifest in any supported format without validating it.
+    fn read(path: &Path) -> Result<Self, RegistryError> {
+        let format = ManifestFormat::from_path(path).ok_or_else(|| {
+            RegistryError::Validation(format!("unsupported manifest format: {}", path.display()))
+        })?;
+        let content = fs::read_to_string(path)?;
+        let value = format.parse(&content).map_err(RegistryError::Parse)?;
+        Self::parse_source(value, &path.display().to_string(), &content)
+    }
+
+    pub fn load_from_json(path: &str) -> Result<Self, RegistryError> {
+        Self::read_json(path)?.validated()
+    }
+
+    /// Loads a JSON, YAML or TOML manifest, picking the parser from the file extension.
+    pub fn load(path: &Path) -> Result<Self, RegistryError> {
+        Self::read(path)?.validated()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// The distributions declared under `distributions`, by name.
+    pub fn distributions(&self) -> &BTreeMap<String, CustomDistribution> {
+        &self.distributions
+    }
+
+    pub fn resource_limits(&self) -> &BTreeMap<String, usize> {
+        &self.resource_limits
     }
 
-    pub fn execute(&mut self) {
-        for entry in self.entries.iter_mut() {
-            entry.setup();
+    /// How many entries of this resource class may run at once.
+    pub fn resource_limit(&self, class: &str) -> usize {
+        self.resource_limits.get(class).copied().unwrap_or(1)
+    }
+
+    /// Loads the manifest from the standard XDG locations, returning it with the path that was used.
+    /// Returns `Ok(None)` when no manifest exists in any of them.
+    pub fn load_discovered() -> Result<Option<(Self, PathBuf)>, RegistryError> {
+        match discover_manifest() {
+            Some(path) => Ok(Some((Self::load(&path)?, path))),
+            None => Ok(None),
         }
     }
+
+    /// Downloads the manifest from `url`, optionally verifying its SHA-256 checksum first.
+    #[cfg(feature = "remote")]
+    pub fn load_from_url(url: &str, sha256: Option<&str>) -> Result<Self, RegistryError> {
+        let content = crate::manifest::fetch_manifest(url, sha256)?;
+        Self::parse_source(serde_json::from_str(&content)?, url, &content)?.validated()
+    }
+
+    /// Loads a manifest that is a Tera template, rendering it with `context` before parsing.
+    #[cfg(feature = "templating")]
+    pub fn load_templated(path: &str, context: &serde_json::Value) -> Result<Self, RegistryError> {
+        let template = fs::read_to_string(path)?;
+        let rendered = crate::manifest::render_template(&template, context)
+            .map_err(|e| RegistryError::Parse(e.to_string()))?;
+        Self::parse_source(serde_json::from_str(&rendered)?, path, &rendered)?.validated()
+    }
+
+    /// Loads several manifests in order and merges them into one registry,
+    /// validated as a whole so entries may depend on entries from other files.
+    pub fn load_from_paths(paths: &[&str]) -> Result<Self, RegistryError> {
+        let mut registry = Self::new();
+        for path in paths {
+            registry.merge(Self::read_json(path)?);
+        }
+        registry.validated()
+    }
+
+    /// Loads every manifest fragment in `dir` (e.g. `setup.d/10-packages.yaml`) in lexical
+    /// order and merges them. Hidden files and files with other extensions are ignored.
+    pub fn load_dir(dir: &Path) -> Result<Self, RegistryError> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let hidden = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.'));
+            if path.is_file() && !hidden && ManifestFormat::from_path(&path).is_some() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut registry = Self::new();
+        for path in paths {
+            registry.merge(Self::read(&path)?);
+        }
+        registry.validated()
+    }
+
+    /// Appends the entries of `other`. An entry whose description already exists
+    /// replaces the earlier one in place, so later files override earlier ones.
+    pub fn merge(&mut self, other: SetupRegistry) {
+        self.secrets.extend(other.secrets);
+        self.distributions.extend(other.distributions);
+        self.resource_limits.extend(other.resource_limits);
+        self.on_error = other.on_error.or(self.on_error);
+        self.max_failures = other.max_failures.or(self.max_failures);
+        for entry in other.entries {
+            match self
+                .entries
+                .iter()
+                .position(|existing| existing.get_description() == entry.get_description())
+            {
+                Some(index) => self.entries[index] = entry,
+                None => self.entries.push(entry),
+            }
+        }
+    }
+
+    pub fn entries(&self) -> &[SetupEntry] {
+        &self.entries
+    }
+
+    pub fn secret_names(&self) -> impl Iterator<Item = &str> {
+        self.secrets.keys().map(String::as_str)
+    }
+
+    pub(crate) fn secrets(&self) -> &BTreeMap<String, String> {
+        &self.secrets
+    }
+
+    /// Decrypts every secret and exports it as an environment variable,
+    /// so commands can reference e.g. `$GITHUB_TOKEN`.
+    pub fn unlock_secrets(&self, key: &SecretKey) -> Result<(), RegistryError> {
+        for (name, ciphertext) in &self.secrets {
+            let value = decrypt_secret(ciphertext, key).map_err(|e| {
+                RegistryError::Io(io::Error::new(
+                    e.kind(),
+                    format!("failed to decrypt secret `{}`: {}", name, e),
+                ))
+            })?;
+            env::set_var(name, value.trim_end_matches('\n'));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn entries_mut(&mut self) -> &mut [SetupEntry] {
+        &mut self.entries
+    }
+
+    pub(super) fn entries_vec(&mut self) -> &mut Vec<SetupEntry> {
+        &mut self.entries
+    }
+
+    pub(crate) fn bundle_dir(&self) -> Option<&Path> {
+        self.bundle_dir.as_deref()
+    }
+
+    pub(super) fn max_failures(&self) -> Option<usize> {
+        self.max_failures
+    }
+
+    /// Collects the configuration of every entry so it can be reverted individually.
+    pub fn configs(&self) -> ConfigRepository<'_> {
+        let mut repository = ConfigRepository::with_distributions(self.distributions.clone());
+        for config in self.entries.iter().filter_map(SetupEntry::config) {
+            repository.add(config);
+        }
+        repository
+    }
+
+    /// Describes the entry with the given description, including where it was defined.
+    pub fn explain(&self, description: &str) -> Option<String> {
+        self.entries
+            .iter()
+            .find(|entry| entry.get_description() == description)
+            .map(SetupEntry::explain)
+    }
+
+    /// Pre-downloads every artifact referenced by the registry into `dir`, and the
+    /// packages of the commands that run on this machine.
+    pub fn bundle(&self, dir: &Path) -> Result<(), RegistryError> {
+        build_bundle(self.entries.iter().flat_map(|e| e.artifacts()), dir)?;
+        bundle_packages(self.entries.iter().flat_map(|e| e.all_commands()), dir)?;
+        Ok(())
+    }
+
+    /// Makes subsequent runs read artifacts from a directory produced by `bundle`.
+    pub fn use_bundle(&mut self, dir: &Path) -> Result<(), RegistryError> {
+        if !dir.join(BUNDLE_INDEX).is_file() {
+            return Err(RegistryError::Validation(format!(
+                "{} is not an artifact bundle",
+                dir.display()
+            )));
+        }
+        self.bundle_dir = Some(dir.to_path_buf());
+        Ok(())
+    }
+
+    /// Pins every artifact to the source recorded in `lockfile`. Artifacts missing from it,
+    /// or whose source changed, are resolved now and written back, unless `frozen`, in which
+    /// case they are an error. Pins no artifact uses any more are dropped.
+    pub fn lock_artifacts(&mut self, lockfile: &Path, frozen: bool) -> Result<(), RegistryError> {
+        let mut lock = Lockfile::load(lockfile)?;
+        let cache_dir = artifact::cache_dir()?;
+        let mut changed = false;
+        let mut used = Vec::new();
+
+        for artifact in self.entries.iter_mut().flat_map(|e| e.artifacts_mut()) {
+            let key = artifact.lock_key();
+            if !lock.artifacts.contains_key(&key) {
+                if frozen {
+                    return Err(RegistryError::Validation(format!(
+                        "artifact `{}` is not in {} (frozen)",
+                        artifact.name(),
+                        lockfile.display()
+                    )));
+                }
+                Status::Running.print_message(&format!("Resolving {}", artifact.name()));
+                let locked = artifact.resolve(&cache_dir)?;
+                lock.artifacts.insert(key.clone(), locked);
+                changed = true;
+            }
+            artifact.pin(&lock.artifacts[&key]);
+            used.push(key);
+        }
+
+        let pinned = lock.artifacts.len();
+        lock.artifacts.retain(|key, _| used.contains(key));
+        changed |= lock.artifacts.len() != pinned && !frozen;
+
+        if changed {
+            lock.save(lockfile)?;
+        }
+        Ok(())
+    }
+
+    /// Keeps the run's temporary workspace (`{{tmpdir}}`) instead of deleting it, for debugging.
+    pub fn keep_tmp(&mut self, keep: bool) {
+        self.keep_tmp = keep;
+    }
+
+    /// Creates the run's temporary workspace and points every entry's `{{tmpdir}}` into it.
+    pub(super) fn prepare_workspace(&self) -> Option<Workspace> {
+        let prepared = Workspace::create(self.keep_tmp).and_then(|workspace| {
+            for entry in &self.entries {
+                entry.use_workspace(&workspace)?;
+            }
+            Ok(workspace)
+        });
+        match prepared {
+            Ok(workspace) => Some(workspace),
+            Err(e) => {
+                Status::Warning
+                    .print_message(&format!("Failed to create temporary workspace: {}", e));
+                None
+            }
+        }
+    }
+
+    /// Refuses to provision while an Ubuntu release upgrade is running,
+    /// since package operations mid-upgrade can corrupt the system.
+    pub(super) fn release_upgrade_guard() -> bool {
+        !DistributionType::Ubuntu.matches(&identify_linux_distribution())
+            || Self::allowed_during(release_upgrade_state())
+    }
+
+    fn allowed_during(state: ReleaseUpgradeState) -> bool {
+        if state == ReleaseUpgradeState::InProgress {
+            Status::Failure.print_message(
+                "An Ubuntu release upgrade is in progress; finish it and reboot before provisioning",
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Asks for sudo once, before anything runs, when a selected entry needs root, and
+    /// keeps the credentials fresh while the session lives. Fails when sudo is unavailable.
+    pub(super) fn start_sudo_session(
+        &self,
+        selected: impl Fn(&SetupEntry) -> bool,
+    ) -> Result<Option<SudoSession>, String> {
+        if !self
+            .entries
+            .iter()
+            .any(|entry| selected(entry) && entry.needs_root())
+        {
+            return Ok(None);
+        }
+        SudoSession::start()
+    }
+
+    pub(super) fn inherit_on_error(&mut self) {
+        for entry in &mut self.entries {
+            entry.inherit_on_error(self.on_error);
+        }
+    }
+
+    /// Why the rest of the run should be skipped after `result`, given `failures` failed
+    /// entries so far: the entry's `on_error` policy or the registry's `max_failures`.
+    fn abort_after(
+        max_failures: Option<usize>,
+        entry: &SetupEntry,
+        result: &EntryResult,
+        failures: usize,
+    ) -> Option<SkipReason> {
+        let aborted = SkipReason::Aborted(entry.name().to_string());
+        if entry.was_aborted() {
+            return Some(aborted);
+        }
+        if result.status != Status::Failure {
+            return None;
+        }
+        if max_failures.is_some_and(|max| failures >= max) {
+            return Some(SkipReason::TooManyFailures(failures));
+        }
+        let proceed = match entry.prompt_answer() {
+            Some(answer) if entry.on_error() == OnError::Prompt => answer,
+            _ => entry
+                .on_error()
+                .should_continue(&format!("Entry {:?}", entry.get_description())),
+        };
+        (!proceed).then_some(aborted)
+    }
+
+    /// Runs the entries accepted by `selected` in dependency order. An entry whose
+    /// dependency failed or was skipped is skipped as well.
+    fn run_selected(&mut self, selected: impl Fn(&SetupEntry) -> bool) -> RunReport {
+        self.run_observed(selected, &mut ())
+    }
+
+    /// `run_selected`, reporting each entry to `observer` as it starts and finishes.
+    pub(crate) fn run_observed(
+        &mut self,
+        selected: impl Fn(&SetupEntry) -> bool,
+        observer: &mut dyn RunObserver,
+    ) -> RunReport {
+        let mut report = RunReport::default();
+        let _distributions = declare_distributions(&self.distributions);
+        if !Self::release_upgrade_guard() {
+            return report;
+        }
+        if let Err(e) = self.sort_by_dependencies() {
+            Status::Failure.print_message(&e.to_string());
+            return report;
+        }
+        let _sudo = match self.start_sudo_session(&selected) {
+            Ok(session) => session,
+            Err(e) => {
+                Status::Failure.print_message(&e);
+                return report;
+            }
+        };
+
+        self.inherit_on_error();
+
+        let _interrupts = InterruptGuard::install();
+        let _workspace = self.prepare_workspace();
+        let total = self.entries.iter().filter(|entry| selected(entry)).count();
+        let mut blocked: Vec<String> = Vec::new();
+        let mut aborted: Option<SkipReason> = None;
+        let mut failures = 0;
+        for (position, entry) in self
+            .entries
+            .iter_mut()
+            .filter(|entry| selected(entry))
+            .enumerate()
+        {
+            observer.entry_started(entry, position, total);
+            let failed_dependency = entry
+                .depends_on()
+                .iter()
+                .find(|reference| blocked.contains(reference))
+                .cloned();
+            let stop_reason = match &aborted {
+                _ if interrupted() => Some(SkipReason::Interrupted),
+                reason => reason.clone(),
+            };
+            if let Some(reason) = stop_reason {
+                let result = entry.skip_with(reason);
+                observer.entry_finished(&result);
+                report.push(result);
+                continue;
+            }
+            let result = match (failed_dependency, observer.skip_entry(entry)) {
+                (Some(dependency), _) => entry.skip_with(SkipReason::DependencyFailed(dependency)),
+                (None, Some(reason)) => entry.skip_with(reason),
+                (None, None) => entry.setup_with_result(self.bundle_dir.as_deref()),
+            };
+            failures += usize::from(result.status == Status::Failure);
+            if matches!(result.status, Status::Failure)
+                || matches!(result.skip_reason, Some(SkipReason::DependencyFailed(_)))
+            {
+                blocked.push(entry.name().to_string());
+                blocked.push(entry.get_description().clone());
+            }
+            if aborted.is_none() {
+                aborted = Self::abort_after(self.max_failures, entry, &result, failures);
+            }
+            observer.entry_finished(&result);
+            report.push(result);
+        }
+        report
+    }
+
+    /// Runs every entry. Like `execute_parallel` and `Engine::run`, it leaves printing
+    /// the summary to the caller.
+    pub fn execute(&mut self) -> RunReport {
+        self.run_selected(|_| true)
+    }
+
+    /// Runs entries concurrently on up to `jobs` threads, starting each once everything it
+    /// `depends_on` has finished and each of its resource classes has room. Entries that use the package
+    /// manager still run one at a time, since pacman and apt hold an exclusive lock.
+    /// Results are in execution order.
+    pub fn execute_parallel(&mut self, jobs: usize) -> RunReport {
+        let report = RunReport::default();
+        let _distributions = declare_distributions(&self.distributions);
+        if !Self::release_upgrade_guard() {
+            return report;
+        }
+        let dependencies = match self
+            .sort_by_dependencies()
+            .and_then(|_| self.dependency_indices())
+        {
+            Ok(dependencies) => dependencies,
+            Err(e) => {
+                Status::Failure.print_message(&e.to_string());
+                return report;
+            }
+        };
+        let _sudo = match self.start_sudo_session(|_| true) {
+            Ok(session) => session,
+            Err(e) => {
+                Status::Failure.print_message(&e);
+                return report;
+            }
+        };
+
+        self.inherit_on_error();
+        // Workers cannot share stdin, so there is no one to answer the prompt.
+        if let Some(entry) = self.entries.iter().find(|entry| entry.prompts_on_error()) {
+            Status::Failure.print_message(&format!(
+                "{}: `on_error: prompt` cannot be answered in parallel runs; use `continue` or `abort`",
+                entry.location()
+            ));
+            return report;
+        }
+
+        let _interrupts = InterruptGuard::install();
+        let _terminal = SharedTerminal::install();
+        let _workspace = self.prepare_workspace();
+        let names: Vec<String> = self.entries.iter().map(|e| e.name().to_string()).collect();
+        let classes: Vec<Vec<(String, usize)>> = self
+            .entries
+            .iter()
+            .map(|e| {
+                e.resource_classes()
+                    .map(|class| (class.to_string(), self.resource_limit(class)))
+                    .collect()
+            })
+            .collect();
+        let bundle_dir = self.bundle_dir.as_deref();
+        let max_failures = self.max_failures();
+        let slots: Vec<Mutex<&mut SetupEntry>> = self.entries.iter_mut().map(Mutex::new).collect();
+        let schedule = Mutex::new(Schedule::new(slots.len()));
+        let finished = Condvar::new();
+        let package_lock = Mutex::new(());
+        // A distribution override is per thread, so workers carry over the caller's.
+        let distribution = current_override();
+
+        thread::scope(|scope| {
+            for _ in 0..jobs.max(1) {
+                scope.spawn(|| {
+                    let _distribution = distribution.clone().map(override_distribution);
+                    let mut state = schedule.lock().unwrap();
+                    while !state.is_done() {
+                        let Some(index) = state.start_next(&dependencies, &classes) else {
+                            state = finished.wait(state).unwrap();
+                            continue;
+                        };
+                        let skip_reason = state.skip_reason(&dependencies[index], &names);
+                        drop(state);
+
+                        let mut entry = slots[index].lock().unwrap();
+                        let result = match skip_reason {
+                            Some(reason) => entry.skip_with(reason),
+                            None if entry.uses_package_manager() => {
+                                let _guard = package_lock.lock().unwrap_or_else(|e| e.into_inner());
+                                entry.setup_with_result(bundle_dir)
+                            }
+                            None => entry.setup_with_result(bundle_dir),
+                        };
+                        state = schedule.lock().unwrap();
+                        state.finish(index, &classes[index], &entry, result, max_failures);
+                        drop(entry);
+                        finished.notify_all();
+                    }
+                });
+            }
+        });
+
+        schedule.into_inner().unwrap().into_report()
+    }
+
+    /// Shows each entry and asks on stdin whether to run it, skip it, or skip the rest.
+    pub fn execute_interactive(&mut self) -> RunReport {
+        self.run_observed(|_| true, &mut Confirmation::stdin())
+    }
+
+    /// Pauses after every command to show its output and status, letting the user retry,
+    /// skip, edit or abort. Meant for debugging a manifest; entries run one at a time.
+    pub fn execute_step_through(&mut self) -> RunReport {
+        for entry in &mut self.entries {
+            entry.set_step_through(true);
+        }
+        let report = self.run_selected(|_| true);
+        for entry in &mut self.entries {
+            entry.set_step_through(false);
+        }
+        report
+    }
+
+    /// Runs every entry, saving progress to `store` as entries succeed. With `resume`,
+    /// entries that succeeded in an earlier, unfinished run are skipped; otherwise
+    /// the recorded progress is discarded first. A fully successful run clears it.
+    pub fn execute_resumable(&mut self, store: &StateStore, resume: bool) -> RunReport {
+        let mut state = match store.load() {
+            Ok(state) => state,
+            Err(e) => {
+                Status::Failure.print_message(&format!("Failed to read state: {}", e));
+                return RunReport::default();
+            }
+        };
+        if !resume {
+            state.completed.clear();
+        }
+
+        let mut progress = ProgressRecorder { store, state };
+        let report = self.run_observed(|_| true, &mut progress);
+        if report.is_success() {
+            progress.state.completed.clear();
+            progress.save();
+        }
+        report
+    }
+
+    /// Runs a single entry, chosen by name, description or manifest position, without
+    /// touching the rest of the registry. Its dependencies are not run first.
+    pub fn execute_entry(
+        &mut self,
+        selector: impl Into<EntrySelector>,
+    ) -> Result<EntryResult, RegistryError> {
+        let index = match selector.into() {
+            EntrySelector::Index(index) if index < self.entries.len() => index,
+            EntrySelector::Index(index) => {
+                return Err(RegistryError::Validation(format!(
+                    "no entry at index {} (registry has {})",
+                    index,
+                    self.entries.len()
+                )))
+            }
+            EntrySelector::Name(name) => self
+                .entries
+                .iter()
+                .position(|entry| entry.is_referenced_by(&name))
+                .ok_or_else(|| RegistryError::Validation(format!("no entry named {:?}", name)))?,
+        };
+
+        let _distributions = declare_distributions(&self.distributions);
+        if !Self::release_upgrade_guard() {
+            return Ok(self.entries[index].skip_with(SkipReason::Condition(
+                "release upgrade in progress".to_string(),
+            )));
+        }
+        self.entries[index].inherit_on_error(self.on_error);
+        let _interrupts = InterruptGuard::install();
+        let _workspace = self.prepare_workspace();
+        Ok(self.entries[index].setup_with_result(self.bundle_dir.as_deref()))
+    }
+
+    /// Runs only the entries tagged with `profile`, plus untagged entries shared by all profiles.
+    pub fn execute_profile(&mut self, profile: &str) -> RunReport {
+        self.run_selected(|entry| entry.matches_profile(profile))
+    }
+
+    /// Runs the entries selected by `include_tags` and not ruled out by `exclude_tags`.
+    pub fn execute_filtered(&mut self, include_tags: &[&str], exclude_tags: &[&str]) -> RunReport {
+        self.run_selected(|entry| entry.matches_tags(include_tags, exclude_tags))
+    }
 }
 
 impl Repository<SetupEntry> for SetupRegistry {
     fn new() -> Self {
         SetupRegistry {
+            version: CURRENT_VERSION,
             entries: Vec::new(),
+            secrets: BTreeMap::new(),
+            on_error: None,
+            max_failures: None,
+            resource_limits: BTreeMap::new(),
+            distributions: BTreeMap::new(),
+            bundle_dir: None,
+            keep_tmp: false,
         }
     }
 
@@ -36,3 +834,712 @@ impl Repository<SetupEntry> for SetupRegistry {
         self.entries.push(item);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distribution::custom_distribution;
+    use serde_json::json;
+
+    fn registry(entries: serde_json::Value) -> SetupRegistry {
+        SetupRegistry::from_value(json!({ "version": CURRENT_VERSION, "entries": entries }))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert!(matches!(
+            SetupRegistry::load_from_json("does-not-exist.json"),
+            Err(RegistryError::Io(_))
+        ));
+        assert!(matches!(
+            SetupRegistry::from_value(json!({ "entries": "nope" })),
+            Err(RegistryError::Parse(_))
+        ));
+        assert!(matches!(
+            SetupRegistry::from_value(json!({ "version": CURRENT_VERSION + 1, "entries": [] })),
+            Err(RegistryError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_when_is_rejected() {
+        let result = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [{
+                "description": "Docker",
+                "commands": [{ "command": "true", "when": "file_exists('/x'" }]
+            }]
+        }));
+        match result {
+            Err(RegistryError::Validation(message)) => {
+                assert!(message.starts_with("\"Docker\": invalid `when`"))
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+
+        let in_revert = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [{
+                "description": "Docker",
+                "commands": [],
+                "config": {
+                    "commands": [],
+                    "revert": [{ "command": "true", "when": "file_exists('/x'" }]
+                }
+            }]
+        }));
+        assert!(matches!(in_revert, Err(RegistryError::Validation(message))
+            if message.starts_with("\"Docker\": invalid `when`")));
+    }
+
+    #[test]
+    fn test_arch_names() {
+        let with_arch = |arch: serde_json::Value| {
+            SetupRegistry::from_value(json!({
+                "version": CURRENT_VERSION,
+                "entries": [{ "description": "Pi", "commands": [{ "command": "true", "arch": arch }] }]
+            }))
+        };
+        assert!(with_arch(json!(["riscv64", "armv6l", "ppc64le", "amd64"])).is_ok());
+        assert!(matches!(
+            with_arch(json!(["arm"])),
+            Err(RegistryError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_custom_distributions() {
+        let registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "distributions": {
+                "ClearLinux": {
+                    "detect": "false",
+                    "install": "swupd bundle-add {{package}}",
+                    "remove": "swupd bundle-remove {{package}}"
+                }
+            },
+            "entries": [{
+                "description": "Dev tools",
+                "commands": [{ "command": "dev-utils", "distribution": "ClearLinux" }]
+            }]
+        }))
+        .unwrap();
+        let command = &registry.entries()[0].commands()[0];
+        assert!(matches!(
+            command.evaluate_skip(),
+            Some(SkipReason::DistributionMismatch { required, .. })
+                if required == [crate::DistributionType::Custom("ClearLinux".to_string())]
+        ));
+
+        // Loading declares nothing; runs declare the registry's distributions until they end.
+        assert!(custom_distribution("ClearLinux").is_none());
+        {
+            let _declared = declare_distributions(registry.distributions());
+            assert!(custom_distribution("ClearLinux").is_some());
+        }
+        assert!(custom_distribution("ClearLinux").is_none());
+
+        let undeclared = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [{
+                "description": "Typo",
+                "commands": [{ "command": "true", "distribution": "Ubunto" }],
+                "config": {
+                    "commands": [],
+                    "revert": [{ "command": "true", "distribution": "Fedorra" }]
+                }
+            }]
+        }));
+        assert!(matches!(undeclared, Err(RegistryError::Validation(message))
+            if message.contains("unknown distribution `Ubunto`")
+                && message.contains("unknown distribution `Fedorra`")));
+    }
+
+    #[test]
+    fn test_plaintext_secret_is_rejected() {
+        let result = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "entries": [],
+            "secrets": { "GITHUB_TOKEN": "ghp_plaintext" }
+        }));
+        assert!(matches!(result, Err(RegistryError::Validation(_))));
+    }
+
+    #[test]
+    fn test_merge_deduplicates_by_description() {
+        let mut base = registry(json!([
+            { "description": "System Update", "commands": [{ "command": "true" }] },
+            { "description": "Git", "commands": [] }
+        ]));
+        let dev = registry(json!([
+            { "description": "Rust", "commands": [] },
+            { "description": "System Update", "commands": [] }
+        ]));
+
+        base.merge(dev);
+
+        let descriptions: Vec<_> = base.entries().iter().map(|e| e.get_description()).collect();
+        assert_eq!(descriptions, ["System Update", "Git", "Rust"]);
+        assert!(base.entries()[0].commands().is_empty());
+    }
+
+    #[test]
+    fn test_load_dir_in_lexical_order() {
+        let dir = std::env::temp_dir().join(format!("lsu-setup-d-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let fragment = |description: &str| {
+            json!({ "version": CURRENT_VERSION, "entries": [{ "description": description, "commands": [] }] })
+                .to_string()
+        };
+        fs::write(dir.join("20-dotfiles.json"), fragment("Dotfiles")).unwrap();
+        fs::write(dir.join("10-packages.json"), fragment("Packages")).unwrap();
+        fs::write(dir.join("README.md"), "not a manifest").unwrap();
+        fs::write(dir.join(".30-draft.json"), "{").unwrap();
+
+        let registry = SetupRegistry::load_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let descriptions: Vec<_> = registry
+            .entries()
+            .iter()
+            .map(|e| e.get_description())
+            .collect();
+        assert_eq!(descriptions, ["Packages", "Dotfiles"]);
+    }
+
+    #[test]
+    fn test_load_dir_keeps_registry_settings() {
+        let dir = std::env::temp_dir().join(format!("lsu-setup-d-settings-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let fragment = |mut manifest: serde_json::Value| {
+            manifest["version"] = json!(CURRENT_VERSION);
+            manifest["entries"] = json!([]);
+            manifest.to_string()
+        };
+        fs::write(
+            dir.join("10-base.json"),
+            fragment(json!({ "on_error": "abort", "max_failures": 2 })),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("20-local.json"),
+            fragment(json!({ "on_error": "continue", "max_failures": 3 })),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("30-extra.json"),
+            fragment(json!({ "resource_limits": { "network": 4 } })),
+        )
+        .unwrap();
+
+        let registry = SetupRegistry::load_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(registry.on_error, Some(OnError::Continue));
+        assert_eq!(registry.max_failures(), Some(3));
+        assert_eq!(registry.resource_limit("network"), 4);
+    }
+
+    #[test]
+    fn test_artifact_names_stay_inside_their_directory() {
+        let with_artifact = |name: &str| {
+            SetupRegistry::from_value(json!({
+                "version": CURRENT_VERSION,
+                "entries": [{
+                    "description": "Neovim",
+                    "commands": [],
+                    "artifacts": [{ "name": name, "url": "https://example.com/nvim.tar.gz" }]
+                }]
+            }))
+        };
+        assert!(with_artifact("nvim.tar.gz").is_ok());
+        for name in [
+            "",
+            "/etc/profile",
+            "../nvim",
+            "bin/nvim",
+            ".nvim",
+            "nvim..tar",
+        ] {
+            assert!(
+                matches!(with_artifact(name), Err(RegistryError::Validation(message))
+                    if message.contains("invalid artifact name")),
+                "{:?} was accepted",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let dir = std::env::temp_dir().join(format!("lsu-bundle-{}", std::process::id()));
+        let source = dir.join("tool.txt");
+        let bundle = dir.join("bundle");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&source, "bundled").unwrap();
+        let url = format!("file://{}", source.display());
+        let check = r#"test "$(cat "$LSU_ARTIFACT_BUNDLED_TOOL")" = bundled"#;
+
+        let mut offline = registry(json!([
+            { "description": "Tool", "commands": [{ "command": check }],
+              "artifacts": [{ "name": "bundled-tool", "url": url }] },
+            { "description": "Tool again", "commands": [{ "command": check }],
+              "artifacts": [{ "name": "bundled-tool", "url": url }] }
+        ]));
+        offline.bundle(&bundle).unwrap();
+        fs::remove_file(&source).unwrap();
+        offline.use_bundle(&bundle).unwrap();
+        assert_eq!(offline.execute().count(Status::Success), 2);
+
+        let clash = registry(json!([
+            { "description": "Tool", "commands": [],
+              "artifacts": [{ "name": "bundled-tool", "url": "file:///dev/null" }] },
+            { "description": "Other tool", "commands": [],
+              "artifacts": [{ "name": "bundled-tool", "url": url }] }
+        ]));
+        let result = clash.bundle(&dir.join("clash"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(result, Err(RegistryError::Io(e))
+            if e.to_string().contains("two different sources")));
+    }
+
+    #[test]
+    fn test_frozen_lock_requires_every_artifact() {
+        let mut registry = registry(json!([{
+            "description": "Neovim",
+            "commands": [],
+            "artifacts": [{ "name": "nvim", "url": "https://example.com/latest/nvim.tar.gz" }]
+        }]));
+        let lockfile = std::env::temp_dir().join(format!("lsu-{}.lock", std::process::id()));
+
+        assert!(matches!(
+            registry.lock_artifacts(&lockfile, true),
+            Err(RegistryError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_lock_is_keyed_by_source() {
+        let neovim = |url: &str| {
+            registry(json!([{
+                "description": "Neovim",
+                "commands": [],
+                "artifacts": [{ "name": "nvim", "url": url }]
+            }]))
+        };
+        let lockfile = std::env::temp_dir().join(format!("lsu-keyed-{}.lock", std::process::id()));
+        let pin = json!({ "url": "https://example.com/v1/nvim.tar.gz", "sha256": "abc" });
+        fs::write(
+            &lockfile,
+            json!({ "artifacts": {
+                "nvim https://example.com/latest/nvim.tar.gz": pin,
+                "gone https://example.com/gone.tar.gz": pin
+            } })
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut registry = neovim("https://example.com/latest/nvim.tar.gz");
+        registry.lock_artifacts(&lockfile, false).unwrap();
+        let lock = Lockfile::load(&lockfile).unwrap();
+        assert_eq!(
+            lock.artifacts.keys().collect::<Vec<_>>(),
+            ["nvim https://example.com/latest/nvim.tar.gz"]
+        );
+
+        let mut moved = neovim("https://example.org/latest/nvim.tar.gz");
+        assert!(matches!(
+            moved.lock_artifacts(&lockfile, true),
+            Err(RegistryError::Validation(_))
+        ));
+        fs::remove_file(lockfile).unwrap();
+    }
+
+    #[test]
+    fn test_execute_parallel_keeps_manifest_order() {
+        let mut registry = registry(json!([
+            { "description": "Slow", "commands": [{ "command": "sleep 0.2" }] },
+            { "description": "Fast", "commands": [{ "command": "true" }] },
+            { "description": "Broken", "commands": [{ "command": "false" }] }
+        ]));
+
+        let report = registry.execute_parallel(3);
+
+        let descriptions: Vec<_> = report
+            .entries
+            .iter()
+            .map(|e| e.description.as_str())
+            .collect();
+        assert_eq!(descriptions, ["Slow", "Fast", "Broken"]);
+        assert_eq!(report.count(Status::Failure), 1);
+    }
+
+    #[test]
+    fn test_lock_classes_limit_concurrency() {
+        let dir = std::env::temp_dir().join(format!("lsu-locks-{}", std::process::id()));
+        // `mkdir` fails if another entry of the same class holds the directory.
+        let hold = |class: &str| {
+            format!(
+                "mkdir {0}/{1} && sleep 0.2 && rmdir {0}/{1}",
+                dir.display(),
+                class
+            )
+        };
+        // Each clone waits until all three have started, so they only pass when the
+        // `network` limit lets them run together; a lower limit runs into the timeout.
+        let meet = |own: usize| {
+            format!(
+                "touch {0}/clone{1} && until [ -e {0}/clone1 ] && [ -e {0}/clone2 ] && [ -e {0}/clone3 ]; do sleep 0.01; done",
+                dir.display(),
+                own
+            )
+        };
+        fs::create_dir_all(&dir).unwrap();
+        let mut registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "resource_limits": { "network": 3 },
+            "entries": [
+                { "description": "apt 1", "lock": "apt", "commands": [{ "command": hold("apt") }] },
+                { "description": "apt 2", "lock": "apt", "commands": [{ "command": hold("apt") }] },
+                { "description": "clone 1", "lock": "network", "commands": [{ "command": meet(1), "timeout": "10s" }] },
+                { "description": "clone 2", "lock": "network", "commands": [{ "command": meet(2), "timeout": "10s" }] },
+                { "description": "clone 3", "lock": "network", "commands": [{ "command": meet(3), "timeout": "10s" }] }
+            ]
+        }))
+        .unwrap();
+
+        let report = registry.execute_parallel(5);
+        assert_eq!(report.count(Status::Success), 5);
+        for clone in 1..=3 {
+            fs::remove_file(dir.join(format!("clone{}", clone))).unwrap();
+        }
+
+        let mut registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "resource_limits": { "cpu-heavy": 1, "network-heavy": 2 },
+            "entries": [
+                { "description": "paru build", "resources": ["cpu-heavy", "network-heavy"],
+                  "commands": [{ "command": hold("cpu") }] },
+                { "description": "yay build", "resources": ["cpu-heavy"],
+                  "commands": [{ "command": hold("cpu") }] },
+                { "description": "fetch", "resources": ["network-heavy"],
+                  "commands": [{ "command": "sleep 0.1" }] }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(registry.execute_parallel(3).count(Status::Success), 3);
+        fs::remove_dir(dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_dependency_skips_dependents() {
+        let mut registry = registry(json!([
+            { "description": "cargo install tools", "commands": [], "depends_on": ["rustup"] },
+            { "description": "Install rustup", "name": "rustup", "commands": [{ "command": "false" }] },
+            { "description": "Git", "commands": [{ "command": "true" }] }
+        ]));
+
+        for report in [registry.execute_parallel(2), registry.execute()] {
+            let statuses: Vec<_> = report
+                .entries
+                .iter()
+                .map(|e| (e.description.as_str(), e.status.clone()))
+                .collect();
+            assert_eq!(
+                statuses,
+                [
+                    ("Install rustup", Status::Failure),
+                    ("cargo install tools", Status::Skipped),
+                    ("Git", Status::Success)
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_tmpdir_is_shared_and_removed() {
+        let mut registry = registry(json!([
+            { "description": "Write", "commands": [{ "command": "echo hi > {{tmpdir}}/file" }] },
+            { "description": "Read", "commands": [{ "command": "test -f \"$LSU_TMPDIR/file\"" }] },
+            { "description": "Own", "own_tmpdir": true,
+              "commands": [{ "command": "test ! -f {{tmpdir}}/file && pwd > /dev/null" }] },
+            { "description": "Checked", "commands": [{ "command": "false", "check": "cat {{tmpdir}}/file" }] }
+        ]));
+
+        let report = registry.execute();
+        assert_eq!(report.count(Status::Success), 4);
+        assert_eq!(registry.entries()[3].commands()[0].status(), Status::Passed);
+    }
+
+    #[test]
+    fn test_resume_skips_completed_entries() {
+        let marker = std::env::temp_dir().join(format!("lsu-resume-{}", std::process::id()));
+        let path = std::env::temp_dir().join(format!("lsu-resume-{}.json", std::process::id()));
+        let store = StateStore::new(&path);
+        let mut registry = registry(json!([
+            { "description": "Packages", "commands": [{ "command": "true" }] },
+            { "description": "Dotfiles", "commands": [{ "command": format!("test -f {}", marker.display()) }] }
+        ]));
+
+        let first = registry.execute_resumable(&store, false);
+        assert_eq!(first.count(Status::Failure), 1);
+        assert!(store.load().unwrap().completed.contains("Packages"));
+
+        fs::write(&marker, "").unwrap();
+        let second = registry.execute_resumable(&store, true);
+        assert_eq!(second.entries[0].skip_reason, Some(SkipReason::Completed));
+        assert!(second.is_success());
+        assert!(store.load().unwrap().completed.is_empty());
+
+        fs::remove_file(marker).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_user_scope_is_refused_as_plain_root() {
+        if !crate::utils::is_root() || crate::utils::invoking_user().is_some() {
+            return;
+        }
+        let mut registry = registry(json!([
+            { "description": "Dotfiles", "scope": "user", "commands": [{ "command": "true" }] },
+            { "description": "Packages", "scope": "system", "commands": [{ "command": "true" }] }
+        ]));
+
+        let report = registry.execute();
+        assert_eq!(report.entries[0].status, Status::Failure);
+        assert_eq!(report.entries[1].status, Status::Success);
+    }
+
+    #[test]
+    fn test_release_upgrade_guard() {
+        assert!(!SetupRegistry::allowed_during(
+            ReleaseUpgradeState::InProgress
+        ));
+        assert!(SetupRegistry::allowed_during(ReleaseUpgradeState::None));
+        assert!(SetupRegistry::allowed_during(ReleaseUpgradeState::Pending(
+            String::new()
+        )));
+    }
+
+    #[test]
+    fn test_profile_matching() {
+        let registry = registry(json!([
+            { "description": "Shared", "commands": [] },
+            { "description": "Work VPN", "commands": [], "profiles": ["work", "laptop"] },
+            { "description": "Steam", "commands": [], "profiles": ["gaming"] }
+        ]));
+
+        let work: Vec<_> = registry
+            .entries()
+            .iter()
+            .filter(|e| e.matches_profile("work"))
+            .map(|e| e.get_description())
+            .collect();
+        assert_eq!(work, ["Shared", "Work VPN"]);
+    }
+
+    #[test]
+    fn test_tag_filtering() {
+        let registry = registry(json!([
+            { "description": "Untagged", "commands": [] },
+            { "description": "Rust", "commands": [], "tags": ["dev"] },
+            { "description": "VS Code", "commands": [], "tags": ["dev", "gui"] }
+        ]));
+        let selected = |include: &[&str], exclude: &[&str]| -> Vec<&String> {
+            registry
+                .entries()
+                .iter()
+                .filter(|e| e.matches_tags(include, exclude))
+                .map(|e| e.get_description())
+                .collect()
+        };
+
+        assert_eq!(selected(&[], &[]).len(), 3);
+        assert_eq!(selected(&["dev"], &["gui"]), ["Rust"]);
+        assert_eq!(selected(&[], &["gui"]), ["Untagged", "Rust"]);
+    }
+
+    #[test]
+    fn test_execute_entry() {
+        let mut registry = registry(json!([
+            { "description": "Fails", "commands": [{ "command": "false" }] },
+            { "description": "Neovim setup", "name": "nvim", "commands": [{ "command": "true" }] }
+        ]));
+
+        let result = registry.execute_entry("Neovim setup").unwrap();
+        assert_eq!(result.status, Status::Success);
+        assert_eq!(
+            registry.execute_entry("nvim").unwrap().status,
+            Status::Success
+        );
+        assert_eq!(registry.execute_entry(0).unwrap().status, Status::Failure);
+        assert!(registry.execute_entry("Missing").is_err());
+        assert!(registry.execute_entry(2).is_err());
+    }
+
+    #[test]
+    fn test_on_error_abort() {
+        let mut registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "on_error": "abort",
+            "entries": [
+                { "description": "Keeps going", "on_error": "continue", "commands": [
+                    { "command": "false" },
+                    { "command": "true" }
+                ] },
+                { "description": "Stops", "commands": [
+                    { "command": "false" },
+                    { "command": "true" }
+                ] },
+                { "description": "Never runs", "commands": [{ "command": "true" }] }
+            ]
+        }))
+        .unwrap();
+
+        let report = registry.execute();
+        assert_eq!(report.entries[0].status, Status::Failure);
+        assert_eq!(
+            registry.entries()[0].commands()[1].status(),
+            Status::Success
+        );
+        assert_eq!(report.entries[1].status, Status::Failure);
+        assert_ne!(
+            registry.entries()[1].commands()[1].status(),
+            Status::Success
+        );
+        assert_eq!(
+            report.entries[2].skip_reason,
+            Some(SkipReason::Aborted("Stops".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_on_error_prompt_asks_once_per_failure() {
+        let registry = registry(json!([
+            { "description": "Asks", "on_error": "prompt", "commands": [{ "command": "false" }] }
+        ]));
+        let entry = &registry.entries()[0];
+        entry.prompt_answer.set(Some(false));
+        let result = entry.entry_result(Status::Failure, std::time::Instant::now());
+
+        // Answered for the command already, so the entry does not read stdin again.
+        assert_eq!(
+            SetupRegistry::abort_after(None, entry, &result, 1),
+            Some(SkipReason::Aborted("Asks".to_string()))
+        );
+        entry.prompt_answer.set(Some(true));
+        assert_eq!(SetupRegistry::abort_after(None, entry, &result, 1), None);
+    }
+
+    #[test]
+    fn test_execute_parallel_rejects_prompt() {
+        let mut registry = registry(json!([
+            { "description": "Fine", "commands": [{ "command": "true" }] },
+            { "description": "Asks", "commands": [{ "command": "false", "on_error": "prompt" }] }
+        ]));
+
+        assert!(registry.execute_parallel(2).entries.is_empty());
+        assert_eq!(registry.entries()[0].commands()[0].status(), Status::Normal);
+    }
+
+    #[test]
+    fn test_requires_previous_chains_fallbacks() {
+        let mut registry = registry(json!([
+            { "description": "Fallback", "on_error": "abort", "commands": [
+                { "command": "false" },
+                { "command": "true", "requires_previous": "failure" },
+                { "command": "true", "requires_previous": "success" },
+                { "command": "true", "requires_previous": "failure" }
+            ] },
+            { "description": "Both fail", "commands": [
+                { "command": "false" },
+                { "command": "false", "requires_previous": "failure" },
+                { "command": "true", "requires_previous": "success" }
+            ] }
+        ]));
+
+        let report = registry.execute();
+        let statuses = |entry: usize| -> Vec<Status> {
+            registry.entries()[entry]
+                .commands()
+                .iter()
+                .map(crate::CommandStruct::status)
+                .collect()
+        };
+        assert_eq!(report.entries[0].status, Status::Success);
+        assert!(report.entries[0].failed_commands.is_empty());
+        assert_eq!(
+            statuses(0),
+            [
+                Status::Warning,
+                Status::Success,
+                Status::Success,
+                Status::Skipped
+            ]
+        );
+        assert_eq!(report.entries[1].status, Status::Failure);
+        assert_eq!(
+            statuses(1),
+            [Status::Failure, Status::Failure, Status::Skipped]
+        );
+    }
+
+    #[test]
+    fn test_rollback_runs_undo() {
+        let marker = std::env::temp_dir().join(format!("lsu-rollback-{}", std::process::id()));
+        let marker = marker.display();
+        let mut registry = registry(json!([
+            { "description": "Half done", "rollback": true, "commands": [
+                { "command": format!("touch {}", marker), "undo": format!("rm {}", marker) },
+                { "command": "false" }
+            ] }
+        ]));
+
+        let report = registry.execute();
+        assert_eq!(report.entries[0].status, Status::Failure);
+        assert!(!Path::new(&marker.to_string()).exists());
+    }
+
+    #[test]
+    fn test_hooks_surround_commands() {
+        let mut registry = registry(json!([
+            { "description": "Guarded",
+              "pre_hooks": [{ "command": "false" }],
+              "commands": [{ "command": "true" }],
+              "post_hooks": [{ "command": "true" }] }
+        ]));
+
+        let report = registry.execute();
+        let entry = &registry.entries()[0];
+        assert_eq!(report.entries[0].status, Status::Failure);
+        assert_eq!(report.entries[0].failed_commands, ["false"]);
+        assert_ne!(entry.commands()[0].status(), Status::Success);
+        assert_eq!(
+            entry.all_commands().last().unwrap().status(),
+            Status::Success
+        );
+    }
+
+    #[test]
+    fn test_max_failures() {
+        let mut registry = SetupRegistry::from_value(json!({
+            "version": CURRENT_VERSION,
+            "max_failures": 2,
+            "entries": [
+                { "description": "A", "commands": [{ "command": "false" }] },
+                { "description": "B", "commands": [{ "command": "true" }] },
+                { "description": "C", "commands": [{ "command": "false" }] },
+                { "description": "D", "commands": [{ "command": "true" }] }
+            ]
+        }))
+        .unwrap();
+
+        let report = registry.execute();
+        assert_eq!(report.count(Status::Failure), 2);
+        assert_eq!(
+            report.entries[3].skip_reason,
+            Some(SkipReason::TooManyFailures(2))
+        );
+    }
+}
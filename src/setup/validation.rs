@@ -0,0 +1,165 @@
+use super::{RegistryError, SetupRegistry};
+use crate::artifact::is_valid_artifact_name;
+use crate::check::parse_version;
+use crate::condition::Condition;
+use crate::distribution::{normalize_arch, ARCHITECTURES};
+use crate::manifest::{is_encrypted, is_valid_secret_name};
+use crate::utils::parse_duration;
+use crate::DistributionType;
+
+impl SetupRegistry {
+    /// Checks the registry for problems that would otherwise only surface mid-run,
+    /// naming the offending entry and where it was defined.
+    pub fn validate(&self) -> Result<(), RegistryError> {
+        let mut problems = Vec::new();
+
+        for entry in self.entries() {
+            let conditions = entry
+                .when()
+                .into_iter()
+                .chain(entry.every_command().filter_map(|command| command.when()));
+
+            for when in conditions {
+                if let Err(e) = Condition::parse(when) {
+                    problems.push(format!("{}: invalid `when`: {}", entry.location(), e));
+                }
+            }
+
+            if let Some(estimate) = entry.raw_estimate() {
+                if parse_duration(estimate).is_none() {
+                    problems.push(format!(
+                        "{}: invalid `estimate`: {}",
+                        entry.location(),
+                        estimate
+                    ));
+                }
+            }
+
+            for command in entry.all_commands() {
+                let durations = [
+                    ("retry_delay", command.retry_delay()),
+                    (
+                        "retry.base",
+                        command.retry().and_then(|retry| retry.base.as_deref()),
+                    ),
+                    ("timeout", command.raw_timeout()),
+                ];
+                for (field, value) in durations {
+                    if let Some(value) = value.filter(|value| parse_duration(value).is_none()) {
+                        problems.push(format!(
+                            "{}: invalid `{}`: {}",
+                            entry.location(),
+                            field,
+                            value
+                        ));
+                    }
+                }
+            }
+
+            let entry_versions = [
+                ("min_version", entry.min_version()),
+                ("max_version", entry.max_version()),
+            ];
+            let command_versions = entry.all_commands().flat_map(|command| {
+                [
+                    ("min_version", command.min_version()),
+                    ("max_version", command.max_version()),
+                ]
+            });
+            for (field, value) in entry_versions.into_iter().chain(command_versions) {
+                if let Some(value) = value.filter(|value| parse_version(value).is_none()) {
+                    problems.push(format!(
+                        "{}: invalid `{}`: {}",
+                        entry.location(),
+                        field,
+                        value
+                    ));
+                }
+            }
+
+            let undeclared = entry
+                .every_command()
+                .flat_map(|command| command.distributions())
+                .filter_map(|target| match target {
+                    DistributionType::Custom(name) => Some(name),
+                    _ => None,
+                })
+                .filter(|name| !self.distributions().contains_key(*name));
+            for name in undeclared {
+                problems.push(format!(
+                    "{}: unknown distribution `{}`; declare it under `distributions`",
+                    entry.location(),
+                    name
+                ));
+            }
+
+            let arches = entry.all_commands().flat_map(|command| command.arch());
+            for arch in arches.filter(|arch| !ARCHITECTURES.contains(&normalize_arch(arch))) {
+                problems.push(format!(
+                    "{}: unknown architecture `{}`, expected one of {}",
+                    entry.location(),
+                    arch,
+                    ARCHITECTURES.join(", ")
+                ));
+            }
+
+            let artifacts = entry.artifacts().iter().map(|artifact| artifact.name());
+            for name in artifacts.filter(|name| !is_valid_artifact_name(name)) {
+                problems.push(format!(
+                    "{}: invalid artifact name `{}`; it must be a plain file name",
+                    entry.location(),
+                    name
+                ));
+            }
+
+            for check in entry.all_commands().filter_map(|command| command.check()) {
+                if let Err(e) = check.validate() {
+                    problems.push(format!("{}: {}", entry.location(), e));
+                }
+            }
+        }
+
+        if let Err(RegistryError::Validation(problem)) = self.execution_order() {
+            problems.push(problem);
+        }
+
+        problems.extend(self.relation_problems());
+
+        for (class, limit) in self.resource_limits() {
+            if *limit == 0 {
+                problems.push(format!(
+                    "resource class `{}`: limit must be at least 1",
+                    class
+                ));
+            }
+        }
+
+        for name in self.distributions().keys() {
+            let parsed = serde_json::from_value(serde_json::Value::String(name.clone()));
+            if !matches!(parsed, Ok(DistributionType::Custom(_))) {
+                problems.push(format!(
+                    "`distributions.{}` is built in and cannot be redefined",
+                    name
+                ));
+            }
+        }
+
+        for (name, value) in self.secrets() {
+            if !is_valid_secret_name(name) {
+                problems.push(format!(
+                    "secret `{}`: not a valid environment variable name",
+                    name
+                ));
+            }
+            if !is_encrypted(value) {
+                problems.push(format!("secret `{}`: value is not age-encrypted", name));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(RegistryError::Validation(problems.join("\n")))
+        }
+    }
+}
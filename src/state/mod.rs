@@ -0,0 +1,7 @@
+#[cfg(feature = "remote")]
+mod remote;
+mod run_state;
+
+#[cfg(feature = "remote")]
+pub use remote::{hostname, RemoteState};
+pub use run_state::{RunState, StateStore};
@@ -0,0 +1,155 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "remote")]
+use super::remote::{hostname, RemoteState};
+use crate::report::RunReport;
+use crate::utils::{expand_path, Status};
+
+/// Progress persisted between runs (and reboots).
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct RunState {
+    /// Index of the next phase to run when entries are split by `reboot_after` barriers.
+    pub phase: usize,
+    /// The manifest and selection `phase` counts through, set while a phased run is unfinished.
+    pub phase_run: Option<String>,
+    /// Last measured duration of each entry that actually ran, in milliseconds, by description.
+    pub durations: BTreeMap<String, u64>,
+    /// Descriptions of entries that succeeded in the current, unfinished run.
+    pub completed: BTreeSet<String>,
+    /// Fingerprint of each entry's definition when it last succeeded, by description.
+    pub applied: BTreeMap<String, String>,
+    /// Fingerprint of the last manifest that passed `SetupRegistry::promote`.
+    pub promoted: Option<String>,
+}
+
+impl RunState {
+    /// Remembers how long the entries that ran took; skipped entries say nothing about cost.
+    pub fn record_durations(&mut self, report: &RunReport) {
+        for entry in &report.entries {
+            if matches!(entry.status, Status::Success | Status::Warning) {
+                self.durations
+                    .insert(entry.description.clone(), entry.duration.as_millis() as u64);
+            }
+        }
+    }
+
+    pub fn duration_of(&self, description: &str) -> Option<Duration> {
+        self.durations
+            .get(description)
+            .map(|millis| Duration::from_millis(*millis))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Backend {
+    File(PathBuf),
+    #[cfg(feature = "remote")]
+    Remote(RemoteState),
+}
+
+/// Reads and writes `RunState` as JSON, by default under `$XDG_STATE_HOME/linux_setup_ur/`.
+#[derive(Debug, Clone)]
+pub struct StateStore {
+    backend: Backend,
+}
+
+impl StateStore {
+    pub fn new(path: &Path) -> Self {
+        StateStore {
+            backend: Backend::File(path.to_path_buf()),
+        }
+    }
+
+    /// A store for `location`: a local path, or with the `remote` feature an
+    /// `http(s)://` endpoint or `s3://bucket/prefix`, keyed by this machine's hostname.
+    pub fn from_location(location: &str) -> io::Result<Self> {
+        if !location.contains("://") {
+            return Ok(StateStore::new(&expand_path(location)));
+        }
+        #[cfg(feature = "remote")]
+        {
+            let remote = RemoteState::parse(location, &hostname()?)?;
+            Ok(StateStore {
+                backend: Backend::Remote(remote),
+            })
+        }
+        #[cfg(not(feature = "remote"))]
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} needs the `remote` feature", location),
+        ))
+    }
+
+    /// The local state file, if state is kept on this machine.
+    pub fn path(&self) -> Option<&Path> {
+        match &self.backend {
+            Backend::File(path) => Some(path),
+            #[cfg(feature = "remote")]
+            Backend::Remote(_) => None,
+        }
+    }
+
+    /// Nothing stored yet means nothing has run yet.
+    pub fn load(&self) -> io::Result<RunState> {
+        let content = match &self.backend {
+            Backend::File(path) => match fs::read_to_string(path) {
+                Ok(content) => Some(content),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            },
+            #[cfg(feature = "remote")]
+            Backend::Remote(remote) => remote.get()?,
+        };
+        match content {
+            Some(content) => serde_json::from_str(&content).map_err(io::Error::other),
+            None => Ok(RunState::default()),
+        }
+    }
+
+    pub fn save(&self, state: &RunState) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(state).map_err(io::Error::other)?;
+        match &self.backend {
+            Backend::File(path) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, content)
+            }
+            #[cfg(feature = "remote")]
+            Backend::Remote(remote) => remote.put(&content),
+        }
+    }
+}
+
+impl Default for StateStore {
+    fn default() -> Self {
+        StateStore::new(&expand_path("${XDG_STATE_HOME}/linux_setup_ur/state.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_round_trip() {
+        let path = std::env::temp_dir().join(format!("lsu-state-{}.json", std::process::id()));
+        let store = StateStore::new(&path);
+        assert_eq!(store.load().unwrap(), RunState::default());
+
+        store
+            .save(&RunState {
+                phase: 2,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(store.load().unwrap().phase, 2);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{expand_path, Status};
+use crate::Configurator;
+
+const INCLUDE_DIR: &str = "${XDG_CONFIG_HOME}/git/include";
+
+/// The `git config` arguments that pick the file, e.g. `--global`.
+type Scope = Vec<String>;
+
+/// Sets git config keys such as `user.email` or `alias.st`, globally or (with `directory`)
+/// in a file pulled in by an `includeIf "gitdir:<directory>/"` section.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GitConfig {
+    /// Only applies to repositories under this directory, e.g. `~/work`.
+    directory: Option<String>,
+    #[serde(flatten)]
+    settings: BTreeMap<String, String>,
+    #[serde(skip)]
+    changes: RefCell<Vec<String>>,
+    /// Each key the last `apply` changed, with its scope and the value it had before.
+    #[serde(skip)]
+    previous: RefCell<Vec<(Scope, String, Option<String>)>>,
+}
+
+impl GitConfig {
+    /// The directory with a trailing slash, so `gitdir:` matches everything below it.
+    fn gitdir(&self) -> Option<String> {
+        self.directory.as_deref().map(|dir| {
+            format!(
+                "{}/",
+                expand_path(dir).display().to_string().trim_end_matches('/')
+            )
+        })
+    }
+
+    fn include_file(gitdir: &str) -> PathBuf {
+        let name: String = gitdir
+            .trim_matches('/')
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        expand_path(INCLUDE_DIR).join(format!("{}.gitconfig", name))
+    }
+
+    fn scope_args(&self) -> Scope {
+        match self.gitdir() {
+            Some(gitdir) => vec![
+                "--file".to_string(),
+                Self::include_file(&gitdir).display().to_string(),
+            ],
+            None => vec!["--global".to_string()],
+        }
+    }
+
+    fn git_config(scope: &[String], args: &[&str]) -> Option<process::Output> {
+        process::Command::new("git")
+            .arg("config")
+            .args(scope)
+            .args(args)
+            .output()
+            .ok()
+    }
+
+    fn current(scope: &[String], key: &str) -> Option<String> {
+        Self::git_config(scope, &["--get", key])
+            .filter(|output| output.status.success())
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .trim_end()
+                    .to_string()
+            })
+    }
+
+    fn set(scope: &[String], key: &str, value: &str) -> Status {
+        match Self::git_config(scope, &[key, value]) {
+            Some(output) if output.status.success() => Status::Success,
+            Some(output) => {
+                Status::Failure.print_message(&format!(
+                    "git config {}: {}",
+                    key,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+                Status::Failure
+            }
+            None => {
+                Status::Failure.print_message(&format!("git config {}: git not found", key));
+                Status::Failure
+            }
+        }
+    }
+
+    /// Settings to apply with the scope they belong to, including the `includeIf` hook.
+    fn desired(&self) -> Vec<(Scope, String, String)> {
+        let scope = self.scope_args();
+        let mut desired: Vec<_> = self
+            .settings
+            .iter()
+            .map(|(key, value)| (scope.clone(), key.clone(), value.clone()))
+            .collect();
+        if let Some(gitdir) = self.gitdir() {
+            desired.push((
+                vec!["--global".to_string()],
+                format!("includeIf.gitdir:{}.path", gitdir),
+                Self::include_file(&gitdir).display().to_string(),
+            ));
+        }
+        desired
+    }
+
+    fn apply_settings(&self, desired: Vec<(Scope, String, String)>) -> Status {
+        let mut status = Status::Passed;
+        self.changes.borrow_mut().clear();
+        self.previous.borrow_mut().clear();
+
+        for (scope, key, value) in desired {
+            let current = Self::current(&scope, &key);
+            if current.as_deref() == Some(value.as_str()) {
+                continue;
+            }
+            if let Some(file) = scope.get(1) {
+                if let Some(parent) = PathBuf::from(file).parent() {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+            }
+            if Self::set(&scope, &key, &value) == Status::Failure {
+                status = Status::Failure;
+                continue;
+            }
+
+            let change = match &current {
+                Some(current) => format!("git {}: {} -> {}", key, current, value),
+                None => format!("git {}: {}", key, value),
+            };
+            Status::Success.print_message(&change);
+            self.changes.borrow_mut().push(change);
+            self.previous.borrow_mut().push((scope, key, current));
+            if status == Status::Passed {
+                status = Status::Success;
+            }
+        }
+
+        if status == Status::Passed {
+            Status::Passed.print_message(&format!("git config ({} keys)", self.settings.len()));
+        }
+        status
+    }
+}
+
+impl Configurator for GitConfig {
+    fn apply(&self) -> Status {
+        self.apply_settings(self.desired())
+    }
+
+    /// Undoes what the last `apply` changed: keys it overwrote get their old value
+    /// back, keys it added are unset, and keys it left alone are not touched.
+    fn revert(&self) -> Status {
+        let mut status = Status::Success;
+        for (scope, key, previous) in self.previous.take().into_iter().rev() {
+            let restored = match &previous {
+                Some(value) => Self::set(&scope, &key, value),
+                None => match Self::git_config(&scope, &["--unset", &key]) {
+                    Some(output) if output.status.success() => Status::Success,
+                    _ => {
+                        Status::Failure.print_message(&format!("git config --unset {}", key));
+                        Status::Failure
+                    }
+                },
+            };
+            if restored == Status::Failure {
+                status = Status::Failure;
+            }
+        }
+        status
+    }
+
+    fn changes(&self) -> Vec<String> {
+        self.changes.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_git_config_scopes() {
+        let global: GitConfig =
+            serde_json::from_value(json!({ "user.email": "me@example.org" })).unwrap();
+        assert_eq!(global.scope_args(), ["--global"]);
+        assert_eq!(global.desired().len(), 1);
+
+        let work: GitConfig = serde_json::from_value(json!({
+            "directory": "/home/me/work",
+            "user.email": "me@work.example"
+        }))
+        .unwrap();
+        let desired = work.desired();
+        assert_eq!(desired[0].1, "user.email");
+        assert_eq!(desired[1].1, "includeIf.gitdir:/home/me/work/.path");
+        assert!(desired[1].2.ends_with("git/include/home-me-work.gitconfig"));
+        assert_eq!(desired[0].0[1], desired[1].2);
+    }
+
+    #[test]
+    fn test_revert_restores_existing_keys() {
+        let file = std::env::temp_dir().join(format!("lsu-gitconfig-{}", std::process::id()));
+        std::fs::write(&file, "[user]\n\tname = Old Name\n").unwrap();
+        let scope = vec!["--file".to_string(), file.display().to_string()];
+        let config: GitConfig = serde_json::from_value(json!({})).unwrap();
+
+        let desired = vec![
+            (
+                scope.clone(),
+                "user.name".to_string(),
+                "New Name".to_string(),
+            ),
+            (scope.clone(), "alias.st".to_string(), "status".to_string()),
+        ];
+        assert_eq!(config.apply_settings(desired), Status::Success);
+        assert_eq!(
+            GitConfig::current(&scope, "user.name").as_deref(),
+            Some("New Name")
+        );
+
+        assert_eq!(config.revert(), Status::Success);
+        assert_eq!(
+            GitConfig::current(&scope, "user.name").as_deref(),
+            Some("Old Name")
+        );
+        assert_eq!(GitConfig::current(&scope, "alias.st"), None);
+
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+use std::process;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{SkipReason, Status};
+use crate::Configurator;
+
+const MEDIA_KEYS: &str = "org.gnome.settings-daemon.plugins.media-keys";
+const KEYBINDING_SCHEMA: &str = "org.gnome.settings-daemon.plugins.media-keys.custom-keybinding";
+const KEYBINDINGS_PATH: &str = "/org/gnome/settings-daemon/plugins/media-keys/custom-keybindings";
+const KEYBINDINGS_KEY: &str = "custom-keybindings";
+
+/// Quotes `value` as a GVariant string literal.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Parses a GVariant string literal as printed by `gsettings get`.
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let inner = value
+        .strip_prefix('\'')
+        .and_then(|v| v.strip_suffix('\''))
+        .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))
+        .unwrap_or(value);
+    let mut unescaped = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Parses an `as` value such as `@as []` or `['/a/', '/b/']`. Keybinding paths never
+/// contain commas or quotes, so a plain split is enough.
+fn parse_list(value: &str) -> Vec<String> {
+    let value = value.trim().trim_start_matches("@as").trim();
+    let inner = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or("");
+    inner
+        .split(',')
+        .map(unquote)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn format_list(items: &[String]) -> String {
+    let quoted: Vec<String> = items.iter().map(|item| quote(item)).collect();
+    format!("[{}]", quoted.join(", "))
+}
+
+fn gsettings(args: &[&str]) -> Result<String, String> {
+    let output = process::Command::new("gsettings")
+        .args(args)
+        .output()
+        .map_err(|e| format!("gsettings: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(format!(
+            "gsettings {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// A GNOME custom keyboard shortcut, stored under its own relocatable dconf path
+/// and registered in the `custom-keybindings` list.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GnomeKeybinding {
+    /// Stable identifier used in the dconf path, e.g. `terminal`.
+    id: String,
+    name: String,
+    command: String,
+    /// Accelerator such as `<Super>Return`.
+    binding: String,
+}
+
+impl GnomeKeybinding {
+    fn path(&self) -> String {
+        let id: String = self
+            .id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        format!("{}/lsu-{}/", KEYBINDINGS_PATH, id)
+    }
+
+    fn schema(&self) -> String {
+        format!("{}:{}", KEYBINDING_SCHEMA, self.path())
+    }
+
+    fn values(&self) -> [(&str, &str); 3] {
+        [
+            ("name", &self.name),
+            ("command", &self.command),
+            ("binding", &self.binding),
+        ]
+    }
+
+    fn registered() -> Result<Vec<String>, String> {
+        gsettings(&["get", MEDIA_KEYS, KEYBINDINGS_KEY]).map(|value| parse_list(&value))
+    }
+
+    fn describe(&self) -> String {
+        format!("GNOME shortcut {} ({})", self.binding, self.name)
+    }
+
+    fn skip_without_gsettings(&self) -> Option<Status> {
+        if gsettings(&["list-schemas"]).is_ok_and(|schemas| schemas.contains(MEDIA_KEYS)) {
+            return None;
+        }
+        let reason = SkipReason::Condition("GNOME settings available".to_string());
+        Status::Skipped.print_message(&format!("{} ({})", self.describe(), reason));
+        Some(Status::Skipped)
+    }
+
+    fn sync(&self) -> Result<bool, String> {
+        let schema = self.schema();
+        let mut changed = false;
+        for (key, value) in self.values() {
+            if unquote(&gsettings(&["get", &schema, key])?) != value {
+                gsettings(&["set", &schema, key, &quote(value)])?;
+                changed = true;
+            }
+        }
+
+        // The list is only written once the binding is complete, in a single `set`.
+        let mut registered = Self::registered()?;
+        let path = self.path();
+        if !registered.contains(&path) {
+            registered.push(path);
+            gsettings(&[
+                "set",
+                MEDIA_KEYS,
+                KEYBINDINGS_KEY,
+                &format_list(&registered),
+            ])?;
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    fn remove(&self) -> Result<(), String> {
+        let path = self.path();
+        let registered = Self::registered()?;
+        if registered.contains(&path) {
+            let remaining: Vec<String> = registered.into_iter().filter(|p| *p != path).collect();
+            gsettings(&["set", MEDIA_KEYS, KEYBINDINGS_KEY, &format_list(&remaining)])?;
+        }
+
+        let schema = self.schema();
+        for (key, _) in self.values() {
+            gsettings(&["reset", &schema, key])?;
+        }
+        Ok(())
+    }
+}
+
+impl Configurator for GnomeKeybinding {
+    fn apply(&self) -> Status {
+        if let Some(status) = self.skip_without_gsettings() {
+            return status;
+        }
+
+        match self.sync() {
+            Ok(false) => {
+                Status::Passed.print_message(&self.describe());
+                Status::Passed
+            }
+            Ok(true) => {
+                Status::Success.print_message(&self.describe());
+                Status::Success
+            }
+            Err(e) => {
+                Status::Failure.print_message(&format!("{}: {}", self.describe(), e));
+                Status::Failure
+            }
+        }
+    }
+
+    fn revert(&self) -> Status {
+        if let Some(status) = self.skip_without_gsettings() {
+            return status;
+        }
+
+        match self.remove() {
+            Ok(()) => Status::Success,
+            Err(e) => {
+                Status::Failure.print_message(&format!("{}: {}", self.describe(), e));
+                Status::Failure
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gvariant_values() {
+        assert_eq!(parse_list("@as []"), Vec::<String>::new());
+        let list = parse_list("['/org/a/custom0/', '/org/a/lsu-term/']");
+        assert_eq!(list, ["/org/a/custom0/", "/org/a/lsu-term/"]);
+        assert_eq!(
+            format_list(&list),
+            "['/org/a/custom0/', '/org/a/lsu-term/']"
+        );
+
+        assert_eq!(quote("it's"), "'it\\'s'");
+        assert_eq!(unquote("'it\\'s'"), "it's");
+        assert_eq!(unquote("\"it's\""), "it's");
+    }
+}
@@ -0,0 +1,95 @@
+mod git_config;
+mod gnome_keybinding;
+mod pacman;
+mod project_workspace;
+mod release_upgrade;
+mod shell_fragment;
+mod ssh_config;
+mod wsl;
+
+use serde::{Deserialize, Serialize};
+
+use crate::distribution::identify_linux_distribution;
+use crate::utils::{SkipReason, Status};
+use crate::{Configurator, DistributionType};
+
+pub use git_config::GitConfig;
+pub use gnome_keybinding::GnomeKeybinding;
+pub use pacman::{Mirrorlist, PacmanHook};
+pub use project_workspace::{ProjectCheckout, ProjectWorkspace};
+pub use release_upgrade::ReleaseUpgrade;
+pub use shell_fragment::ShellFragment;
+pub use ssh_config::SshHost;
+pub use wsl::{WindowsCommand, WindowsSymlink, WslConf};
+
+/// Reports the step as skipped unless running on `required`.
+pub(crate) fn skip_unless_distribution(
+    required: DistributionType,
+    description: &str,
+) -> Option<Status> {
+    let detected = identify_linux_distribution();
+    if required.matches(&detected) {
+        return None;
+    }
+
+    let reason = SkipReason::DistributionMismatch {
+        required: vec![required],
+        detected,
+    };
+    Status::Skipped.print_message(&format!("{} ({})", description, reason));
+    Some(Status::Skipped)
+}
+
+/// Declarative steps that manage state directly instead of through a shell command.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    WslConf(WslConf),
+    WindowsCommand(WindowsCommand),
+    WindowsSymlink(WindowsSymlink),
+    ReleaseUpgrade(ReleaseUpgrade),
+    PacmanHook(PacmanHook),
+    Mirrorlist(Mirrorlist),
+    ShellFragment(ShellFragment),
+    SshHost(SshHost),
+    GitConfig(GitConfig),
+    GnomeKeybinding(GnomeKeybinding),
+    ProjectWorkspace(ProjectWorkspace),
+}
+
+impl Step {
+    fn configurator(&self) -> &dyn Configurator {
+        match self {
+            Step::WslConf(step) => step,
+            Step::WindowsCommand(step) => step,
+            Step::WindowsSymlink(step) => step,
+            Step::ReleaseUpgrade(step) => step,
+            Step::PacmanHook(step) => step,
+            Step::Mirrorlist(step) => step,
+            Step::ShellFragment(step) => step,
+            Step::SshHost(step) => step,
+            Step::GitConfig(step) => step,
+            Step::GnomeKeybinding(step) => step,
+            Step::ProjectWorkspace(step) => step,
+        }
+    }
+}
+
+impl Configurator for Step {
+    fn apply(&self) -> Status {
+        self.configurator().apply()
+    }
+
+    fn revert(&self) -> Status {
+        self.configurator().revert()
+    }
+
+    fn changes(&self) -> Vec<String> {
+        self.configurator().changes()
+    }
+
+    fn env_assignments(&self) -> Vec<(String, String)> {
+        self.configurator().env_assignments()
+    }
+}
@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+use std::{fs, process};
+
+use serde::{Deserialize, Serialize};
+
+use super::skip_unless_distribution;
+use crate::utils::{write_file, Status};
+use crate::{Configurator, DistributionType};
+
+const HOOKS_DIR: &str = "/etc/pacman.d/hooks";
+const MIRRORLIST: &str = "/etc/pacman.d/mirrorlist";
+const MIRRORLIST_BACKUP: &str = "/etc/pacman.d/mirrorlist.lsu-backup";
+
+fn sudo(args: &[&str]) -> Status {
+    match process::Command::new("sudo").args(args).status() {
+        Ok(status) if status.success() => Status::Success,
+        _ => Status::Failure,
+    }
+}
+
+/// Installs an ALPM hook as `/etc/pacman.d/hooks/<name>.hook`.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PacmanHook {
+    name: String,
+    content: String,
+}
+
+impl PacmanHook {
+    fn path(&self) -> PathBuf {
+        PathBuf::from(HOOKS_DIR).join(format!("{}.hook", self.name))
+    }
+}
+
+impl Configurator for PacmanHook {
+    fn apply(&self) -> Status {
+        let path = self.path();
+        let description = format!("pacman hook {}", path.display());
+        if let Some(status) = skip_unless_distribution(DistributionType::ArchLinux, &description) {
+            return status;
+        }
+
+        if fs::read_to_string(&path).is_ok_and(|current| current == self.content) {
+            Status::Passed.print_message(&description);
+            return Status::Passed;
+        }
+
+        Status::Running.print_message(&description);
+        if sudo(&["mkdir", "-p", HOOKS_DIR]) == Status::Failure {
+            return Status::Failure;
+        }
+        match write_file(&path, &self.content, true) {
+            Ok(()) => Status::Success,
+            Err(e) => {
+                Status::Failure.print_message(&format!("{}: {}", description, e));
+                Status::Failure
+            }
+        }
+    }
+
+    fn revert(&self) -> Status {
+        let path = self.path();
+        if !path.exists() {
+            return Status::Passed;
+        }
+        sudo(&["rm", "-f", &path.display().to_string()])
+    }
+}
+
+/// Regenerates the mirrorlist with `reflector`, skipping the run when the current
+/// mirrorlist was already generated with the same options.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Mirrorlist {
+    countries: Option<Vec<String>>,
+    protocols: Option<Vec<String>>,
+    latest: Option<u32>,
+    sort: Option<String>,
+}
+
+impl Mirrorlist {
+    fn reflector_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(countries) = self.countries.as_ref().filter(|c| !c.is_empty()) {
+            args.extend(["--country".to_string(), countries.join(",")]);
+        }
+        if let Some(protocols) = self.protocols.as_ref().filter(|p| !p.is_empty()) {
+            args.extend(["--protocol".to_string(), protocols.join(",")]);
+        }
+        if let Some(latest) = self.latest {
+            args.extend(["--latest".to_string(), latest.to_string()]);
+        }
+        if let Some(sort) = &self.sort {
+            args.extend(["--sort".to_string(), sort.clone()]);
+        }
+        args
+    }
+
+    /// reflector records its arguments in a `# With:` header line of the generated file.
+    fn is_current(&self, mirrorlist: &str) -> bool {
+        let expected = self.reflector_args().join(" ");
+        mirrorlist
+            .lines()
+            .filter_map(|line| line.strip_prefix("# With:"))
+            .any(|args| {
+                let args = args.trim().trim_start_matches("reflector").trim();
+                args.replace(&format!(" --save {}", MIRRORLIST), "").trim() == expected
+            })
+    }
+}
+
+impl Configurator for Mirrorlist {
+    fn apply(&self) -> Status {
+        let description = format!("reflector {}", self.reflector_args().join(" "));
+        if let Some(status) = skip_unless_distribution(DistributionType::ArchLinux, &description) {
+            return status;
+        }
+
+        if fs::read_to_string(MIRRORLIST).is_ok_and(|current| self.is_current(&current)) {
+            Status::Passed.print_message(&description);
+            return Status::Passed;
+        }
+
+        Status::Running.print_message(&description);
+        if !PathBuf::from(MIRRORLIST_BACKUP).exists()
+            && sudo(&["cp", MIRRORLIST, MIRRORLIST_BACKUP]) == Status::Failure
+        {
+            return Status::Failure;
+        }
+
+        let mut args: Vec<String> = vec!["reflector".to_string()];
+        args.extend(self.reflector_args());
+        args.extend(["--save".to_string(), MIRRORLIST.to_string()]);
+        sudo(&args.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+
+    /// Restores the mirrorlist saved before the first reflector run.
+    fn revert(&self) -> Status {
+        if !PathBuf::from(MIRRORLIST_BACKUP).exists() {
+            return Status::Passed;
+        }
+        sudo(&["mv", MIRRORLIST_BACKUP, MIRRORLIST])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirrorlist_is_current() {
+        let mirrorlist = Mirrorlist {
+            countries: Some(vec!["Germany".to_string(), "France".to_string()]),
+            protocols: Some(vec!["https".to_string()]),
+            latest: Some(20),
+            sort: Some("rate".to_string()),
+        };
+        let header = "################################################################################\n\
+            # With:       reflector --country Germany,France --protocol https --latest 20 --sort rate --save /etc/pacman.d/mirrorlist\n";
+        assert!(mirrorlist.is_current(header));
+        assert!(!mirrorlist.is_current("# With:       reflector --latest 5\n"));
+        assert!(!mirrorlist.is_current("Server = https://example.org/$repo/os/$arch\n"));
+    }
+}
@@ -0,0 +1,309 @@
+use std::cell::RefCell;
+use std::path::{Component, Path, PathBuf};
+use std::{fs, process};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{expand_path, Status};
+use crate::Configurator;
+
+/// A repository checked out into a `ProjectWorkspace`.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProjectCheckout {
+    url: String,
+    /// Directory under the workspace root; defaults to the repository name.
+    path: Option<String>,
+    /// Branch or tag to check out instead of the default branch.
+    branch: Option<String>,
+    /// Run inside the fresh checkout, e.g. `direnv allow` or `mise install`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bootstrap: Vec<String>,
+}
+
+impl ProjectCheckout {
+    fn dir_name(&self) -> &str {
+        self.path.as_deref().unwrap_or_else(|| {
+            let name = self.url.trim_end_matches('/').trim_end_matches(".git");
+            name.rsplit(['/', ':']).next().unwrap_or(name)
+        })
+    }
+
+    /// Checkouts must stay under the workspace root, so absolute paths and `..` are refused.
+    fn checked_dir_name(&self) -> Result<&str, String> {
+        let name = self.dir_name();
+        let escapes = Path::new(name)
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+        if name.is_empty() || escapes {
+            return Err(format!(
+                "{}: path {:?} must be relative to the workspace root without `..`",
+                self.url, name
+            ));
+        }
+        Ok(name)
+    }
+}
+
+/// Left in `.git` from the clone until every bootstrap script has passed, so a failed
+/// bootstrap is retried on the next run instead of the checkout counting as done.
+const BOOTSTRAP_PENDING: &str = "lsu-bootstrap-pending";
+
+/// Clones project repositories into a workspace root and bootstraps each new checkout,
+/// so a fresh machine gets working projects and not just tools. Existing checkouts
+/// are left alone and not bootstrapped again; new ones are recorded as changes with
+/// the commit they are at. A checkout whose bootstrap failed is bootstrapped again.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProjectWorkspace {
+    /// e.g. `~/src`.
+    root: String,
+    repositories: Vec<ProjectCheckout>,
+    #[serde(skip)]
+    changes: RefCell<Vec<String>>,
+}
+
+impl ProjectWorkspace {
+    fn checkout_dir(&self, repository: &ProjectCheckout) -> Result<PathBuf, String> {
+        Ok(expand_path(&self.root).join(repository.checked_dir_name()?))
+    }
+
+    fn run(mut command: process::Command, what: &str) -> bool {
+        match command.output() {
+            Ok(output) if output.status.success() => true,
+            Ok(output) => {
+                Status::Failure.print_message(&format!(
+                    "{}: {}",
+                    what,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+                false
+            }
+            Err(e) => {
+                Status::Failure.print_message(&format!("{}: {}", what, e));
+                false
+            }
+        }
+    }
+
+    /// The short commit checked out in `dir`, e.g. `3f2c1ab`.
+    fn version(dir: &Path) -> String {
+        process::Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    fn clone(repository: &ProjectCheckout, dir: &Path) -> bool {
+        let mut clone = process::Command::new("git");
+        clone.arg("clone");
+        if let Some(branch) = &repository.branch {
+            clone.args(["--branch", branch]);
+        }
+        clone.arg("--").arg(&repository.url).arg(dir);
+        if !Self::run(clone, &format!("git clone {}", repository.url)) {
+            return false;
+        }
+        if repository.bootstrap.is_empty() {
+            return true;
+        }
+        if let Err(e) = fs::write(dir.join(".git").join(BOOTSTRAP_PENDING), "") {
+            Status::Failure.print_message(&format!("{}: {}", dir.display(), e));
+            return false;
+        }
+        Self::bootstrap(repository, dir)
+    }
+
+    fn bootstrap(repository: &ProjectCheckout, dir: &Path) -> bool {
+        let passed = repository.bootstrap.iter().all(|script| {
+            let mut command = process::Command::new("sh");
+            command.arg("-c").arg(script).current_dir(dir);
+            Self::run(command, &format!("{}: {}", repository.dir_name(), script))
+        });
+        passed && Self::finish_bootstrap(dir)
+    }
+
+    fn finish_bootstrap(dir: &Path) -> bool {
+        match fs::remove_file(dir.join(".git").join(BOOTSTRAP_PENDING)) {
+            Ok(()) => true,
+            Err(e) => {
+                Status::Failure.print_message(&format!("{}: {}", dir.display(), e));
+                false
+            }
+        }
+    }
+}
+
+impl Configurator for ProjectWorkspace {
+    fn apply(&self) -> Status {
+        self.changes.borrow_mut().clear();
+        if let Err(e) = fs::create_dir_all(expand_path(&self.root)) {
+            Status::Failure.print_message(&format!("{}: {}", self.root, e));
+            return Status::Failure;
+        }
+
+        let mut status = Status::Passed;
+        for repository in &self.repositories {
+            let dir = match self.checkout_dir(repository) {
+                Ok(dir) => dir,
+                Err(e) => {
+                    Status::Failure.print_message(&e);
+                    status = Status::Failure;
+                    continue;
+                }
+            };
+            let change = if dir.join(".git").join(BOOTSTRAP_PENDING).exists() {
+                if !Self::bootstrap(repository, &dir) {
+                    status = Status::Failure;
+                    continue;
+                }
+                format!("bootstrapped {} @ {}", dir.display(), Self::version(&dir))
+            } else if dir.join(".git").exists() {
+                Status::Passed.print_message(&format!(
+                    "{} @ {}",
+                    dir.display(),
+                    Self::version(&dir)
+                ));
+                continue;
+            } else if dir.exists() {
+                Status::Failure.print_message(&format!(
+                    "{} exists but is not a git checkout",
+                    dir.display()
+                ));
+                status = Status::Failure;
+                continue;
+            } else if Self::clone(repository, &dir) {
+                format!(
+                    "cloned {} into {} @ {}",
+                    repository.url,
+                    dir.display(),
+                    Self::version(&dir)
+                )
+            } else {
+                status = Status::Failure;
+                continue;
+            };
+            Status::Success.print_message(&change);
+            self.changes.borrow_mut().push(change);
+            if status == Status::Passed {
+                status = Status::Success;
+            }
+        }
+        status
+    }
+
+    /// Checkouts may hold unpushed work, so they are never removed.
+    fn revert(&self) -> Status {
+        Status::Skipped.print_message(&format!("Keeping the checkouts under {}", self.root));
+        Status::Skipped
+    }
+
+    fn changes(&self) -> Vec<String> {
+        self.changes.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = process::Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=lsu", "-c", "user.email=lsu@example.org"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    #[test]
+    fn test_clones_and_bootstraps_once() {
+        let base = std::env::temp_dir().join(format!("lsu-projects-{}", std::process::id()));
+        let upstream = base.join("upstream/dotfiles.git");
+        fs::create_dir_all(&upstream).unwrap();
+        git(&upstream, &["init", "-q"]);
+        git(
+            &upstream,
+            &["commit", "-q", "--allow-empty", "-m", "initial"],
+        );
+
+        let workspace: ProjectWorkspace = serde_json::from_value(json!({
+            "root": base.join("src"),
+            "repositories": [
+                { "url": upstream, "bootstrap": ["touch bootstrapped"] }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(workspace.apply(), Status::Success);
+        let checkout = base.join("src/dotfiles");
+        assert!(checkout.join("bootstrapped").exists());
+        let changes = workspace.changes();
+        assert!(changes[0].ends_with(&format!("@ {}", ProjectWorkspace::version(&checkout))));
+
+        fs::remove_file(checkout.join("bootstrapped")).unwrap();
+        assert_eq!(workspace.apply(), Status::Passed);
+        assert!(!checkout.join("bootstrapped").exists());
+
+        fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_retries_a_failed_bootstrap() {
+        let base = std::env::temp_dir().join(format!("lsu-bootstrap-{}", std::process::id()));
+        let upstream = base.join("upstream/tools.git");
+        fs::create_dir_all(&upstream).unwrap();
+        git(&upstream, &["init", "-q"]);
+        git(
+            &upstream,
+            &["commit", "-q", "--allow-empty", "-m", "initial"],
+        );
+
+        let workspace: ProjectWorkspace = serde_json::from_value(json!({
+            "root": base.join("src"),
+            "repositories": [
+                { "url": upstream, "bootstrap": ["test -e ../ready && touch bootstrapped"] }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(workspace.apply(), Status::Failure);
+        let checkout = base.join("src/tools");
+        assert!(checkout.join(".git").exists());
+
+        fs::write(base.join("src/ready"), "").unwrap();
+        assert_eq!(workspace.apply(), Status::Success);
+        assert!(checkout.join("bootstrapped").exists());
+        assert!(workspace.changes()[0].starts_with("bootstrapped"));
+        assert_eq!(workspace.apply(), Status::Passed);
+
+        fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_paths_outside_the_root() {
+        for path in ["../elsewhere", "/tmp/elsewhere", "nested/../../up", ""] {
+            let checkout: ProjectCheckout = serde_json::from_value(json!({
+                "url": "https://example.org/dotfiles.git",
+                "path": path
+            }))
+            .unwrap();
+            assert!(checkout.checked_dir_name().is_err(), "{:?}", path);
+        }
+        let checkout: ProjectCheckout = serde_json::from_value(json!({
+            "url": "https://example.org/dotfiles.git",
+            "path": "work/dotfiles"
+        }))
+        .unwrap();
+        assert_eq!(checkout.checked_dir_name(), Ok("work/dotfiles"));
+    }
+}
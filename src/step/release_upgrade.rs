@@ -0,0 +1,105 @@
+use std::{io, process};
+
+use serde::{Deserialize, Serialize};
+
+use super::skip_unless_distribution;
+use crate::distribution::{release_upgrade_state, ReleaseUpgradeState};
+use crate::utils::{reporter, Status};
+use crate::{Configurator, DistributionType};
+
+const DESCRIPTION: &str = "Ubuntu release upgrade";
+
+/// Runs `do-release-upgrade` when a new Ubuntu release is pending, after explicit confirmation.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ReleaseUpgrade {
+    /// Ask before upgrading; defaults to true since the upgrade cannot be undone.
+    confirm: Option<bool>,
+}
+
+impl ReleaseUpgrade {
+    fn confirmed(&self, notice: &str) -> bool {
+        if !self.confirm.unwrap_or(true) {
+            return true;
+        }
+
+        if !notice.is_empty() {
+            reporter().line(notice);
+        }
+        reporter().prompt("Upgrade to the new Ubuntu release now? (y/N): ");
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer).is_ok() && answer.trim().eq_ignore_ascii_case("y")
+    }
+
+    fn print_resume_plan() {
+        reporter().line("Release upgrade finished. To resume provisioning:");
+        reporter().line("  1. Reboot into the new release.");
+        reporter().line("  2. Run the setup again; entries whose checks pass are not repeated.");
+    }
+}
+
+impl Configurator for ReleaseUpgrade {
+    fn apply(&self) -> Status {
+        if let Some(status) = skip_unless_distribution(DistributionType::Ubuntu, DESCRIPTION) {
+            return status;
+        }
+
+        let notice = match release_upgrade_state() {
+            ReleaseUpgradeState::None => {
+                Status::Passed.print_message(&format!("{} (no new release)", DESCRIPTION));
+                return Status::Passed;
+            }
+            ReleaseUpgradeState::InProgress => {
+                Status::Warning.print_message(&format!("{} already in progress", DESCRIPTION));
+                return Status::Failure;
+            }
+            ReleaseUpgradeState::Pending(notice) => notice,
+        };
+
+        if !self.confirmed(&notice) {
+            Status::Skipped.print_message(&format!("{} (declined)", DESCRIPTION));
+            return Status::Skipped;
+        }
+
+        Status::Running.print_message(DESCRIPTION);
+        match process::Command::new("sudo")
+            .arg("do-release-upgrade")
+            .status()
+        {
+            Ok(status) if status.success() => {
+                Self::print_resume_plan();
+                Status::Success
+            }
+            Ok(_) => Status::Failure,
+            Err(e) => {
+                Status::Failure.print_message(&format!("{}: {}", DESCRIPTION, e));
+                Status::Failure
+            }
+        }
+    }
+
+    fn revert(&self) -> Status {
+        Status::Skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unconfirmed_upgrade_does_not_prompt() {
+        let step: ReleaseUpgrade = serde_json::from_value(serde_json::json!({
+            "confirm": false
+        }))
+        .unwrap();
+        assert!(step.confirmed("New release '24.04 LTS' available."));
+    }
+
+    #[test]
+    fn test_upgrade_is_skipped_off_ubuntu() {
+        let _fedora = crate::distribution::override_distribution(DistributionType::Fedora);
+        let step = ReleaseUpgrade { confirm: None };
+        assert_eq!(step.apply(), Status::Skipped);
+    }
+}
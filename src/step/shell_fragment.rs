@@ -0,0 +1,163 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{
+    expand_path, managed_block, remove_block, shell_assignments, upsert_block, write_file, Status,
+};
+use crate::Configurator;
+
+const FRAGMENT_DIR: &str = "${XDG_CONFIG_HOME}/shell/rc.d";
+const DEFAULT_RC_FILES: [&str; 2] = ["~/.bashrc", "~/.zshrc"];
+const BEGIN_MARKER: &str = "# >>> linux_setup_ur rc.d >>>";
+const END_MARKER: &str = "# <<< linux_setup_ur rc.d <<<";
+const LOADER: &str = r#"for lsu_fragment in "${XDG_CONFIG_HOME:-$HOME/.config}"/shell/rc.d/*.sh; do
+    [ -r "$lsu_fragment" ] && . "$lsu_fragment"
+done
+unset lsu_fragment"#;
+
+fn ensure_loader(content: &str) -> Option<String> {
+    let block = managed_block(BEGIN_MARKER, LOADER, END_MARKER);
+    upsert_block(content, BEGIN_MARKER, END_MARKER, &block, false)
+}
+
+fn remove_loader(content: &str) -> Option<String> {
+    remove_block(content, BEGIN_MARKER, END_MARKER)
+}
+
+/// Installs `content` as `~/.config/shell/rc.d/<name>.sh` and makes sure the shell rc
+/// files source that directory from a managed block, instead of appending lines with echo.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ShellFragment {
+    name: String,
+    content: String,
+    /// rc files that should load the fragments; defaults to whichever of
+    /// `~/.bashrc` and `~/.zshrc` exist.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    rc_files: Vec<String>,
+}
+
+impl ShellFragment {
+    fn fragment_dir() -> PathBuf {
+        expand_path(FRAGMENT_DIR)
+    }
+
+    fn path(&self) -> PathBuf {
+        Self::fragment_dir().join(format!("{}.sh", self.name))
+    }
+
+    fn rc_files(&self) -> Vec<PathBuf> {
+        if !self.rc_files.is_empty() {
+            return self.rc_files.iter().map(|file| expand_path(file)).collect();
+        }
+        DEFAULT_RC_FILES
+            .iter()
+            .map(|file| expand_path(file))
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    fn update_rc(path: &Path, update: fn(&str) -> Option<String>) -> Result<bool, String> {
+        let current = fs::read_to_string(path).unwrap_or_default();
+        match update(&current) {
+            Some(updated) => write_file(path, &updated, false)
+                .map(|_| true)
+                .map_err(|e| format!("{}: {}", path.display(), e)),
+            None => Ok(false),
+        }
+    }
+
+    fn has_fragments() -> bool {
+        fs::read_dir(Self::fragment_dir()).is_ok_and(|entries| {
+            entries
+                .flatten()
+                .any(|entry| entry.path().extension().is_some_and(|ext| ext == "sh"))
+        })
+    }
+}
+
+impl Configurator for ShellFragment {
+    fn apply(&self) -> Status {
+        let path = self.path();
+        let description = format!("shell fragment {}", path.display());
+        let mut changed = false;
+
+        if !fs::read_to_string(&path).is_ok_and(|current| current == self.content) {
+            if let Err(e) = write_file(&path, &self.content, false) {
+                Status::Failure.print_message(&format!("{}: {}", description, e));
+                return Status::Failure;
+            }
+            changed = true;
+        }
+
+        for rc in self.rc_files() {
+            match Self::update_rc(&rc, ensure_loader) {
+                Ok(updated) => changed |= updated,
+                Err(e) => {
+                    Status::Failure.print_message(&e);
+                    return Status::Failure;
+                }
+            }
+        }
+
+        if changed {
+            Status::Success.print_message(&description);
+            Status::Success
+        } else {
+            Status::Passed.print_message(&description);
+            Status::Passed
+        }
+    }
+
+    /// Removes the fragment, and the loader block once no fragments are left.
+    fn revert(&self) -> Status {
+        let path = self.path();
+        if path.exists() {
+            if let Err(e) = fs::remove_file(&path) {
+                Status::Failure.print_message(&format!("{}: {}", path.display(), e));
+                return Status::Failure;
+            }
+        }
+        if Self::has_fragments() {
+            return Status::Success;
+        }
+
+        for rc in self.rc_files().iter().filter(|rc| rc.exists()) {
+            if let Err(e) = Self::update_rc(rc, remove_loader) {
+                Status::Failure.print_message(&e);
+                return Status::Failure;
+            }
+        }
+        Status::Success
+    }
+
+    fn env_assignments(&self) -> Vec<(String, String)> {
+        shell_assignments(&self.content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loader_block_round_trip() {
+        let original = "alias ll='ls -l'";
+        let with_loader = ensure_loader(original).unwrap();
+        assert!(with_loader.starts_with("alias ll='ls -l'\n# >>> linux_setup_ur rc.d >>>\n"));
+        assert!(with_loader.ends_with("# <<< linux_setup_ur rc.d <<<\n"));
+        assert_eq!(ensure_loader(&with_loader), None);
+
+        let edited = with_loader.replace("unset lsu_fragment", "");
+        assert_eq!(ensure_loader(&edited).unwrap(), with_loader);
+
+        let appended = format!("{}export EDITOR=nvim\n", with_loader);
+        assert_eq!(
+            remove_loader(&appended).unwrap(),
+            "alias ll='ls -l'\nexport EDITOR=nvim\n"
+        );
+        assert_eq!(remove_loader(original), None);
+    }
+}
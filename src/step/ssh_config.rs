@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{expand_path, managed_block, remove_block, upsert_block, write_file, Status};
+use crate::Configurator;
+
+const DEFAULT_CONFIG: &str = "~/.ssh/config";
+
+/// Manages one `Host` block in `~/.ssh/config` between marker comments.
+/// New blocks go at the top of the file: ssh uses the first value it finds for each
+/// option, so a trailing `Host *` would otherwise override them.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SshHost {
+    host: String,
+    /// Options such as `HostName`, `User`, `ProxyJump` or `IdentityFile`.
+    options: BTreeMap<String, String>,
+    path: Option<String>,
+}
+
+impl SshHost {
+    fn config_path(&self) -> PathBuf {
+        expand_path(self.path.as_deref().unwrap_or(DEFAULT_CONFIG))
+    }
+
+    fn markers(&self) -> (String, String) {
+        (
+            format!("# >>> linux_setup_ur host {} >>>", self.host),
+            format!("# <<< linux_setup_ur host {} <<<", self.host),
+        )
+    }
+
+    fn block(&self) -> String {
+        let (begin, end) = self.markers();
+        let mut body = format!("Host {}", self.host);
+        for (key, value) in &self.options {
+            body.push_str(&format!("\n    {} {}", key, value));
+        }
+        managed_block(&begin, &body, &end)
+    }
+
+    fn updated(&self, content: &str) -> Option<String> {
+        let (begin, end) = self.markers();
+        upsert_block(content, &begin, &end, &self.block(), true)
+    }
+
+    fn removed(&self, content: &str) -> Option<String> {
+        let (begin, end) = self.markers();
+        // Drop the blank line `upsert_block` put after the block as well.
+        remove_block(content, &begin, &end)
+            .map(|rest| rest.strip_prefix('\n').unwrap_or(&rest).to_string())
+    }
+
+    /// A line break in any field would end the `Host` block early and let the rest of
+    /// the value inject options of its own.
+    fn check_fields(&self) -> Result<(), String> {
+        let mut fields = [&self.host]
+            .into_iter()
+            .chain(self.options.keys())
+            .chain(self.options.values())
+            .chain(&self.path);
+        match fields.find(|field| field.contains(['\n', '\r'])) {
+            Some(field) => Err(format!(
+                "ssh Host {:?}: {:?} must not contain line breaks",
+                self.host, field
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// ssh refuses configs that other users can write. A `~/.ssh` created here is made
+    /// private; an existing directory keeps the permissions it has.
+    fn restrict_permissions(path: &Path, created_dir: Option<&Path>) -> std::io::Result<()> {
+        if let Some(dir) = created_dir {
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+    }
+
+    fn write(&self, path: &Path, content: &str, description: &str) -> Status {
+        let created_dir = path.parent().filter(|dir| !dir.exists());
+        let written = write_file(path, content, false)
+            .and_then(|_| Self::restrict_permissions(path, created_dir));
+        match written {
+            Ok(()) => Status::Success,
+            Err(e) => {
+                Status::Failure.print_message(&format!("{}: {}", description, e));
+                Status::Failure
+            }
+        }
+    }
+}
+
+impl Configurator for SshHost {
+    fn apply(&self) -> Status {
+        if let Err(e) = self.check_fields() {
+            Status::Failure.print_message(&e);
+            return Status::Failure;
+        }
+        let path = self.config_path();
+        let description = format!("ssh Host {} in {}", self.host, path.display());
+        let content = fs::read_to_string(&path).unwrap_or_default();
+
+        match self.updated(&content) {
+            None => {
+                Status::Passed.print_message(&description);
+                Status::Passed
+            }
+            Some(updated) => {
+                Status::Running.print_message(&description);
+                self.write(&path, &updated, &description)
+            }
+        }
+    }
+
+    fn revert(&self) -> Status {
+        if let Err(e) = self.check_fields() {
+            Status::Failure.print_message(&e);
+            return Status::Failure;
+        }
+        let path = self.config_path();
+        let description = format!("ssh Host {} in {}", self.host, path.display());
+        let content = fs::read_to_string(&path).unwrap_or_default();
+
+        match self.removed(&content) {
+            None => Status::Passed,
+            Some(updated) => self.write(&path, &updated, &description),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_host_block() {
+        let host = SshHost {
+            host: "forge".to_string(),
+            options: BTreeMap::from([
+                ("User".to_string(), "git".to_string()),
+                ("HostName".to_string(), "git.example.org".to_string()),
+            ]),
+            path: None,
+        };
+        let existing = "Host *\n    ServerAliveInterval 60\n";
+
+        let updated = host.updated(existing).unwrap();
+        assert_eq!(
+            updated,
+            "# >>> linux_setup_ur host forge >>>\nHost forge\n    HostName git.example.org\n    User git\n# <<< linux_setup_ur host forge <<<\n\nHost *\n    ServerAliveInterval 60\n"
+        );
+        assert_eq!(host.updated(&updated), None);
+
+        let stale = updated.replace("User git", "User root");
+        assert_eq!(host.updated(&stale).unwrap(), updated);
+        assert_eq!(host.removed(&updated).unwrap(), existing);
+    }
+
+    #[test]
+    fn test_line_breaks_are_rejected() {
+        let host = |name: &str, value: &str| SshHost {
+            host: name.to_string(),
+            options: BTreeMap::from([("User".to_string(), value.to_string())]),
+            path: None,
+        };
+        assert!(host("forge", "git").check_fields().is_ok());
+        assert!(host("forge", "git\nProxyCommand sh")
+            .check_fields()
+            .is_err());
+        assert!(host("forge\rHost *", "git").check_fields().is_err());
+    }
+
+    #[test]
+    fn test_only_a_created_directory_is_made_private() {
+        let base = std::env::temp_dir().join(format!("lsu-ssh-{}", std::process::id()));
+        let shared = base.join("shared");
+        fs::create_dir_all(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o755)).unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let host = |path: &Path| SshHost {
+            host: "forge".to_string(),
+            options: BTreeMap::new(),
+            path: Some(path.display().to_string()),
+        };
+        assert_eq!(host(&shared.join("config")).apply(), Status::Success);
+        assert_eq!(mode(&shared), 0o755);
+        assert_eq!(mode(&shared.join("config")), 0o600);
+
+        let created = base.join("created");
+        assert_eq!(host(&created.join("config")).apply(), Status::Success);
+        assert_eq!(mode(&created), 0o700);
+
+        fs::remove_dir_all(base).unwrap();
+    }
+}
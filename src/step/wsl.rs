@@ -0,0 +1,273 @@
+use std::path::{Path, PathBuf};
+use std::{fs, os::unix, process};
+
+use serde::{Deserialize, Serialize};
+
+use crate::distribution::{is_wsl, windows_to_wsl_path};
+use crate::utils::{expand_path, write_file, SkipReason, Status};
+use crate::Configurator;
+
+const WSL_CONF: &str = "/etc/wsl.conf";
+
+fn skip_outside_wsl(description: &str) -> Option<Status> {
+    if is_wsl() {
+        return None;
+    }
+
+    let reason = SkipReason::Condition("running under WSL".to_string());
+    Status::Skipped.print_message(&format!("{} ({})", description, reason));
+    Some(Status::Skipped)
+}
+
+/// Sets `value` for `key` inside `[section]`, returning `None` when the content is already up to date.
+fn set_ini_value(content: &str, section: &str, key: &str, value: &str) -> Option<String> {
+    let header = format!("[{}]", section);
+    let entry = format!("{} = {}", key, value);
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    let section_start = lines.iter().position(|line| line.trim() == header);
+    let Some(start) = section_start else {
+        if !lines.is_empty() && !lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push(header);
+        lines.push(entry);
+        return Some(lines.join("\n") + "\n");
+    };
+
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+
+    let existing = lines[start + 1..end].iter().position(|line| {
+        line.split_once('=')
+            .is_some_and(|(name, _)| name.trim() == key)
+    });
+
+    match existing {
+        Some(offset) => {
+            let line = &lines[start + 1 + offset];
+            let current = line.split_once('=').map(|(_, v)| v.trim());
+            if current == Some(value) {
+                return None;
+            }
+            lines[start + 1 + offset] = entry;
+        }
+        None => lines.insert(end, entry),
+    }
+
+    Some(lines.join("\n") + "\n")
+}
+
+fn remove_ini_value(content: &str, section: &str, key: &str) -> Option<String> {
+    let header = format!("[{}]", section);
+    let mut in_section = false;
+    let mut removed = false;
+    let lines: Vec<&str> = content
+        .lines()
+        .filter(|line| {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                in_section = trimmed == header;
+            } else if in_section
+                && line
+                    .split_once('=')
+                    .is_some_and(|(name, _)| name.trim() == key)
+            {
+                removed = true;
+                return false;
+            }
+            true
+        })
+        .collect();
+
+    removed.then(|| lines.join("\n") + "\n")
+}
+
+/// Manages a single `key = value` setting in `/etc/wsl.conf`.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WslConf {
+    section: String,
+    key: String,
+    value: String,
+}
+
+impl WslConf {
+    fn describe(&self) -> String {
+        format!("wsl.conf [{}] {} = {}", self.section, self.key, self.value)
+    }
+
+    fn write(&self, content: String) -> Status {
+        match write_file(Path::new(WSL_CONF), &content, true) {
+            Ok(()) => Status::Success,
+            Err(e) => {
+                Status::Failure.print_message(&format!("{}: {}", self.describe(), e));
+                Status::Failure
+            }
+        }
+    }
+}
+
+impl Configurator for WslConf {
+    fn apply(&self) -> Status {
+        if let Some(status) = skip_outside_wsl(&self.describe()) {
+            return status;
+        }
+
+        let content = fs::read_to_string(WSL_CONF).unwrap_or_default();
+        match set_ini_value(&content, &self.section, &self.key, &self.value) {
+            None => {
+                Status::Passed.print_message(&self.describe());
+                Status::Passed
+            }
+            Some(updated) => {
+                Status::Running.print_message(&self.describe());
+                self.write(updated)
+            }
+        }
+    }
+
+    fn revert(&self) -> Status {
+        if let Some(status) = skip_outside_wsl(&self.describe()) {
+            return status;
+        }
+
+        let content = fs::read_to_string(WSL_CONF).unwrap_or_default();
+        match remove_ini_value(&content, &self.section, &self.key) {
+            None => Status::Passed,
+            Some(updated) => self.write(updated),
+        }
+    }
+}
+
+/// Runs a PowerShell command on the Windows host through WSL interop,
+/// e.g. to install fonts or update Windows Terminal settings.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WindowsCommand {
+    command: String,
+}
+
+impl Configurator for WindowsCommand {
+    fn apply(&self) -> Status {
+        let description = format!("powershell.exe: {}", self.command);
+        if let Some(status) = skip_outside_wsl(&description) {
+            return status;
+        }
+
+        Status::Running.print_message(&description);
+        let output = process::Command::new("powershell.exe")
+            .args(["-NoProfile", "-NonInteractive", "-Command"])
+            .arg(&self.command)
+            .output();
+
+        match output {
+            Ok(output) if output.status.success() => Status::Success,
+            Ok(output) => {
+                Status::Failure.print_message(&String::from_utf8_lossy(&output.stderr));
+                Status::Failure
+            }
+            Err(e) => {
+                Status::Failure.print_message(&format!("{}: {}", description, e));
+                Status::Failure
+            }
+        }
+    }
+
+    fn revert(&self) -> Status {
+        Status::Skipped
+    }
+}
+
+/// Symlinks a Linux path to a Windows-side location; `target` may be `C:\...` or `/mnt/c/...`.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WindowsSymlink {
+    target: String,
+    link: String,
+}
+
+impl WindowsSymlink {
+    fn paths(&self) -> (PathBuf, PathBuf) {
+        (
+            PathBuf::from(windows_to_wsl_path(&self.target)),
+            expand_path(&self.link),
+        )
+    }
+}
+
+impl Configurator for WindowsSymlink {
+    fn apply(&self) -> Status {
+        let (target, link) = self.paths();
+        let description = format!("{} -> {}", link.display(), target.display());
+        if let Some(status) = skip_outside_wsl(&description) {
+            return status;
+        }
+
+        if fs::read_link(&link).is_ok_and(|current| current == target) {
+            Status::Passed.print_message(&description);
+            return Status::Passed;
+        }
+
+        Status::Running.print_message(&description);
+        let result = link
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| unix::fs::symlink(&target, &link));
+
+        match result {
+            Ok(()) => Status::Success,
+            Err(e) => {
+                Status::Failure.print_message(&format!("{}: {}", description, e));
+                Status::Failure
+            }
+        }
+    }
+
+    fn revert(&self) -> Status {
+        let (target, link) = self.paths();
+        if !fs::read_link(&link).is_ok_and(|current| current == target) {
+            return Status::Passed;
+        }
+
+        match fs::remove_file(&link) {
+            Ok(()) => Status::Success,
+            Err(_) => Status::Failure,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_ini_value() {
+        let content = "[boot]\nsystemd = false\n\n[interop]\nenabled = true\n";
+        assert_eq!(
+            set_ini_value(content, "boot", "systemd", "true").unwrap(),
+            "[boot]\nsystemd = true\n\n[interop]\nenabled = true\n"
+        );
+        assert_eq!(set_ini_value(content, "interop", "enabled", "true"), None);
+        assert_eq!(
+            set_ini_value(content, "interop", "appendWindowsPath", "false").unwrap(),
+            "[boot]\nsystemd = false\n\n[interop]\nenabled = true\nappendWindowsPath = false\n"
+        );
+        assert_eq!(
+            set_ini_value("", "automount", "root", "/").unwrap(),
+            "[automount]\nroot = /\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_ini_value() {
+        let content = "[boot]\nsystemd = true\n[user]\ndefault = me\n";
+        assert_eq!(
+            remove_ini_value(content, "boot", "systemd").unwrap(),
+            "[boot]\n[user]\ndefault = me\n"
+        );
+        assert_eq!(remove_ini_value(content, "boot", "missing"), None);
+    }
+}
@@ -0,0 +1,17 @@
+use serde_json::{Map, Value};
+
+/// A kind of `check` selected by its `type`, e.g. `{ "type": "service_enabled", "service": "sshd" }`.
+/// The built-in providers live in `check`; downstream crates add their own with
+/// `check::register_check_provider` and they work wherever checks do (runs, plans, audits).
+pub trait CheckProvider: Send + Sync {
+    /// The `type` this provider handles.
+    fn name(&self) -> &str;
+
+    /// Rejects parameters the provider cannot use; called when the manifest is validated.
+    fn validate(&self, _params: &Map<String, Value>) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Whether the check holds, i.e. the command it guards is already satisfied.
+    fn is_satisfied(&self, params: &Map<String, Value>) -> bool;
+}
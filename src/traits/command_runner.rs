@@ -1,8 +1,48 @@
-use std::process;
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::time::Duration;
+use std::{io, process, thread};
 
-use crate::utils::Status;
+use crate::utils::{
+    format_duration, output_interactive, output_streaming, output_timeout, reporter,
+    run_foreground, terminal_shared, Status,
+};
+#[cfg(feature = "async")]
+use crate::utils::{output_async, Cancellation, LineSink};
 
 use super::ErrorHandler;
+use crate::command::RetryPolicy;
+
+/// `Some` when `before_run` decided that the command does not run.
+fn not_running(status: Status) -> Option<Status> {
+    match status {
+        Status::Passed | Status::Failure | Status::Aborted | Status::Skipped => Some(status),
+        _ => None,
+    }
+}
+
+/// Announces retry number `attempt` and returns how long to wait before it.
+fn announce_retry(policy: &RetryPolicy, attempt: u32) -> Duration {
+    let delay = policy.delay(attempt);
+    Status::Warning.print_message(&format!(
+        "Retrying ({}/{}) in {}",
+        attempt,
+        policy.retries,
+        format_duration(delay)
+    ));
+    delay
+}
+
+/// The status `execute` reports for what `after_run` returned.
+fn finished(status: Status) -> Status {
+    match status {
+        Status::Aborted => Status::Aborted,
+        Status::Passed => Status::Passed,
+        Status::Failure => Status::Failure,
+        Status::Skipped => Status::Skipped,
+        _ => Status::Success,
+    }
+}
 
 pub trait CommandRunner: ErrorHandler {
     fn setup_command(&self) -> process::Command;
@@ -11,44 +51,129 @@ pub trait CommandRunner: ErrorHandler {
         false
     }
 
+    /// Overrides the exit-code based status after a captured run; `None` keeps the default.
+    fn evaluate(&self, _output: &process::Output) -> Option<Status> {
+        None
+    }
+
+    /// How long the command may run before it is killed; `None` waits forever.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn timed_out(&self) -> Status {
+        let limit = self.timeout().map(format_duration).unwrap_or_default();
+        Self::handle_command_error(&format!("timed out after {}", limit));
+        Status::Failure.print_message(&format!("Timed out after {}", limit));
+        Status::Failure
+    }
+
+    /// Whether the exit status counts as success; some tools exit non-zero when there
+    /// was nothing to do.
+    fn is_success(&self, status: &process::ExitStatus) -> bool {
+        status.success()
+    }
+
+    /// When set, captured output is also printed line by line as it arrives, after this prefix.
+    fn stream_prefix(&self) -> Option<String> {
+        None
+    }
+
+    /// Receives the captured output of a `run_spawn` command once it finished, so it
+    /// can be shown in one piece instead of interleaved with other commands. Output is
+    /// only captured while entries run in parallel; otherwise it goes to the terminal.
+    fn spawned_output(&self, _output: &process::Output) {}
+
+    fn interrupted(&self) -> Status {
+        Status::Aborted.print_message("Interrupted");
+        Status::Aborted
+    }
+
     fn run(&self) -> Status {
         if self.is_run_spawn() {
-            let mut child = match self.setup_command().spawn() {
-                Ok(child) => child,
-                Err(e) => {
-                    Self::handle_command_error(&format!("{}", e));
-                    return Status::Failure;
-                }
+            let mut command = self.setup_command();
+            let status = match terminal_shared() {
+                true => output_interactive(&mut command, self.timeout()).map(|output| {
+                    output.map(|output| {
+                        self.spawned_output(&output);
+                        output.status
+                    })
+                }),
+                false => run_foreground(&mut command, self.timeout()),
             };
-
-            match &child.wait() {
-                Ok(status) => {
-                    if status.success() {
+            match status {
+                Ok(None) => self.timed_out(),
+                Ok(Some(status)) => {
+                    if self.is_success(&status) {
                         Status::Success
                     } else {
                         Status::Failure
                     }
                 }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => self.interrupted(),
                 Err(e) => {
                     Self::handle_command_error(&format!("{}", e));
                     Status::Failure
                 }
             }
         } else {
-            match self.setup_command().output() {
-                Ok(output) => {
-                    if output.status.success() {
-                        Status::Success
-                    } else {
-                        Self::handle_command_error(&format!("{:?}", output));
-                        Status::Failure
-                    }
+            let mut command = self.setup_command();
+            let output = match self.stream_prefix() {
+                Some(prefix) => output_streaming(&mut command, self.timeout(), &|line| {
+                    reporter().line(&format!("{} {}", prefix, line))
+                }),
+                None => output_timeout(&mut command, self.timeout()),
+            };
+            self.captured_status(output)
+        }
+    }
+
+    /// The status of a run whose output was captured; `Ok(None)` means it timed out.
+    fn captured_status(&self, output: io::Result<Option<process::Output>>) -> Status {
+        match output {
+            Ok(None) => self.timed_out(),
+            Ok(Some(output)) => {
+                if let Some(status) = self.evaluate(&output) {
+                    return status;
                 }
-                Err(e) => {
-                    Self::handle_command_error(&format!("{}", e));
+
+                if self.is_success(&output.status) {
+                    Status::Success
+                } else {
+                    Self::handle_command_error(&format!("{:?}", output));
                     Status::Failure
                 }
             }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => self.interrupted(),
+            Err(e) => {
+                Self::handle_command_error(&format!("{}", e));
+                Status::Failure
+            }
+        }
+    }
+
+    /// `run` without tying up a thread while the child runs. Output is captured and,
+    /// with a `stream_prefix`, streamed as in `run`. stdin is closed, so `run_spawn`
+    /// commands cannot prompt; their output goes to `spawned_output` as it does under
+    /// `execute_parallel`. A timeout or `cancel` kills the command and everything it
+    /// started.
+    #[cfg(feature = "async")]
+    fn run_async(&self, cancel: &Cancellation) -> impl Future<Output = Status> {
+        async move {
+            let prefix = self.stream_prefix();
+            let print = |line: &str| {
+                reporter().line(&format!(
+                    "{} {}",
+                    prefix.as_deref().unwrap_or_default(),
+                    line
+                ))
+            };
+            let on_line: Option<LineSink> = prefix.is_some().then_some(&print);
+            let output = output_async(self.setup_command(), self.timeout(), on_line, cancel).await;
+            if let (true, Ok(Some(output))) = (self.is_run_spawn(), &output) {
+                self.spawned_output(output);
+            }
+            self.captured_status(output)
         }
     }
 }
@@ -57,22 +182,52 @@ pub trait ProcessRunner: CommandRunner {
     fn before_run(&self) -> Status;
     fn after_run(&self, command_status: Status) -> Status;
     fn print_pre_run_info(&self);
+
+    /// How many times to re-run a failed command, and how long to wait in between.
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
     fn execute(&self) -> Status {
-        match self.before_run() {
-            Status::Passed => return Status::Passed,
-            Status::Failure => return Status::Failure,
-            Status::Skipped => return Status::Skipped,
-            _ => (),
-        };
+        if let Some(status) = not_running(self.before_run()) {
+            return status;
+        }
 
         self.print_pre_run_info();
-        let status = self.run();
+        let policy = self.retry_policy();
+        let mut status = self.run();
+        for attempt in 1..=policy.retries {
+            if status != Status::Failure {
+                break;
+            }
+            thread::sleep(announce_retry(&policy, attempt));
+            status = self.run();
+        }
+        finished(self.after_run(status))
+    }
+
+    /// `execute` with `run_async`; the pause between retries can be cancelled too.
+    #[cfg(feature = "async")]
+    fn execute_async(&self, cancel: &Cancellation) -> impl Future<Output = Status> {
+        async move {
+            if let Some(status) = not_running(self.before_run()) {
+                return status;
+            }
 
-        match self.after_run(status) {
-            Status::Passed => return Status::Passed,
-            Status::Failure => return Status::Failure,
-            Status::Skipped => return Status::Skipped,
-            _ => Status::Success,
+            self.print_pre_run_info();
+            let policy = self.retry_policy();
+            let mut status = self.run_async(cancel).await;
+            for attempt in 1..=policy.retries {
+                if status != Status::Failure {
+                    break;
+                }
+                let delay = announce_retry(&policy, attempt);
+                status = tokio::select! {
+                    _ = tokio::time::sleep(delay) => self.run_async(cancel).await,
+                    _ = cancel.cancelled() => self.interrupted(),
+                };
+            }
+            finished(self.after_run(status))
         }
     }
 }
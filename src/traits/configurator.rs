@@ -3,4 +3,14 @@ use crate::utils::Status;
 pub trait Configurator {
     fn apply(&self) -> Status;
     fn revert(&self) -> Status;
+
+    /// What the last `apply` changed, one line per setting, for the run report.
+    fn changes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Variables the step sets in the user's shell, as written, for plans to diff.
+    fn env_assignments(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
 }
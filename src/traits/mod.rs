@@ -1,9 +1,11 @@
+mod check_provider;
 mod command_runner;
 mod configurator;
 mod error_handler;
 pub mod executable_setup;
 mod repository;
 
+pub use check_provider::CheckProvider;
 pub use command_runner::{CommandRunner, ProcessRunner};
 pub use configurator::Configurator;
 pub use error_handler::ErrorHandler;
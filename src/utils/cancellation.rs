@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+use crate::utils::interrupted;
+
+/// Stops an async run from the outside, e.g. a frontend's cancel button: running
+/// commands are killed and entries that have not started yet are skipped.
+#[derive(Debug, Clone)]
+pub struct Cancellation(Arc<watch::Sender<bool>>);
+
+impl Default for Cancellation {
+    fn default() -> Self {
+        Cancellation(Arc::new(watch::channel(false).0))
+    }
+}
+
+impl Cancellation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.send_replace(true);
+    }
+
+    /// Whether `cancel` was called or the run caught SIGINT/SIGTERM.
+    pub fn is_cancelled(&self) -> bool {
+        *self.0.borrow() || interrupted()
+    }
+
+    /// Completes once `cancel` is called.
+    pub async fn cancelled(&self) {
+        let mut cancelled = self.0.subscribe();
+        let _ = cancelled.wait_for(|cancelled| *cancelled).await;
+    }
+}
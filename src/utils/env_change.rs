@@ -0,0 +1,92 @@
+use std::{env, fmt};
+
+use super::expand_path;
+
+/// A change a step would make to the user's shell environment, as shown by a plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvChange {
+    /// `name` would be set to `value`; `previous` is its value in the current environment.
+    Set {
+        name: String,
+        value: String,
+        previous: Option<String>,
+    },
+    /// The directory would be added to `PATH`.
+    PathEntry(String),
+}
+
+impl fmt::Display for EnvChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvChange::Set {
+                name,
+                value,
+                previous: None,
+            } => write!(f, "+ {}={}", name, value),
+            EnvChange::Set {
+                name,
+                value,
+                previous: Some(previous),
+            } => write!(f, "~ {}={} (was {})", name, value, previous),
+            EnvChange::PathEntry(dir) => write!(f, "+ PATH {}", dir),
+        }
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Variable assignments in a shell script (`export NAME=value` or `NAME=value` lines),
+/// with surrounding quotes removed.
+pub fn shell_assignments(script: &str) -> Vec<(String, String)> {
+    script
+        .lines()
+        .map(str::trim)
+        .map(|line| line.strip_prefix("export ").unwrap_or(line).trim_start())
+        .filter_map(|line| line.split_once('='))
+        .filter(|(name, _)| is_name(name))
+        .map(|(name, value)| {
+            let value = value.trim();
+            let unquoted = ['"', '\'']
+                .iter()
+                .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+                .unwrap_or(value);
+            (name.to_string(), unquoted.to_string())
+        })
+        .collect()
+}
+
+/// What `assignments` would change compared to the current environment; variables
+/// that already have the value and directories already on `PATH` are left out.
+pub fn env_changes(assignments: &[(String, String)]) -> Vec<EnvChange> {
+    let path = env::var("PATH").unwrap_or_default();
+    let on_path: Vec<_> = env::split_paths(&path).collect();
+    let mut changes = Vec::new();
+
+    for (name, value) in assignments {
+        if name == "PATH" {
+            let added = value
+                .split(':')
+                .filter(|dir| !dir.is_empty() && !matches!(*dir, "$PATH" | "${PATH}"))
+                .filter(|dir| !on_path.contains(&expand_path(dir)))
+                .map(|dir| EnvChange::PathEntry(dir.to_string()));
+            changes.extend(added);
+            continue;
+        }
+
+        let previous = env::var(name).ok();
+        let expanded = expand_path(value).display().to_string();
+        if previous.as_deref() != Some(expanded.as_str()) {
+            changes.push(EnvChange::Set {
+                name: name.clone(),
+                value: value.clone(),
+                previous,
+            });
+        }
+    }
+    changes
+}
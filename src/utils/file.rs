@@ -0,0 +1,35 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::{fs, process};
+
+/// Writes `content` to `path`, going through `sudo tee` when the target is root-owned.
+pub fn write_file(path: &Path, content: &str, use_sudo: bool) -> io::Result<()> {
+    if !use_sudo {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        return fs::write(path, content);
+    }
+
+    let mut child = process::Command::new("sudo")
+        .arg("tee")
+        .arg(path)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::null())
+        .spawn()?;
+
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| io::Error::other("failed to open sudo tee stdin"))?
+        .write_all(content.as_bytes())?;
+
+    if child.wait()?.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "sudo tee {} failed",
+            path.display()
+        )))
+    }
+}
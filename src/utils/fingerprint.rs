@@ -0,0 +1,26 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Returns the lowercase hex SHA-256 digest of `content`.
+pub fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// The SHA-256 digest of `value`'s JSON form, stable across runs and Rust versions,
+/// for noticing that a manifest or entry changed.
+pub fn fingerprint(value: &impl Serialize) -> String {
+    sha256_hex(&serde_json::to_vec(value).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::io::{self, IsTerminal, Write};
+use std::process::{self, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::SigId;
+
+use crate::utils::Color;
+
+/// Exit code of a process killed by SIGINT, used when a second signal arrives.
+const INTERRUPT_EXIT_CODE: i32 = 130;
+
+static DEPTH: AtomicUsize = AtomicUsize::new(0);
+static HANDLERS: Mutex<Vec<SigId>> = Mutex::new(Vec::new());
+
+fn flag() -> &'static Arc<AtomicBool> {
+    static FLAG: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+    FLAG.get_or_init(|| Arc::new(AtomicBool::new(false)))
+}
+
+/// Whether SIGINT or SIGTERM arrived since the current run started.
+pub fn interrupted() -> bool {
+    flag().load(Ordering::SeqCst)
+}
+
+/// Whether a run is catching SIGINT/SIGTERM, in which case child processes are
+/// polled instead of waited on so they can be stopped.
+pub(crate) fn catching_interrupts() -> bool {
+    DEPTH.load(Ordering::SeqCst) > 0
+}
+
+/// Catches SIGINT and SIGTERM for as long as it lives, so a run can stop the current
+/// child, record what happened and return normally. A second signal exits immediately.
+pub struct InterruptGuard(());
+
+impl InterruptGuard {
+    pub fn install() -> Self {
+        if DEPTH.fetch_add(1, Ordering::SeqCst) == 0 {
+            flag().store(false, Ordering::SeqCst);
+            let mut handlers = HANDLERS.lock().unwrap_or_else(|e| e.into_inner());
+            for signal in [SIGINT, SIGTERM] {
+                // Registered first, so it only sees the flag set by an earlier signal.
+                let shutdown = signal_hook::flag::register_conditional_shutdown(
+                    signal,
+                    INTERRUPT_EXIT_CODE,
+                    Arc::clone(flag()),
+                );
+                let record = signal_hook::flag::register(signal, Arc::clone(flag()));
+                handlers.extend(shutdown.into_iter().chain(record));
+            }
+        }
+        InterruptGuard(())
+    }
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        if DEPTH.fetch_sub(1, Ordering::SeqCst) != 1 {
+            return;
+        }
+        let mut handlers = HANDLERS.lock().unwrap_or_else(|e| e.into_inner());
+        for id in handlers.drain(..) {
+            signal_hook::low_level::unregister(id);
+        }
+        if interrupted() {
+            restore_terminal();
+        }
+    }
+}
+
+/// Undoes what a killed child may have left behind: raw mode, hidden cursor, colors.
+fn restore_terminal() {
+    let mut stdout = io::stdout();
+    let _ = write!(stdout, "{}\x1b[?25h", Color::None);
+    let _ = stdout.flush();
+    if io::stdin().is_terminal() {
+        let _ = process::Command::new("stty")
+            .arg("sane")
+            .stderr(Stdio::null())
+            .status();
+    }
+}
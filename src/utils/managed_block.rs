@@ -0,0 +1,48 @@
+/// Locates the block between the `begin` and `end` marker lines, including a trailing newline.
+fn find_block(content: &str, begin: &str, end: &str) -> Option<(usize, usize)> {
+    let start = content.find(begin)?;
+    let stop = start + content[start..].find(end)? + end.len();
+    let stop = if content[stop..].starts_with('\n') {
+        stop + 1
+    } else {
+        stop
+    };
+    Some((start, stop))
+}
+
+/// Wraps `body` in marker lines so it can be found and replaced on the next run.
+pub fn managed_block(begin: &str, body: &str, end: &str) -> String {
+    format!("{}\n{}\n{}\n", begin, body.trim_end_matches('\n'), end)
+}
+
+/// Replaces the marked block with `block`, or adds it (at the top with `prepend`, otherwise
+/// at the end, separated by a blank line) when missing. Returns `None` when the content is already up to date.
+pub fn upsert_block(
+    content: &str,
+    begin: &str,
+    end: &str,
+    block: &str,
+    prepend: bool,
+) -> Option<String> {
+    match find_block(content, begin, end) {
+        Some((start, stop)) if content[start..stop] == *block => None,
+        Some((start, stop)) => Some(format!(
+            "{}{}{}",
+            &content[..start],
+            block,
+            &content[stop..]
+        )),
+        None if prepend && content.is_empty() => Some(block.to_string()),
+        None if prepend => Some(format!("{}\n{}", block, content)),
+        None if content.is_empty() || content.ends_with('\n') => {
+            Some(format!("{}{}", content, block))
+        }
+        None => Some(format!("{}\n{}", content, block)),
+    }
+}
+
+/// Drops the marked block, returning `None` when there is none.
+pub fn remove_block(content: &str, begin: &str, end: &str) -> Option<String> {
+    let (start, stop) = find_block(content, begin, end)?;
+    Some(format!("{}{}", &content[..start], &content[stop..]))
+}
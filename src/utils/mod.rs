@@ -1,5 +1,47 @@
+#[cfg(feature = "async")]
+pub(crate) mod cancellation;
 pub(crate) mod color;
+pub(crate) mod duration;
+pub(crate) mod env_change;
+pub(crate) mod file;
+pub(crate) mod fingerprint;
+pub(crate) mod interrupt;
+pub(crate) mod managed_block;
+pub(crate) mod on_error;
+pub(crate) mod path;
+pub(crate) mod privilege;
+pub(crate) mod process;
+pub(crate) mod reporter;
+pub(crate) mod skip_reason;
 pub(crate) mod status;
+pub(crate) mod title;
+pub(crate) mod user;
+pub(crate) mod workspace;
 
+#[cfg(feature = "async")]
+pub use cancellation::Cancellation;
 pub use color::Color;
+pub use duration::{format_duration, parse_duration};
+pub use env_change::{env_changes, shell_assignments, EnvChange};
+pub use file::write_file;
+pub use fingerprint::{fingerprint, sha256_hex};
+pub use interrupt::{interrupted, InterruptGuard};
+pub use managed_block::{managed_block, remove_block, upsert_block};
+pub use on_error::OnError;
+pub(crate) use path::cache_home;
+pub use path::expand_path;
+pub use privilege::SudoSession;
+#[cfg(feature = "async")]
+pub(crate) use process::output_async;
+pub(crate) use process::terminal_shared;
+pub use process::{
+    output_interactive, output_streaming, output_timeout, run_foreground, wait_timeout, LineSink,
+    SharedTerminal,
+};
+pub use reporter::{reporter, set_reporter, Reporter};
+pub use skip_reason::SkipReason;
 pub use status::Status;
+pub use title::set_terminal_title;
+pub(crate) use title::{pop_terminal_title, push_terminal_title};
+pub use user::{invoking_user, is_root};
+pub use workspace::{Workspace, TMPDIR_PLACEHOLDER};
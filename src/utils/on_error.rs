@@ -0,0 +1,42 @@
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::reporter;
+
+/// What to do with the rest of the run (or entry) once something fails.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum OnError {
+    /// Keep going with whatever comes next.
+    #[default]
+    Continue,
+    /// Stop at the first failure.
+    Abort,
+    /// Ask on stdin whether to keep going; refused by `execute_parallel`.
+    Prompt,
+}
+
+impl OnError {
+    /// Whether to carry on after `failed` (e.g. "command `make`") failed.
+    /// A prompt that gets no answer aborts.
+    pub fn should_continue(self, failed: &str) -> bool {
+        match self {
+            OnError::Continue => true,
+            OnError::Abort => false,
+            OnError::Prompt => loop {
+                reporter().prompt(&format!("{} failed. [c]ontinue or [a]bort? ", failed));
+                let mut answer = String::new();
+                if io::stdin().read_line(&mut answer).unwrap_or(0) == 0 {
+                    return false;
+                }
+                match answer.trim().to_lowercase().as_str() {
+                    "c" | "continue" => return true,
+                    "a" | "abort" => return false,
+                    _ => continue,
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::env;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Deserializer};
+
+/// XDG base directory variables and their fallbacks relative to `$HOME`.
+const XDG_DEFAULTS: [(&str, &str); 4] = [
+    ("XDG_CONFIG_HOME", ".config"),
+    ("XDG_DATA_HOME", ".local/share"),
+    ("XDG_CACHE_HOME", ".cache"),
+    ("XDG_STATE_HOME", ".local/state"),
+];
+
+fn home_dir() -> Option<String> {
+    env::var("HOME").ok().filter(|home| !home.is_empty())
+}
+
+fn lookup_var(name: &str) -> Option<String> {
+    if let Ok(value) = env::var(name) {
+        if !value.is_empty() {
+            return Some(value);
+        }
+    }
+
+    XDG_DEFAULTS
+        .iter()
+        .find(|(var, _)| *var == name)
+        .and_then(|(_, fallback)| home_dir().map(|home| format!("{}/{}", home, fallback)))
+}
+
+/// `$XDG_CACHE_HOME`, or `~/.cache` when it is unset.
+pub(crate) fn cache_home() -> Option<PathBuf> {
+    lookup_var("XDG_CACHE_HOME").map(PathBuf::from)
+}
+
+/// Expands a leading `~`, `$VAR` / `${VAR}` references and XDG base directories
+/// (falling back to their spec defaults when unset).
+/// Unknown variables are left untouched instead of collapsing to an empty string.
+pub fn expand_path(input: &str) -> PathBuf {
+    let mut expanded = String::with_capacity(input.len());
+    let mut rest = input;
+
+    if rest == "~" || rest.starts_with("~/") {
+        if let Some(home) = home_dir() {
+            expanded.push_str(&home);
+            rest = &rest[1..];
+        }
+    }
+
+    while let Some(index) = rest.find('$') {
+        expanded.push_str(&rest[..index]);
+        let after = &rest[index + 1..];
+
+        let (name, consumed) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+
+        match lookup_var(name).filter(|_| !name.is_empty()) {
+            Some(value) => expanded.push_str(&value),
+            None => expanded.push_str(&rest[index..index + 1 + consumed]),
+        }
+        rest = &after[consumed..];
+    }
+
+    expanded.push_str(rest);
+    PathBuf::from(expanded)
+}
+
+/// Serde helper for optional path fields that should be expanded at load time.
+pub fn deserialize_expanded_path<'de, D>(deserializer: D) -> Result<Option<PathBuf>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw: Option<String> = Option::deserialize(deserializer)?;
+    Ok(raw.map(|path| expand_path(&path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = env::var("HOME").unwrap();
+        assert_eq!(expand_path("~"), PathBuf::from(&home));
+        assert_eq!(
+            expand_path("~/projects"),
+            PathBuf::from(format!("{}/projects", home))
+        );
+        assert_eq!(expand_path("/tmp/~"), PathBuf::from("/tmp/~"));
+    }
+
+    #[test]
+    fn test_expand_env_vars() {
+        env::set_var("LSU_PATH_TEST", "/opt/lsu");
+        assert_eq!(
+            expand_path("$LSU_PATH_TEST/bin"),
+            PathBuf::from("/opt/lsu/bin")
+        );
+        assert_eq!(
+            expand_path("${LSU_PATH_TEST}bin"),
+            PathBuf::from("/opt/lsubin")
+        );
+    }
+
+    #[test]
+    fn test_unknown_vars_are_kept() {
+        assert_eq!(
+            expand_path("$LSU_PATH_UNSET/x/${LSU_PATH_UNSET}"),
+            PathBuf::from("$LSU_PATH_UNSET/x/${LSU_PATH_UNSET}")
+        );
+        assert_eq!(expand_path("cost$"), PathBuf::from("cost$"));
+    }
+
+    #[test]
+    fn test_expand_xdg_fallback() {
+        let expanded = expand_path("${XDG_CACHE_HOME}/lsu");
+        assert!(expanded.ends_with("lsu"));
+        assert!(!expanded.to_string_lossy().contains('$'));
+    }
+}
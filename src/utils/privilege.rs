@@ -0,0 +1,74 @@
+use std::process::{self, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::utils::{is_root, reporter};
+
+/// How often the sudo timestamp is refreshed; sudo's default timeout is 15 minutes.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(60);
+
+fn sudo(args: &[&str], interactive: bool) -> bool {
+    let mut command = process::Command::new("sudo");
+    command.args(args);
+    if !interactive {
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+    }
+    command.status().is_ok_and(|status| status.success())
+}
+
+/// Holds sudo credentials for the length of a run: asks for the password once up front
+/// and refreshes the timestamp in the background, so privileged commands run with
+/// `sudo -n` and never prompt halfway through an unattended run.
+pub struct SudoSession {
+    stop: Option<Sender<()>>,
+    keep_alive: Option<JoinHandle<()>>,
+}
+
+impl SudoSession {
+    /// Validates sudo credentials, prompting if needed. `None` when already root; fails
+    /// when sudo is missing or the credentials are refused.
+    pub fn start() -> Result<Option<Self>, String> {
+        if is_root() {
+            return Ok(None);
+        }
+        if process::Command::new("sudo")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_err()
+        {
+            return Err("root is needed, but sudo is not installed".to_string());
+        }
+        if !sudo(&["-n", "-v"], false) {
+            reporter().line("Some entries need root; sudo asks for your password once.");
+            if !sudo(&["-v"], true) {
+                return Err("root is needed, but sudo refused the credentials".to_string());
+            }
+        }
+
+        let (stop, stopped) = mpsc::channel();
+        let keep_alive = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(KEEP_ALIVE_INTERVAL) {
+                sudo(&["-n", "-v"], false);
+            }
+        });
+        Ok(Some(SudoSession {
+            stop: Some(stop),
+            keep_alive: Some(keep_alive),
+        }))
+    }
+}
+
+impl Drop for SudoSession {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(keep_alive) = self.keep_alive.take() {
+            let _ = keep_alive.join();
+        }
+    }
+}
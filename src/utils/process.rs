@@ -0,0 +1,454 @@
+use std::io::{self, BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{self, Child, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use tokio::io::{AsyncBufReadExt, AsyncRead};
+
+#[cfg(feature = "async")]
+use super::cancellation::Cancellation;
+use super::interrupt::{catching_interrupts, interrupted};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How long an interrupted child gets to clean up (e.g. release the pacman lock) before SIGKILL.
+const INTERRUPT_GRACE: Duration = Duration::from_secs(5);
+
+static SHARED_TERMINAL: AtomicUsize = AtomicUsize::new(0);
+
+/// Marks a run whose entries share the terminal for as long as it lives, so
+/// `run_spawn` output is captured and shown in one block instead of interleaving.
+pub struct SharedTerminal(());
+
+impl SharedTerminal {
+    pub fn install() -> Self {
+        SHARED_TERMINAL.fetch_add(1, Ordering::SeqCst);
+        SharedTerminal(())
+    }
+}
+
+impl Drop for SharedTerminal {
+    fn drop(&mut self) {
+        SHARED_TERMINAL.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Whether a `SharedTerminal` is installed.
+pub(crate) fn terminal_shared() -> bool {
+    SHARED_TERMINAL.load(Ordering::SeqCst) > 0
+}
+
+/// Waits for `child`, killing it once `timeout` has passed. `Ok(None)` means it was killed.
+/// While a run catches interrupts, a SIGINT/SIGTERM stops the child first with SIGINT,
+/// then SIGKILL, and is reported as an `Interrupted` error.
+pub fn wait_timeout(
+    child: &mut Child,
+    timeout: Option<Duration>,
+) -> io::Result<Option<ExitStatus>> {
+    if timeout.is_none() && !catching_interrupts() {
+        return child.wait().map(Some);
+    }
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if interrupted() {
+            stop(child);
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"));
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            kill_tree(child);
+            child.wait()?;
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Every process below `pid`, parents before their children.
+fn descendants(pid: &str) -> Vec<String> {
+    let children = process::Command::new("pgrep")
+        .args(["-P", pid])
+        .stderr(Stdio::null())
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        .unwrap_or_default();
+    children
+        .split_whitespace()
+        .flat_map(|child| {
+            let mut tree = vec![child.to_string()];
+            tree.extend(descendants(child));
+            tree
+        })
+        .collect()
+}
+
+/// Sends `signal` to the child's process group, if it leads one, and to every process
+/// below it, so the installer started by `sh -c` gets it too whether or not the
+/// command runs in a group of its own.
+fn signal_tree(pid: u32, signal: &str) {
+    let pid = pid.to_string();
+    let tree = descendants(&pid);
+    let _ = process::Command::new("kill")
+        .args([&format!("-{}", signal), "--", &format!("-{}", pid)])
+        .stderr(Stdio::null())
+        .status();
+    if !tree.is_empty() {
+        let _ = process::Command::new("kill")
+            .arg(format!("-{}", signal))
+            .arg("--")
+            .args(&tree)
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// Kills everything the child started, then the child itself.
+fn kill_tree(child: &mut Child) {
+    signal_tree(child.id(), "KILL");
+    let _ = child.kill();
+}
+
+/// Asks the child to stop with SIGINT, like Ctrl-C would, so package managers can release
+/// their locks; kills it if it is still running after `INTERRUPT_GRACE`.
+fn stop(child: &mut Child) {
+    signal_tree(child.id(), "INT");
+    let _ = process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .stderr(Stdio::null())
+        .status();
+
+    let deadline = Instant::now() + INTERRUPT_GRACE;
+    while Instant::now() < deadline {
+        if let Ok(Some(_)) = child.try_wait() {
+            return;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    kill_tree(child);
+    let _ = child.wait();
+}
+
+/// Called with each line of output as it arrives, without the line ending.
+pub type LineSink<'a> = &'a (dyn Fn(&str) + Sync);
+
+/// Reads `pipe` to the end, passing each line to `on_line` along the way.
+fn drain(pipe: impl Read, on_line: Option<LineSink>) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    let Some(on_line) = on_line else {
+        let mut pipe = pipe;
+        let _ = pipe.read_to_end(&mut buffer);
+        return buffer;
+    };
+
+    let mut reader = BufReader::new(pipe);
+    loop {
+        let start = buffer.len();
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) | Err(_) => return buffer,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buffer[start..]);
+                on_line(line.trim_end_matches(['\n', '\r']));
+            }
+        }
+    }
+}
+
+/// Like `Command::output`, but kills the command and everything it started after
+/// `timeout`. `Ok(None)` means it timed out.
+///
+/// Captured commands cannot prompt, so they run in a process group of their own, which
+/// keeps a terminal Ctrl-C away from them until the run has decided how to stop them.
+pub fn output_timeout(
+    command: &mut process::Command,
+    timeout: Option<Duration>,
+) -> io::Result<Option<Output>> {
+    if timeout.is_none() && !catching_interrupts() {
+        return command.output().map(Some);
+    }
+    command.process_group(0);
+    capture(command.stdin(Stdio::null()), timeout, None)
+}
+
+/// Like `output_timeout`, but also hands every stdout and stderr line to `on_line`
+/// while the command runs.
+pub fn output_streaming(
+    command: &mut process::Command,
+    timeout: Option<Duration>,
+    on_line: LineSink,
+) -> io::Result<Option<Output>> {
+    command.process_group(0);
+    capture(command.stdin(Stdio::null()), timeout, Some(on_line))
+}
+
+/// Like `output_timeout`, but the command keeps the terminal's stdin and stays in its
+/// foreground process group, so it can still ask questions (sudo included) without
+/// being stopped by SIGTTIN. stdout and stderr share one pipe to keep their order;
+/// everything ends up in `Output::stdout`.
+pub fn output_interactive(
+    command: &mut process::Command,
+    timeout: Option<Duration>,
+) -> io::Result<Option<Output>> {
+    let (reader, writer) = io::pipe()?;
+    command
+        .stdin(Stdio::inherit())
+        .stdout(writer.try_clone()?)
+        .stderr(writer);
+    let child = command.spawn();
+    // The command keeps its copies of the write end; close them so reading ends with
+    // the child.
+    command.stdout(Stdio::null()).stderr(Stdio::null());
+    let mut child = child?;
+    let (status, stdout) = thread::scope(|scope| {
+        let stdout = scope.spawn(move || drain(reader, None));
+        let status = wait_timeout(&mut child, timeout);
+        (status, stdout.join().unwrap_or_default())
+    });
+
+    Ok(status?.map(|status| Output {
+        status,
+        stdout,
+        stderr: Vec::new(),
+    }))
+}
+
+/// Runs the command on the terminal, output included, killing it and everything it
+/// started after `timeout`. `Ok(None)` means it timed out.
+pub fn run_foreground(
+    command: &mut process::Command,
+    timeout: Option<Duration>,
+) -> io::Result<Option<ExitStatus>> {
+    let mut child = command
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+    wait_timeout(&mut child, timeout)
+}
+
+/// `drain` for the pipes of an async child.
+#[cfg(feature = "async")]
+async fn drain_async(
+    pipe: Option<impl AsyncRead + Unpin>,
+    on_line: Option<LineSink<'_>>,
+) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    let Some(pipe) = pipe else {
+        return buffer;
+    };
+    let mut reader = tokio::io::BufReader::new(pipe);
+    loop {
+        let start = buffer.len();
+        match reader.read_until(b'\n', &mut buffer).await {
+            Ok(0) | Err(_) => return buffer,
+            Ok(_) => {
+                if let Some(on_line) = on_line {
+                    let line = String::from_utf8_lossy(&buffer[start..]);
+                    on_line(line.trim_end_matches(['\n', '\r']));
+                }
+            }
+        }
+    }
+}
+
+/// `output_timeout`, or `output_streaming` with `on_line`, on the async runtime. The
+/// command runs in a process group of its own with stdin closed; after `timeout` or
+/// `cancel` it is killed along with everything it started. `Ok(None)` means it timed
+/// out, and a cancelled command is reported as an `Interrupted` error.
+#[cfg(feature = "async")]
+pub(crate) async fn output_async(
+    command: process::Command,
+    timeout: Option<Duration>,
+    on_line: Option<LineSink<'_>>,
+    cancel: &Cancellation,
+) -> io::Result<Option<Output>> {
+    let mut command = tokio::process::Command::from(command);
+    command
+        .process_group(0)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = command.spawn()?;
+    let pid = child.id();
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+
+    let finished = async {
+        let (status, stdout, stderr) = tokio::join!(
+            child.wait(),
+            drain_async(stdout, on_line),
+            drain_async(stderr, on_line)
+        );
+        status.map(|status| Output {
+            status,
+            stdout,
+            stderr,
+        })
+    };
+    let limit = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+    let stopped = tokio::select! {
+        output = finished => return output.map(Some),
+        _ = limit => Ok(None),
+        _ = cancel.cancelled() => Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled")),
+    };
+
+    if let Some(pid) = pid {
+        signal_tree(pid, "KILL");
+    }
+    let _ = child.kill().await;
+    stopped
+}
+
+fn capture(
+    command: &mut process::Command,
+    timeout: Option<Duration>,
+    on_line: Option<LineSink>,
+) -> io::Result<Option<Output>> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let (status, stdout, stderr) = thread::scope(|scope| {
+        // Drain both pipes while waiting, or a chatty command blocks on a full pipe.
+        let stdout = child
+            .stdout
+            .take()
+            .map(|pipe| scope.spawn(move || drain(pipe, on_line)));
+        let stderr = child
+            .stderr
+            .take()
+            .map(|pipe| scope.spawn(move || drain(pipe, on_line)));
+
+        let status = wait_timeout(&mut child, timeout);
+        let collect = |handle: Option<thread::ScopedJoinHandle<Vec<u8>>>| {
+            handle
+                .map(|handle| handle.join().unwrap_or_default())
+                .unwrap_or_default()
+        };
+        (status, collect(stdout), collect(stderr))
+    });
+    let status = status?;
+
+    Ok(status.map(|status| Output {
+        status,
+        stdout,
+        stderr,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_timeout_kills_the_whole_group() {
+        let started = Instant::now();
+        let output = output_timeout(
+            process::Command::new("sh").args(["-c", "sleep 5; echo done"]),
+            Some(Duration::from_millis(200)),
+        )
+        .unwrap();
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let output = output_timeout(
+            process::Command::new("sh").args(["-c", "echo done"]),
+            Some(Duration::from_secs(5)),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(output.stdout, b"done\n");
+    }
+
+    #[test]
+    fn test_timeout_kills_grandchildren_outside_a_group() {
+        let started = Instant::now();
+        // The grandchild holds the pipe open, so this only returns once it is dead too.
+        let output = output_interactive(
+            process::Command::new("sh").args(["-c", "sh -c 'sleep 5'; echo done"]),
+            Some(Duration::from_millis(200)),
+        )
+        .unwrap();
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let output = output_interactive(
+            process::Command::new("sh").args(["-c", "echo one; echo two >&2; echo three"]),
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(output.stdout, b"one\ntwo\nthree\n");
+    }
+
+    #[test]
+    fn test_output_streaming_passes_lines_through() {
+        let lines = std::sync::Mutex::new(Vec::new());
+        let output = output_streaming(
+            process::Command::new("sh").args(["-c", "echo one; echo two >&2; printf three"]),
+            None,
+            &|line| lines.lock().unwrap().push(line.to_string()),
+        )
+        .unwrap()
+        .unwrap();
+
+        let mut lines = lines.into_inner().unwrap();
+        lines.sort();
+        assert_eq!(lines, ["one", "three", "two"]);
+        assert_eq!(output.stdout, b"one\nthree");
+        assert_eq!(output.stderr, b"two\n");
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_output_async_streams_and_kills_the_tree() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let cancel = Cancellation::new();
+        let shell = |script: &str| {
+            let mut command = process::Command::new("sh");
+            command.args(["-c", script]);
+            command
+        };
+
+        let lines = std::sync::Mutex::new(Vec::new());
+        let on_line = |line: &str| lines.lock().unwrap().push(line.to_string());
+        let output = runtime
+            .block_on(output_async(
+                shell("echo one; echo two >&2"),
+                None,
+                Some(&on_line),
+                &cancel,
+            ))
+            .unwrap()
+            .unwrap();
+        assert_eq!(output.stdout, b"one\n");
+        let mut lines = lines.into_inner().unwrap();
+        lines.sort();
+        assert_eq!(lines, ["one", "two"]);
+
+        // The grandchild holds the pipes open, so this only returns once it is dead too.
+        let output = runtime.block_on(output_async(
+            shell("sh -c 'sleep 30'; echo done"),
+            Some(Duration::from_millis(100)),
+            None,
+            &cancel,
+        ));
+        assert!(output.unwrap().is_none());
+
+        cancel.cancel();
+        let output = runtime.block_on(output_async(shell("sh -c 'sleep 30'"), None, None, &cancel));
+        assert_eq!(output.unwrap_err().kind(), io::ErrorKind::Interrupted);
+    }
+}
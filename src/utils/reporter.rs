@@ -0,0 +1,131 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::utils::Status;
+
+enum Sink {
+    Stdout,
+    Buffer(Vec<u8>),
+}
+
+/// A cloneable, thread-safe output handle.
+/// Every message is written under a single lock so lines from concurrent entries never interleave.
+#[derive(Clone)]
+pub struct Reporter {
+    sink: Arc<Mutex<Sink>>,
+}
+
+impl Reporter {
+    pub fn stdout() -> Self {
+        Reporter {
+            sink: Arc::new(Mutex::new(Sink::Stdout)),
+        }
+    }
+
+    /// A reporter that keeps everything in memory, mainly for tests and embedding.
+    pub fn buffered() -> Self {
+        Reporter {
+            sink: Arc::new(Mutex::new(Sink::Buffer(Vec::new()))),
+        }
+    }
+
+    fn write(&self, text: &str, to_stderr: bool) {
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        match &mut *sink {
+            Sink::Stdout if to_stderr => {
+                let mut stderr = io::stderr().lock();
+                let _ = stderr.write_all(text.as_bytes());
+                let _ = stderr.flush();
+            }
+            Sink::Stdout => {
+                let mut stdout = io::stdout().lock();
+                let _ = stdout.write_all(text.as_bytes());
+                let _ = stdout.flush();
+            }
+            Sink::Buffer(buffer) => buffer.extend_from_slice(text.as_bytes()),
+        }
+    }
+
+    pub fn line(&self, message: &str) {
+        self.write(&format!("{}\n", message), false);
+    }
+
+    pub fn error(&self, message: &str) {
+        self.write(&format!("{}\n", message), true);
+    }
+
+    /// Writes without a trailing newline, for questions answered on stdin.
+    pub fn prompt(&self, message: &str) {
+        self.write(message, false);
+    }
+
+    pub fn status(&self, status: &Status, message: &str) {
+        self.line(&status.format_message(message));
+    }
+
+    /// Everything written so far; always empty for the stdout reporter.
+    pub fn contents(&self) -> String {
+        match &*self.sink.lock().unwrap_or_else(|e| e.into_inner()) {
+            Sink::Stdout => String::new(),
+            Sink::Buffer(buffer) => String::from_utf8_lossy(buffer).into_owned(),
+        }
+    }
+}
+
+impl Default for Reporter {
+    fn default() -> Self {
+        Reporter::stdout()
+    }
+}
+
+fn global() -> &'static Mutex<Reporter> {
+    static GLOBAL: OnceLock<Mutex<Reporter>> = OnceLock::new();
+    GLOBAL.get_or_init(|| Mutex::new(Reporter::stdout()))
+}
+
+/// Returns the process-wide reporter used by `Status`, `SetupItem` and `SetupEntry`.
+pub fn reporter() -> Reporter {
+    global().lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Replaces the process-wide reporter, e.g. with `Reporter::buffered()` when embedding.
+pub fn set_reporter(reporter: Reporter) {
+    *global().lock().unwrap_or_else(|e| e.into_inner()) = reporter;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_buffered_reporter() {
+        let reporter = Reporter::buffered();
+        reporter.line("first");
+        reporter.prompt("question? ");
+        reporter.status(&Status::Normal, "second");
+        assert_eq!(reporter.contents(), "first\nquestion? second\n");
+    }
+
+    #[test]
+    fn test_concurrent_lines_stay_whole() {
+        let reporter = Reporter::buffered();
+        let handles: Vec<_> = (0..4)
+            .map(|id| {
+                let reporter = reporter.clone();
+                thread::spawn(move || {
+                    for _ in 0..50 {
+                        reporter.line(&format!("worker-{}-line", id));
+                    }
+                })
+            })
+            .collect();
+        handles.into_iter().for_each(|h| h.join().unwrap());
+
+        let contents = reporter.contents();
+        assert_eq!(contents.lines().count(), 200);
+        assert!(contents
+            .lines()
+            .all(|line| line.starts_with("worker-") && line.ends_with("-line")));
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+
+use crate::DistributionType;
+
+/// Explains why a command or entry did not run, so output and reports can say more than "Skipped".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    DistributionMismatch {
+        /// The distributions the command targets; any one of them would do.
+        required: Vec<DistributionType>,
+        detected: DistributionType,
+    },
+    CheckPassed,
+    Condition(String),
+    /// An entry this one `depends_on` failed or was skipped for the same reason.
+    DependencyFailed(String),
+    /// Succeeded in an earlier run that is being resumed.
+    Completed,
+    /// Succeeded before and its definition has not changed since.
+    Unchanged,
+    /// Declined when asked in interactive mode.
+    Declined,
+    /// An earlier entry failed with `on_error: abort`.
+    Aborted(String),
+    /// The registry's `max_failures` was reached after this many failed entries.
+    TooManyFailures(usize),
+    /// SIGINT or SIGTERM arrived before the entry started.
+    Interrupted,
+    /// `requires_previous: success`, but the preceding command failed.
+    PreviousFailed,
+    /// `requires_previous: failure`, but there was nothing to fall back from.
+    PreviousSucceeded,
+    /// The distribution's release is outside `min_version..=max_version`, or unknown.
+    ReleaseMismatch {
+        min: Option<String>,
+        max: Option<String>,
+        detected: Option<String>,
+    },
+    /// The CPU architecture is not among the command's `arch` list.
+    ArchitectureMismatch {
+        required: Vec<String>,
+        detected: String,
+    },
+    /// `skip_in_container`, and running inside this container runtime.
+    InContainer(String),
+    /// Running on NixOS, and this command would change `/etc`, which NixOS manages.
+    ManagedByNixOs(String),
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::DistributionMismatch { required, detected } => {
+                let required: Vec<String> = required.iter().map(ToString::to_string).collect();
+                write!(
+                    f,
+                    "requires {}, detected {}",
+                    required.join(" or "),
+                    detected
+                )
+            }
+            SkipReason::ReleaseMismatch { min, max, detected } => {
+                let bounds: Vec<String> = [(">=", min), ("<=", max)]
+                    .into_iter()
+                    .filter_map(|(op, bound)| {
+                        bound.as_ref().map(|bound| format!("{} {}", op, bound))
+                    })
+                    .collect();
+                write!(f, "requires release {}, detected ", bounds.join(" and "))?;
+                match detected {
+                    Some(detected) => write!(f, "{}", detected),
+                    None => write!(f, "no release number"),
+                }
+            }
+            SkipReason::ArchitectureMismatch { required, detected } => {
+                write!(
+                    f,
+                    "requires {}, detected {}",
+                    required.join(" or "),
+                    detected
+                )
+            }
+            SkipReason::CheckPassed => write!(f, "check passed, already satisfied"),
+            SkipReason::Condition(condition) => write!(f, "condition not met: {}", condition),
+            SkipReason::DependencyFailed(name) => write!(f, "dependency {:?} failed", name),
+            SkipReason::Completed => write!(f, "completed in a previous run"),
+            SkipReason::Unchanged => write!(f, "unchanged since it was last applied"),
+            SkipReason::Declined => write!(f, "declined interactively"),
+            SkipReason::Aborted(name) => write!(f, "run aborted after {:?} failed", name),
+            SkipReason::Interrupted => write!(f, "run interrupted"),
+            SkipReason::PreviousFailed => write!(f, "previous command failed"),
+            SkipReason::PreviousSucceeded => write!(f, "previous command did not fail"),
+            SkipReason::InContainer(runtime) => write!(f, "running in a {} container", runtime),
+            SkipReason::ManagedByNixOs(command) => {
+                write!(f, "NixOS manages /etc, but `{}` changes it", command)
+            }
+            SkipReason::TooManyFailures(count) => {
+                write!(f, "run aborted after {} failed entries", count)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_skip_reason() {
+        let reason = SkipReason::DistributionMismatch {
+            required: vec![DistributionType::ArchLinux, DistributionType::Fedora],
+            detected: DistributionType::Ubuntu,
+        };
+        assert_eq!(
+            format!("{}", reason),
+            "requires Arch Linux or Fedora, detected Ubuntu"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                SkipReason::Condition("env('CI') == 'true'".to_string())
+            ),
+            "condition not met: env('CI') == 'true'"
+        );
+        let release = SkipReason::ReleaseMismatch {
+            min: Some("22.04".to_string()),
+            max: Some("24.04".to_string()),
+            detected: Some("20.04".to_string()),
+        };
+        assert_eq!(
+            release.to_string(),
+            "requires release >= 22.04 and <= 24.04, detected 20.04"
+        );
+    }
+}
@@ -1,10 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-use crate::utils::Color;
+use crate::utils::{reporter, Color};
 
 /// Defines an enum representing different statuses of a command execution.
-/// Implements `print_message(message: &str)` methods to print messages based on the command status.
+/// Implements `print_message(message: &str)` methods to print messages based on the command status
+/// through the process-wide `Reporter`.
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
 pub enum Status {
     Running,
@@ -15,10 +16,16 @@ pub enum Status {
     Normal,
     Skipped,
     Passed,
+    /// Stopped by SIGINT/SIGTERM before it could finish.
+    Aborted,
 }
 
 impl Status {
     pub fn print_message(&self, message: &str) {
+        reporter().status(self, message);
+    }
+
+    pub fn format_message(&self, message: &str) -> String {
         use Status::*;
         let (status_icon, status_text) = match self {
             Running => ("⏳", "Running"),
@@ -27,13 +34,14 @@ impl Status {
             Failure => ("❌", "Failed"),
             Skipped => ("⏭️", "Skipped"),
             Passed => ("✔️", "Passed"),
-            Normal => return println!("{}", message),
+            Aborted => ("🛑", "Aborted"),
+            Normal => return message.to_string(),
         };
-        println!(
+        format!(
             "{self_color}==> {status_icon} {status_text}{reset_color}: {message}",
             self_color = self.to_color(),
             reset_color = Color::None
-        );
+        )
     }
 }
 
@@ -54,6 +62,7 @@ impl Status {
             Normal => Color::None,
             Skipped => Color::Yellow,
             Passed => Color::Yellow,
+            Aborted => Color::Red,
         }
     }
 }
@@ -80,4 +89,13 @@ mod tests {
         Failure.print_message("Test failure");
         Normal.print_message("Test normal");
     }
+
+    #[test]
+    fn test_format_message() {
+        assert_eq!(Normal.format_message("plain"), "plain");
+        assert_eq!(
+            Success.format_message("done"),
+            "\x1b[32m==> ✅ Success\x1b[0m: done"
+        );
+    }
 }
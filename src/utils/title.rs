@@ -0,0 +1,32 @@
+use std::env;
+use std::io::{self, IsTerminal, Write};
+
+/// Whether stdout is a terminal that understands OSC escapes, so titles never end up
+/// in piped output or log files.
+fn title_supported() -> bool {
+    io::stdout().is_terminal() && env::var("TERM").is_ok_and(|term| term != "dumb")
+}
+
+fn write_escape(sequence: &str) {
+    if title_supported() {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(sequence.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+/// Sets the window title with OSC 2; tmux shows it as the pane title.
+pub fn set_terminal_title(title: &str) {
+    let title: String = title.chars().filter(|c| !c.is_control()).collect();
+    write_escape(&format!("\x1b]2;{}\x07", title));
+}
+
+/// Saves the current title on the terminal's title stack (XTWINOPS).
+pub(crate) fn push_terminal_title() {
+    write_escape("\x1b[22;2t");
+}
+
+/// Restores the title saved by `push_terminal_title`.
+pub(crate) fn pop_terminal_title() {
+    write_escape("\x1b[23;2t");
+}
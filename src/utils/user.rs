@@ -0,0 +1,14 @@
+use std::os::unix::fs::MetadataExt;
+use std::{env, fs};
+
+/// Whether the process runs as root, judged by the owner of `/proc/self`.
+pub fn is_root() -> bool {
+    fs::metadata("/proc/self").is_ok_and(|metadata| metadata.uid() == 0)
+}
+
+/// The user who invoked `sudo`, if the process runs under it on behalf of someone else.
+pub fn invoking_user() -> Option<String> {
+    env::var("SUDO_USER")
+        .ok()
+        .filter(|user| !user.is_empty() && user != "root")
+}
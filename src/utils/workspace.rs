@@ -0,0 +1,109 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+use super::Status;
+
+/// Placeholder in command strings replaced by the run's (or entry's) temporary directory.
+pub const TMPDIR_PLACEHOLDER: &str = "{{tmpdir}}";
+
+/// A temporary directory that lives for one run and is removed when dropped,
+/// unless it is kept for debugging.
+#[derive(Debug)]
+pub struct Workspace {
+    path: PathBuf,
+    keep: bool,
+}
+
+/// How many names `Workspace::create` tries before giving up.
+const CREATE_ATTEMPTS: usize = 16;
+
+impl Workspace {
+    /// Creates a fresh directory only the current user can enter, under a name no one
+    /// else can predict, so nothing planted in the shared temp directory is reused.
+    pub fn create(keep: bool) -> io::Result<Self> {
+        let mut builder = fs::DirBuilder::new();
+        builder.mode(0o700);
+        for _ in 0..CREATE_ATTEMPTS {
+            let suffix = RandomState::new().build_hasher().finish();
+            let path = env::temp_dir().join(format!(
+                "linux_setup_ur-{}-{:016x}",
+                std::process::id(),
+                suffix
+            ));
+            match builder.create(&path) {
+                Ok(()) => return Ok(Workspace { path, keep }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "no unused temporary workspace name",
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// A subdirectory of the workspace reserved for one entry, private like the workspace.
+    pub fn entry_dir(&self, name: &str) -> io::Result<PathBuf> {
+        let slug: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let path = self.path.join(slug);
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&path)?;
+        Ok(path)
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        if self.keep {
+            Status::Normal
+                .print_message(&format!("Kept temporary workspace {}", self.path.display()));
+        } else {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_cleanup() {
+        let workspace = Workspace::create(false).unwrap();
+        let mode = fs::metadata(workspace.path()).unwrap().permissions();
+        assert_eq!(
+            std::os::unix::fs::PermissionsExt::mode(&mode) & 0o777,
+            0o700
+        );
+        assert_ne!(workspace.path(), Workspace::create(false).unwrap().path());
+        let entry = workspace.entry_dir("Install Rust").unwrap();
+        assert!(entry.ends_with("install-rust"));
+        let path = workspace.path().to_path_buf();
+        drop(workspace);
+        assert!(!path.exists());
+
+        let kept = Workspace::create(true).unwrap();
+        let path = kept.path().to_path_buf();
+        drop(kept);
+        assert!(path.exists());
+        fs::remove_dir_all(path).unwrap();
+    }
+}